@@ -123,6 +123,15 @@ pub(crate) trait Squarey: Ranked + Filed {
     fn get_offset(&self, other_square: Self) -> (i8, i8) {
         (self.rank_gap(&other_square), self.file_gap(&other_square))
     }
+    // The color of the square itself: a1 is dark, so it counts as black. 
+    #[inline(always)]
+    fn square_color(&self) -> EnumColor {
+        let (rank_offset, file_offset) = self.get_offset(Self::build_square(EnumRank::One, EnumFile::A));
+        match (rank_offset + file_offset) % 2 == 0 {
+            true => EnumColor::Black,
+            false => EnumColor::White,
+        }
+    }
     #[inline(always)]
     fn try_get_ray_to(&self, other_square: Self) -> Option<Ray<Self, (SmallOffset, SmallOffset)>> {
         let (rank_offset, file_offset) = self.get_offset(other_square);
@@ -175,6 +184,14 @@ pub(crate) trait Squarey: Ranked + Filed {
             }
         }
     }
+    // The squares strictly between the two, for blocking checks and for pins. Empty when 
+    // they don't share a line, and also when they're next to each other. 
+    fn squares_between(&self, other_square: Self) -> Vec<Self> {
+        match self.try_get_ray_to(other_square) {
+            None => Vec::new(),
+            Some(ray) => ray.take_while(|passed_square| *passed_square != other_square).collect(),
+        }
+    }
     #[inline(always)]
     fn try_get_ray_away(&self, other_square: Self) -> Option<Ray<Self, (SmallOffset, SmallOffset)>> {
         let (rank_offset, file_offset) = self.get_offset(other_square);
@@ -228,6 +245,17 @@ pub(crate) trait Squarey: Ranked + Filed {
         }
     }
 
+    // Where a pawn of attacking_color would have to stand to attack this square: one rank 
+    // back from its side, one file either way. None where that's off the board. 
+    #[inline(always)]
+    fn get_pawn_attacker_squares(&self, attacking_color: EnumColor) -> [Option<Self>; 2] {
+        let reverse_pawn_move_dir = match attacking_color {
+            EnumColor::White => SmallOffset::MinusOne,
+            EnumColor::Black => SmallOffset::PlusOne,
+        };
+        [SmallOffset::MinusOne, SmallOffset::PlusOne].map(|file_movement| self.try_get_offset_square(reverse_pawn_move_dir, file_movement))
+    }
+
     fn get_king_offset_squares(&self) -> Vec<Self> {
         let mut offset_squares = Vec::new();
         for offset in PROPER_KING_OFFSETS {
@@ -305,19 +333,56 @@ pub(crate) trait HasBoard: Sized + Copy {
     fn query_square(&self, square: Self::PositionRep) -> Self::ContentsRep;
     fn set_square(&mut self, square: Self::PositionRep, new_contents: Self::ContentsRep) -> ();
 
+    // Every square holding this exact piece, in CANONICAL_ARRAY order. 
+    fn find_pieces(&self, color: EnumColor, piece_type: EnumPiecesUncolored) -> Vec<Self::PositionRep> {
+        Self::CANONICAL_ARRAY.into_iter()
+            .filter(|square| match self.query_square(*square).get_contents() {
+                None => false,
+                Some(piece) => piece.get_color() == color && piece.get_piece_type() == piece_type,
+            })
+            .collect()
+    }
+
+    // Which pieces are on the board, ignoring where: four bits of count per piece, in slot 
+    // 2 * type + color (white first, P N B R Q K), so two positions share a key exactly when 
+    // they have the same material. Nothing legal can have 16 of one piece, so the counts 
+    // never spill into each other. 
+    fn material_key(&self) -> u64 {
+        Self::CANONICAL_ARRAY.into_iter()
+            .filter_map(|square| self.query_square(square).get_contents())
+            .map(|piece| 1u64 << (4 * (2 * piece.get_piece_type() as u64 + piece.get_color() as u64)))
+            .sum()
+    }
+
+    // How many of color's pawns attack the square. 
+    fn count_pawn_attackers(&self, color: EnumColor, square: Self::PositionRep) -> usize {
+        square.get_pawn_attacker_squares(color).into_iter()
+            .flatten()
+            .filter(|pawn_square| match self.query_square(*pawn_square).get_contents() {
+                None => false,
+                Some(piece) => piece.get_color() == color && piece.get_piece_type() == EnumPiecesUncolored::Pawn,
+            })
+            .count()
+    }
+    #[inline(always)]
+    fn pawn_defends(&self, color: EnumColor, square: Self::PositionRep) -> bool {
+        self.count_pawn_attackers(color, square) > 0
+    }
+
     // For doing things like detecting whether the king is in check. 
+    #[inline(always)]
     fn sees_obvious_attack(&self, defending_color: EnumColor, square: Self::PositionRep) -> bool {
+        self.sees_obvious_attack_ignoring(defending_color, square, &[])
+    }
+    // Same as above, but pretending the ignored squares are empty. Useful for x-rays, and for 
+    // en passant, where two pawns leave the same rank at once and can uncover a rook on it. 
+    fn sees_obvious_attack_ignoring(&self, defending_color: EnumColor, square: Self::PositionRep, ignored_squares: &[Self::PositionRep]) -> bool {
 
-        let reverse_opponent_pawn_move_dir = match defending_color {
-            EnumColor::White => SmallOffset::PlusOne,
-            EnumColor::Black => SmallOffset::MinusOne,
-        };
-
-        for file_movement in [SmallOffset::MinusOne, SmallOffset::PlusOne] {
-            match square.try_get_offset_square(reverse_opponent_pawn_move_dir, file_movement) {
+        for possible_pawn_square in square.get_pawn_attacker_squares(defending_color.get_opposite_color()) {
+            match possible_pawn_square {
                 None => {},
                 Some(possibly_attacking_square) => {
-                    match self.query_square(possibly_attacking_square).get_contents() {
+                    match self.query_square_ignoring(possibly_attacking_square, ignored_squares) {
                         None => {},
                         Some(piece) => {
                             if piece.get_color() != defending_color && match piece.get_piece_type() {
@@ -336,7 +401,7 @@ pub(crate) trait HasBoard: Sized + Copy {
         }
 
         for knight_square in square.get_knight_offset_squares() {
-            match self.query_square(knight_square).get_contents() {
+            match self.query_square_ignoring(knight_square, ignored_squares) {
                 None => {},
                 Some(piece) => {
                     if (piece.get_color() != defending_color) && (piece.get_piece_type() == EnumPiecesUncolored::Knight) {
@@ -349,7 +414,7 @@ pub(crate) trait HasBoard: Sized + Copy {
         for bishop_ray in square.get_bishop_rays() {
             let mut king_relevant = true;
             for possibly_attacking_square in bishop_ray {
-                match self.query_square(possibly_attacking_square).get_contents() {
+                match self.query_square_ignoring(possibly_attacking_square, ignored_squares) {
                     None => {},
                     Some(piece) => {
                         if piece.get_color() != defending_color && match piece.get_piece_type() {
@@ -370,7 +435,7 @@ pub(crate) trait HasBoard: Sized + Copy {
         for rook_ray in square.get_rook_rays() {
             let mut king_relevant = true;
             for possibly_attacking_square in rook_ray {
-                match self.query_square(possibly_attacking_square).get_contents() {
+                match self.query_square_ignoring(possibly_attacking_square, ignored_squares) {
                     None => {},
                     Some(piece) => {
                         if piece.get_color() != defending_color && match piece.get_piece_type() {
@@ -390,18 +455,27 @@ pub(crate) trait HasBoard: Sized + Copy {
 
         return false
     }
+    #[inline(always)]
+    fn query_square_ignoring(&self, square: Self::PositionRep, ignored_squares: &[Self::PositionRep]) -> Option<<Self::ContentsRep as Contentsy>::Content> {
+        match ignored_squares.contains(&square) {
+            true => None,
+            false => self.query_square(square).get_contents(),
+        }
+    }
     // For doing things like detecting *why* the king is in check. 
     fn get_obvious_attackers(&self, defending_color: EnumColor, square: Self::PositionRep) -> Vec<Self::MoveRep> {
 
         let mut attacking_moves = Vec::new();
 
-        let (opponent_pawn_move_dir, opponent_pawn_promotion_rank) = match defending_color {
-            EnumColor::White => (SmallOffset::MinusOne, EnumRank::One),
-            EnumColor::Black => (SmallOffset::PlusOne, EnumRank::Eight),
+        // Enemy pawns attack from the side of the board they're moving away from, same as 
+        // in sees_obvious_attack. 
+        let (reverse_opponent_pawn_move_dir, opponent_pawn_promotion_rank) = match defending_color {
+            EnumColor::White => (SmallOffset::PlusOne, EnumRank::One),
+            EnumColor::Black => (SmallOffset::MinusOne, EnumRank::Eight),
         };
 
         for file_movement in [SmallOffset::MinusOne, SmallOffset::PlusOne] {
-            match square.try_get_offset_square(opponent_pawn_move_dir, file_movement) {
+            match square.try_get_offset_square(reverse_opponent_pawn_move_dir, file_movement) {
                 None => {},
                 Some(possibly_attacking_square) => {
                     match self.query_square(possibly_attacking_square).get_contents() {
@@ -416,12 +490,7 @@ pub(crate) trait HasBoard: Sized + Copy {
                                                     from_square: possibly_attacking_square, 
                                                     to_square: square})))
                                 } else {
-                                    for promotion_option in [
-                                        EnumPiecesUncolored::Queen, 
-                                        EnumPiecesUncolored::Knight, 
-                                        EnumPiecesUncolored::Rook, 
-                                        EnumPiecesUncolored::Bishop
-                                        ] {
+                                    for promotion_option in PROMOTION_ORDER {
                                         attacking_moves.push(
                                             Self::MoveRep::build_move(
                                                 ChessMove::PromotionMove(
@@ -510,6 +579,25 @@ pub(crate) trait HasBoard: Sized + Copy {
 
         attacking_moves
     }
+    // Every piece of either color bearing on the square, for things like SEE where both the 
+    // attackers and the defenders matter. Each piece shows up once even when it could promote 
+    // four ways. 
+    fn attackers_of(&self, square: Self::PositionRep) -> Vec<(Self::PositionRep, EnumColor)> {
+        let mut attackers = Vec::new();
+        for attacking_color in [EnumColor::White, EnumColor::Black] {
+            for attacking_move in self.get_obvious_attackers(attacking_color.get_opposite_color(), square) {
+                let from_square = match attacking_move.get_move() {
+                    ChessMove::StandardMove(standard_move) => standard_move.from_square,
+                    ChessMove::PromotionMove(promotion_move) => promotion_move.from_square,
+                    _ => continue,
+                };
+                if !attackers.contains(&(from_square, attacking_color)) {
+                    attackers.push((from_square, attacking_color));
+                }
+            }
+        }
+        attackers
+    }
     
     // Freeze any extra FEN-type data (etc) and just update the position. If I 
     // implement custom NNUE code this would probably also affect the accumulators. 
@@ -562,20 +650,22 @@ pub(crate) trait HasBoard: Sized + Copy {
 // Set and get method: types implementing this have a ply counter. 
 // It should be logically equivalent to the provided one, as usual. 
 pub(crate) trait PlyCounting {
-    fn get_ply_count(&self) -> i8;
-    fn set_ply_count(&mut self, ply_count: i8) -> ();
+    fn get_ply_count(&self) -> i16;
+    fn set_ply_count(&mut self, ply_count: i16) -> ();
     #[inline(always)]
     fn reset_ply_counter(&mut self) {
         self.set_ply_count(0)
     }
+    // The counter is in plies, so the 50mr kicks in at 100 and the 75-move rule at 150. 
+    // An i16 has room for both; saturating just keeps absurd games from wrapping round. 
     #[inline(always)]
     fn increment_ply(&mut self) {
-        let new_count = self.get_ply_count().max(49) + 1;
+        let new_count = self.get_ply_count().saturating_add(1);
         self.set_ply_count(new_count)
     }
     #[inline(always)]
     fn time_up(&self) -> bool {
-        self.get_ply_count() == 50
+        self.get_ply_count() >= 100
     }
 }
 
@@ -628,56 +718,121 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
         }
         self.set_castling(self.get_opposite_color(), new_rules);
     }
+    // Whether a castling rule still has its king and rook where it says they start. Stale 
+    // rules like these were behind the old Kiwipete castling bugs. 
+    fn castling_rule_holds(&self, color: EnumColor, castling_rule: CastlingMove<Self::PositionRep>) -> bool {
+        let holds = |square: Self::PositionRep, piece_type: EnumPiecesUncolored| match self.query_square(square).get_contents() {
+            None => false,
+            Some(piece) => piece.get_color() == color && piece.get_piece_type() == piece_type,
+        };
+        holds(castling_rule.king_from, EnumPiecesUncolored::King) && holds(castling_rule.rook_from, EnumPiecesUncolored::Rook)
+    }
+    fn validate_castling_rights(&self) -> bool {
+        [EnumColor::White, EnumColor::Black].into_iter().all(|color| self.get_castling(color).into_iter()
+            .all(|castling_rule| match castling_rule {
+                None => true,
+                Some(castling_rule) => self.castling_rule_holds(color, castling_rule),
+            }))
+    }
+    // Throws out the rules that don't hold, keeping the rest. 
+    fn repair_castling_rights(&mut self) -> () {
+        for color in [EnumColor::White, EnumColor::Black] {
+            let mut new_rules = self.get_castling(color);
+            for i in [0, 1] {
+                match new_rules[i] {
+                    None => {},
+                    Some(castling_rule) => {
+                        if !self.castling_rule_holds(color, castling_rule) {
+                            new_rules[i] = None;
+                        }
+                    }
+                }
+            }
+            self.set_castling(color, new_rules);
+        }
+    }
     fn get_w_king_square(&self) -> Self::PositionRep;
     fn set_w_king_square(&mut self, square: Self::PositionRep) -> ();
     fn get_b_king_square(&self) -> Self::PositionRep;
     fn set_b_king_square(&mut self, square: Self::PositionRep) -> ();
+    // Either king, without everyone writing out the match on color. The squares are tracked 
+    // through make_move, so this is as cheap as the getters. 
+    #[inline(always)]
+    fn king_square_of(&self, color: EnumColor) -> Self::PositionRep {
+        match color {
+            EnumColor::White => self.get_w_king_square(),
+            EnumColor::Black => self.get_b_king_square(),
+        }
+    }
     fn try_get_ep_square(&self) -> Option<(Self::PositionRep, Self::PositionRep)>;
     fn set_ep_square(&mut self, value: Option<(Self::PositionRep, Self::PositionRep)>) -> ();
 
     #[inline(always)]
     fn mover_in_check(&self) -> bool {
-        let relevant_king_square = match self.get_color() {
-            EnumColor::White => self.get_w_king_square(),
-            EnumColor::Black => self.get_b_king_square(),
-        };
+        let relevant_king_square = self.king_square_of(self.get_color());
         self.sees_obvious_attack(self.get_color(), relevant_king_square)
     }
     #[inline(always)]
     fn non_mover_in_check(&self) -> bool {
-        let relevant_king_square = match self.get_color() {
-            EnumColor::White => self.get_b_king_square(),
-            EnumColor::Black => self.get_w_king_square(),
-        };
+        let relevant_king_square = self.king_square_of(self.get_opposite_color());
         // let debug_help = self.sees_obvious_attack(self.get_opposite_color(), relevant_king_square);
         self.sees_obvious_attack(self.get_opposite_color(), relevant_king_square)
     }
+    // Where the checks on the side to move come from. Empty out of check, two squares for a 
+    // double check. A pawn checking a king on the back rank shows up once per promotion in 
+    // get_obvious_attackers, hence the dedup. 
+    fn checkers(&self) -> Vec<Self::PositionRep> {
+        let relevant_king_square = self.king_square_of(self.get_color());
+        let mut checking_squares: Vec<Self::PositionRep> = Vec::new();
+        for attacking_move in self.get_obvious_attackers(self.get_color(), relevant_king_square) {
+            let from_square = match attacking_move.get_move() {
+                ChessMove::StandardMove(standard_move) => standard_move.from_square,
+                ChessMove::PromotionMove(promotion_move) => promotion_move.from_square,
+                _ => continue,
+            };
+            if !checking_squares.contains(&from_square) {
+                checking_squares.push(from_square);
+            }
+        }
+        checking_squares
+    }
+    // Only bothers looking for check once it knows there are no legal moves. 
+    #[inline(always)]
+    fn terminal_state(&self) -> Option<GameEnd> {
+        self.terminal_state_given(&self.get_legal_proper_moves())
+    }
+    // Same as above, for when the caller has already generated the legal moves 
+    // and would rather not do it all over again. 
+    #[inline(always)]
+    fn terminal_state_given(&self, legal_moves: &[Self::MoveRep]) -> Option<GameEnd> {
+        match legal_moves.len() == 0 {
+            false => None,
+            true => match self.mover_in_check() {
+                true => Some(GameEnd::Checkmate),
+                false => Some(GameEnd::Stalemate),
+            },
+        }
+    }
     #[inline(always)]
     fn is_stalemate(&self) -> bool {
-        self.get_legal_proper_moves().len() == 0 && !self.mover_in_check()
+        self.terminal_state() == Some(GameEnd::Stalemate)
     }
     #[inline(always)]
     fn is_checkmate(&self) -> bool {
-        self.get_legal_proper_moves().len() == 0 && self.mover_in_check()
+        self.terminal_state() == Some(GameEnd::Checkmate)
     }
 
     fn is_pinned(&self, square: Self::PositionRep) -> bool {
-        let king_square = match self.get_color() {
-            EnumColor::White => self.get_w_king_square(),
-            EnumColor::Black => self.get_b_king_square(),
-        };
+        let king_square = self.king_square_of(self.get_color());
         match king_square.try_get_ray_to(square) {
             None => false,
             Some(ray) => {
-                let ray_piece_type = match ray.direction.0 == SmallOffset::Stay || ray.direction.1 == SmallOffset::Stay {
-                    true => EnumPiecesUncolored::Rook,
-                    false => EnumPiecesUncolored::Bishop,
-                };
+                let ray_kind = ray.kind();
                 for threatening_square in ray {
                     match self.query_square(threatening_square).get_contents() {
                         None => {},
                         Some(piece) => {
-                            if piece.get_color() == self.get_opposite_color() && (piece.get_piece_type() == EnumPiecesUncolored::Queen || piece.get_piece_type() == ray_piece_type) {
+                            if piece.get_color() == self.get_opposite_color() && ray_kind.is_slid_by(piece.get_piece_type()) {
                                 return true
                             }
                             if threatening_square != square {
@@ -691,22 +846,16 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
         }
     }
     fn pinning_square(&self, square: Self::PositionRep) -> Option<Self::PositionRep> {
-        let king_square = match self.get_color() {
-            EnumColor::White => self.get_w_king_square(),
-            EnumColor::Black => self.get_b_king_square(),
-        };
+        let king_square = self.king_square_of(self.get_color());
         match king_square.try_get_ray_to(square) {
             None => None,
             Some(ray) => {
-                let ray_piece_type = match ray.direction.0 == SmallOffset::Stay || ray.direction.1 == SmallOffset::Stay {
-                    true => EnumPiecesUncolored::Rook,
-                    false => EnumPiecesUncolored::Bishop,
-                };
+                let ray_kind = ray.kind();
                 for threatening_square in ray {
                     match self.query_square(threatening_square).get_contents() {
                         None => {},
                         Some(piece) => {
-                            if piece.get_color() == self.get_opposite_color() && (piece.get_piece_type() == EnumPiecesUncolored::Queen || piece.get_piece_type() == ray_piece_type) {
+                            if piece.get_color() == self.get_opposite_color() && ray_kind.is_slid_by(piece.get_piece_type()) {
                                 return Some(threatening_square)
                             }
                             if threatening_square != square {
@@ -719,6 +868,31 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
             },
         }
     }
+    // Every pin on the side to move at once, where pinning_square answers for one square at 
+    // a time. Walks out from the king along each of the eight lines: a pin is one of our 
+    // pieces followed by an enemy slider that moves along that line, with nothing between. 
+    fn pins(&self) -> Vec<Pin<Self::PositionRep>> {
+        let king_square = self.king_square_of(self.get_color());
+        let mut pins = Vec::new();
+        for ray in king_square.get_queen_rays() {
+            let (direction, ray_kind) = (ray.direction, ray.kind());
+            let mut own_piece_square = None;
+            for passed_square in ray {
+                match self.query_square(passed_square).get_contents() {
+                    None => {},
+                    Some(piece) => match (own_piece_square, piece.get_color() == self.get_color()) {
+                        (None, true) => own_piece_square = Some(passed_square),
+                        (Some(pinned_square), false) if ray_kind.is_slid_by(piece.get_piece_type()) => {
+                            pins.push(Pin { pinned_square: pinned_square, pinning_square: passed_square, direction: direction });
+                            break
+                        },
+                        _ => break,
+                    },
+                }
+            }
+        }
+        pins
+    }
     fn check_remaining_legality(&self, possible_move: Self::MoveRep) -> bool {
         match possible_move.get_move() {
             ChessMove::CastlingMove(proposed_move) => {
@@ -742,6 +916,9 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                     }
                 }
             },
+            // En passant has to stay on this path even if the other moves get a faster pin-based 
+            // check one day: the capturing pawn can be pinned, and taking the victim off the board 
+            // can open a line to the king that no pin on our side shows. 
             _ => {return !self.after_move(possible_move).non_mover_in_check()},
         }
     }
@@ -762,9 +939,126 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
             _ => true,
         }
     }
+    // Whether something of the opponent's leaves the board.
+    fn is_capture(&self, possible_move: Self::MoveRep) -> bool {
+        match possible_move.get_move() {
+            ChessMove::StandardMove(unwrapped_move) => self.query_square(unwrapped_move.to_square).get_contents().is_some(),
+            ChessMove::PromotionMove(promotion_move) => self.query_square(promotion_move.to_square).get_contents().is_some(),
+            ChessMove::EnPassantMove(_) => true,
+            _ => false,
+        }
+    }
+    // The obviously correct version of gives_check, kept around to test the fast one against.
+    #[inline(always)]
+    fn gives_check_by_making(&self, possible_move: Self::MoveRep) -> bool {
+        self.after_move(possible_move).mover_in_check()
+    }
+    // Whether a legal move puts the opponent in check, worked out without making it. Either the
+    // moved piece attacks the enemy king from where it lands, or taking it (or an en passant
+    // victim) off a line to the king uncovers one of our sliders. Castling is rare enough that
+    // it just makes the move.
+    fn gives_check(&self, possible_move: Self::MoveRep) -> bool {
+        let enemy_king_square = self.king_square_of(self.get_opposite_color());
+        let (from_square, to_square, moved_piece_type, taken_square) = match possible_move.get_move() {
+            ChessMove::StandardMove(unwrapped_move) => match self.query_square(unwrapped_move.from_square).get_contents() {
+                None => return false,
+                Some(piece) => (unwrapped_move.from_square, unwrapped_move.to_square, piece.get_piece_type(), unwrapped_move.from_square),
+            },
+            ChessMove::PromotionMove(promotion_move) => (promotion_move.from_square, promotion_move.to_square, promotion_move.promotion_choice.get_piece_type(), promotion_move.from_square),
+            ChessMove::EnPassantMove(ep_move) => (ep_move.from_square, ep_move.to_square, EnumPiecesUncolored::Pawn, ep_move.taken_square),
+            ChessMove::CastlingMove(_) => return self.gives_check_by_making(possible_move),
+            ChessMove::NullMove => return false,
+        };
+        // Outside of en passant the "taken" square is just the from square again.
+        let vacated_squares = [from_square, taken_square];
+
+        let gives_direct_check = match moved_piece_type {
+            EnumPiecesUncolored::Pawn => {
+                let pawn_move_dir = match self.get_color() {
+                    EnumColor::White => SmallOffset::PlusOne,
+                    EnumColor::Black => SmallOffset::MinusOne,
+                };
+                to_square.try_get_offset_square(pawn_move_dir, SmallOffset::MinusOne) == Some(enemy_king_square)
+                    || to_square.try_get_offset_square(pawn_move_dir, SmallOffset::PlusOne) == Some(enemy_king_square)
+            },
+            EnumPiecesUncolored::Knight => to_square.get_knight_offset_squares().contains(&enemy_king_square),
+            EnumPiecesUncolored::King => false,
+            slider_type => match to_square.try_get_ray_to(enemy_king_square) {
+                None => false,
+                Some(ray) => {
+                    ray.kind().is_slid_by(slider_type)
+                        && ray.take_while(|passed_square| *passed_square != enemy_king_square)
+                            .all(|passed_square| self.query_square_ignoring(passed_square, &vacated_squares).is_none())
+                },
+            },
+        };
+        if gives_direct_check {
+            return true
+        }
 
+        // Discovered checks. Look out from the king through each vacated square; the moved
+        // piece landing back on the line blocks it again.
+        for vacated_square in vacated_squares {
+            match enemy_king_square.try_get_ray_to(vacated_square) {
+                None => {},
+                Some(ray) => {
+                    let ray_kind = ray.kind();
+                    for passed_square in ray {
+                        if passed_square == to_square {
+                            break
+                        }
+                        match self.query_square_ignoring(passed_square, &vacated_squares) {
+                            None => {},
+                            Some(piece) => {
+                                if piece.get_color() == self.get_color() && ray_kind.is_slid_by(piece.get_piece_type()) {
+                                    return true
+                                }
+                                break
+                            },
+                        }
+                    }
+                },
+            }
+        }
+        false
+    }
+    // Nothing taken, nothing promoted, no check given. These are the moves quiescence can
+    // leave alone and futility pruning is allowed to skip. En passant counts as a capture.
+    #[inline(always)]
+    fn is_quiet(&self, possible_move: Self::MoveRep) -> bool {
+        match possible_move.get_move() {
+            ChessMove::PromotionMove(_) | ChessMove::EnPassantMove(_) => false,
+            _ => !self.is_capture(possible_move) && !self.gives_check(possible_move),
+        }
+    }
+    // A position where standing pat is fair: not in check and nothing to take.
+    fn is_position_quiet(&self) -> bool {
+        !self.mover_in_check() && !self.get_legal_proper_moves().into_iter().any(|possible_move| self.is_capture(possible_move))
+    }
+    // The moves quiescence searches: captures and promotions, or every evasion when in check, 
+    // since standing pat isn't allowed then and a quiet king step may be the only way out. 
+    // Takes the full legal move list so that the caller can still spot mates and stalemates. 
+    fn retain_quiescence_moves(&self, legal_moves: &mut Vec<Self::MoveRep>) -> () {
+        if !self.mover_in_check() {
+            legal_moves.retain(|possible_move| match possible_move.get_move() {
+                ChessMove::PromotionMove(_) => true,
+                _ => self.is_capture(*possible_move),
+            });
+        }
+    }
+    fn get_quiescence_moves(&self) -> Vec<Self::MoveRep> {
+        let mut quiescence_moves = self.get_legal_proper_moves();
+        self.retain_quiescence_moves(&mut quiescence_moves);
+        quiescence_moves
+    }
+
+    #[inline(always)]
     fn get_likely_pawn_moves(&self, square: Self::PositionRep) -> Vec<Self::MoveRep> {
         let mut pawn_moves = Vec::new();
+        self.push_likely_pawn_moves(square, &mut pawn_moves);
+        pawn_moves
+    }
+    fn push_likely_pawn_moves(&self, square: Self::PositionRep, pawn_moves: &mut Vec<Self::MoveRep>) -> () {
 
         let (double_move_rank, promotion_rank, pawn_move_dir) = match self.get_color() {
             EnumColor::White => (EnumRank::Two, EnumRank::Eight, SmallOffset::PlusOne),
@@ -781,12 +1075,7 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                         Some(attacked_piece) => {
                             if attacked_piece.get_color() == self.get_opposite_color() {
                                 if attacked_square.get_rank() == promotion_rank {
-                                    for promotion_option in [
-                                        EnumPiecesUncolored::Queen,
-                                        EnumPiecesUncolored::Knight,
-                                        EnumPiecesUncolored::Rook,
-                                        EnumPiecesUncolored::Bishop,
-                                    ] {
+                                    for promotion_option in PROMOTION_ORDER {
                                         pawn_moves.push(
                                             Self::MoveRep::build_move(
                                                 ChessMove::PromotionMove(
@@ -819,12 +1108,7 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 match self.query_square(forward_square).get_contents() {
                     None => {
                         if forward_square.get_rank() == promotion_rank {
-                            for promotion_option in [
-                                        EnumPiecesUncolored::Queen,
-                                        EnumPiecesUncolored::Knight,
-                                        EnumPiecesUncolored::Rook,
-                                        EnumPiecesUncolored::Bishop,
-                                    ] {
+                            for promotion_option in PROMOTION_ORDER {
                                         pawn_moves.push(
                                             Self::MoveRep::build_move(
                                                 ChessMove::PromotionMove(
@@ -866,11 +1150,14 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 }
             }
         }
-
-        pawn_moves
     }
+    #[inline(always)]
     fn get_likely_knight_moves(&self, square: Self::PositionRep) -> Vec<Self::MoveRep> {
         let mut knight_moves = Vec::new();
+        self.push_likely_knight_moves(square, &mut knight_moves);
+        knight_moves
+    }
+    fn push_likely_knight_moves(&self, square: Self::PositionRep, knight_moves: &mut Vec<Self::MoveRep>) -> () {
 
         for to_square in square.get_knight_offset_squares() {
             match self.query_square(to_square).get_contents() {
@@ -894,11 +1181,14 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 }
             }
         }
-
-        knight_moves
     }
+    #[inline(always)]
     fn get_likely_bishop_moves(&self, square: Self::PositionRep) -> Vec<Self::MoveRep> {
         let mut bishop_moves = Vec::new();
+        self.push_likely_bishop_moves(square, &mut bishop_moves);
+        bishop_moves
+    }
+    fn push_likely_bishop_moves(&self, square: Self::PositionRep, bishop_moves: &mut Vec<Self::MoveRep>) -> () {
 
         for ray in square.get_bishop_rays() {
             for attacked_square in ray {
@@ -925,11 +1215,14 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 }
             }
         }
-
-        bishop_moves
     }
+    #[inline(always)]
     fn get_likely_rook_moves(&self, square: Self::PositionRep) -> Vec<Self::MoveRep> {
         let mut rook_moves = Vec::new();
+        self.push_likely_rook_moves(square, &mut rook_moves);
+        rook_moves
+    }
+    fn push_likely_rook_moves(&self, square: Self::PositionRep, rook_moves: &mut Vec<Self::MoveRep>) -> () {
 
         for ray in square.get_rook_rays() {
             for attacked_square in ray {
@@ -956,11 +1249,14 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 }
             }
         }
-
-        rook_moves
     }
+    #[inline(always)]
     fn get_likely_queen_moves(&self, square: Self::PositionRep) -> Vec<Self::MoveRep> {
         let mut queen_moves = Vec::new();
+        self.push_likely_queen_moves(square, &mut queen_moves);
+        queen_moves
+    }
+    fn push_likely_queen_moves(&self, square: Self::PositionRep, queen_moves: &mut Vec<Self::MoveRep>) -> () {
 
         for ray in square.get_queen_rays() {
             for attacked_square in ray {
@@ -987,11 +1283,14 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 }
             }
         }
-
-        queen_moves
     }
+    #[inline(always)]
     fn get_likely_king_moves(&self, square: Self::PositionRep) -> Vec<Self::MoveRep> {
         let mut king_moves = Vec::new();
+        self.push_likely_king_moves(square, &mut king_moves);
+        king_moves
+    }
+    fn push_likely_king_moves(&self, square: Self::PositionRep, king_moves: &mut Vec<Self::MoveRep>) -> () {
 
         for to_square in square.get_king_offset_squares() {
             match self.query_square(to_square).get_contents() {
@@ -1015,15 +1314,17 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 }
             }
         }
-
-        king_moves
     }
+    #[inline(always)]
     fn get_likely_ep_moves(&self) -> Vec<Self::MoveRep> {
+        let mut ep_captures = Vec::new();
+        self.push_likely_ep_moves(&mut ep_captures);
+        ep_captures
+    }
+    fn push_likely_ep_moves(&self, ep_captures: &mut Vec<Self::MoveRep>) -> () {
         match self.try_get_ep_square() {
-            None => Vec::new(),
+            None => {},
             Some((taken_square, ep_square)) => {
-                let mut ep_captures = Vec::new();
-
                 let back_capturing_dir = match self.get_color() {
                     EnumColor::White => SmallOffset::MinusOne,
                     EnumColor::Black => SmallOffset::PlusOne,
@@ -1050,13 +1351,16 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                         },
                     }
                 }
-
-                ep_captures
             },
         }
     }
+    #[inline(always)]
     fn get_likely_castling_moves(&self) -> Vec<Self::MoveRep> {
         let mut castling_moves = Vec::new();
+        self.push_likely_castling_moves(&mut castling_moves);
+        castling_moves
+    }
+    fn push_likely_castling_moves(&self, castling_moves: &mut Vec<Self::MoveRep>) -> () {
         for possible_castling_move in self.get_castling(self.get_color()) {
             match possible_castling_move {
                 None => {},
@@ -1079,10 +1383,17 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 },
             }
         }
-        castling_moves
     }
+    #[inline(always)]
     fn get_pseudo_legal_proper_moves(&self) -> Vec<Self::MoveRep> {
         let mut probable_moves = Vec::new();
+        self.push_pseudo_legal_proper_moves(&mut probable_moves);
+        probable_moves
+    }
+    // The non-allocating versions of movegen append to whatever buffer they're handed, 
+    // so a search can hang on to one buffer per ply instead of making fresh Vecs at 
+    // every node. 
+    fn push_pseudo_legal_proper_moves(&self, probable_moves: &mut Vec<Self::MoveRep>) -> () {
         for square in Self::CANONICAL_ARRAY {
             match self.query_square(square).get_contents() {
                 None => {},
@@ -1090,42 +1401,117 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                     if piece.get_color() == self.get_color() {
                         match piece.get_piece_type() {
                             EnumPiecesUncolored::Pawn => {
-                                probable_moves.append(&mut self.get_likely_pawn_moves(square))
+                                self.push_likely_pawn_moves(square, probable_moves)
                             },
                             EnumPiecesUncolored::Knight => {
-                                probable_moves.append(&mut self.get_likely_knight_moves(square))
+                                self.push_likely_knight_moves(square, probable_moves)
                             },
                             EnumPiecesUncolored::Bishop => {
-                                probable_moves.append(&mut self.get_likely_bishop_moves(square))
+                                self.push_likely_bishop_moves(square, probable_moves)
                             },
                             EnumPiecesUncolored::Rook => {
-                                probable_moves.append(&mut self.get_likely_rook_moves(square))
+                                self.push_likely_rook_moves(square, probable_moves)
                             },
                             EnumPiecesUncolored::Queen => {
-                                probable_moves.append(&mut self.get_likely_queen_moves(square))
+                                self.push_likely_queen_moves(square, probable_moves)
                             },
                             EnumPiecesUncolored::King => {
-                                probable_moves.append(&mut self.get_likely_king_moves(square))
+                                self.push_likely_king_moves(square, probable_moves)
                             }
                         }
                     }
                 },
             }
         }
-        probable_moves.append(&mut self.get_likely_castling_moves());
-        probable_moves.append(&mut self.get_likely_ep_moves());
-        probable_moves
+        self.push_likely_castling_moves(probable_moves);
+        self.push_likely_ep_moves(probable_moves);
+    }
+    // The pseudo-legal moves of just one type of piece, for the side to move. En passant 
+    // counts as a pawn move and castling as a king move. 
+    fn get_moves_for_piece_type(&self, piece_type: EnumPiecesUncolored) -> Vec<Self::MoveRep> {
+        let mut piece_moves = Vec::new();
+        for square in self.find_pieces(self.get_color(), piece_type) {
+            match piece_type {
+                EnumPiecesUncolored::Pawn => self.push_likely_pawn_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::Knight => self.push_likely_knight_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::Bishop => self.push_likely_bishop_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::Rook => self.push_likely_rook_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::Queen => self.push_likely_queen_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::King => self.push_likely_king_moves(square, &mut piece_moves),
+            }
+        }
+        match piece_type {
+            EnumPiecesUncolored::Pawn => self.push_likely_ep_moves(&mut piece_moves),
+            EnumPiecesUncolored::King => self.push_likely_castling_moves(&mut piece_moves),
+            _ => {},
+        }
+        piece_moves
     }
     
     #[inline(always)]
     fn get_legal_proper_moves(&self) -> Vec<Self::MoveRep> {
         let mut legal_moves = Vec::new();
-        for possible_move in self.get_pseudo_legal_proper_moves() {
+        self.write_legal_proper_moves(&mut legal_moves);
+        legal_moves
+    }
+    // Clears the buffer first, unlike the push_* methods. 
+    #[inline(always)]
+    fn write_legal_proper_moves(&self, legal_moves: &mut Vec<Self::MoveRep>) -> () {
+        legal_moves.clear();
+        self.get_legal_proper_moves_into(legal_moves);
+    }
+    // Appends just the legal moves, leaving anything already in the buffer alone. The 
+    // pseudo-legal moves go straight into the buffer and the illegal ones get squeezed out 
+    // in place, so there's never a second Vec. 
+    fn get_legal_proper_moves_into(&self, legal_moves: &mut Vec<Self::MoveRep>) -> () {
+        let first_new_index = legal_moves.len();
+        self.push_pseudo_legal_proper_moves(legal_moves);
+        let mut kept_until = first_new_index;
+        for move_index in first_new_index..legal_moves.len() {
+            let possible_move = legal_moves[move_index];
             if self.check_remaining_legality(possible_move) {
-                legal_moves.push(possible_move)
+                legal_moves[kept_until] = possible_move;
+                kept_until += 1;
             }
         }
-        legal_moves
+        legal_moves.truncate(kept_until);
+    }
+
+    // The legal moves of whatever stands on the square, for a GUI to highlight when a piece 
+    // gets picked up. Castling counts as a move of the king and en passant as one of the 
+    // capturing pawn. Nothing for an empty square or an enemy piece. 
+    fn legal_moves_from(&self, square: Self::PositionRep) -> Vec<Self::MoveRep> {
+        let mut piece_moves = Vec::new();
+        match self.query_square(square).get_contents() {
+            Some(piece) if piece.get_color() == self.get_color() => match piece.get_piece_type() {
+                EnumPiecesUncolored::Pawn => {
+                    self.push_likely_pawn_moves(square, &mut piece_moves);
+                    self.push_likely_ep_moves(&mut piece_moves);
+                },
+                EnumPiecesUncolored::Knight => self.push_likely_knight_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::Bishop => self.push_likely_bishop_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::Rook => self.push_likely_rook_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::Queen => self.push_likely_queen_moves(square, &mut piece_moves),
+                EnumPiecesUncolored::King => {
+                    self.push_likely_king_moves(square, &mut piece_moves);
+                    self.push_likely_castling_moves(&mut piece_moves);
+                },
+            },
+            _ => return piece_moves,
+        }
+        // The ep and castling moves get generated for the whole side at once, so they might 
+        // belong to another pawn. 
+        piece_moves.retain(|possible_move| {
+            let starts_on_square = match possible_move.get_move() {
+                ChessMove::StandardMove(standard_move) => standard_move.from_square == square,
+                ChessMove::PromotionMove(promotion_move) => promotion_move.from_square == square,
+                ChessMove::EnPassantMove(ep_move) => ep_move.from_square == square,
+                ChessMove::CastlingMove(castling_move) => castling_move.king_from == square,
+                ChessMove::NullMove => false,
+            };
+            starts_on_square && self.check_remaining_legality(*possible_move)
+        });
+        piece_moves
     }
 
     #[inline(always)]
@@ -1143,6 +1529,65 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
         self.set_ep_square(None);
         self.set_color(self.get_opposite_color());
     }
+    // do_null_move, but handing back what it clobbers so that unmake_null_move can put it 
+    // back. Saves the search copying the whole position just to try a null move. 
+    #[inline(always)]
+    fn make_null_move(&mut self) -> NullMoveUndo<Self::PositionRep> {
+        let undo_info = NullMoveUndo {
+            ep_square: self.try_get_ep_square(),
+            ply_count: self.get_ply_count(),
+        };
+        self.do_null_move();
+        undo_info
+    }
+    #[inline(always)]
+    fn unmake_null_move(&mut self, undo_info: NullMoveUndo<Self::PositionRep>) -> () {
+        self.set_color(self.get_opposite_color());
+        if self.get_color() == EnumColor::Black {
+            self.set_move_count(self.get_move_count() - 1)
+        }
+        self.set_ep_square(undo_info.ep_square);
+        self.set_ply_count(undo_info.ply_count);
+    }
+    // make_move, but handing back everything unmake_move needs to take it back again, which 
+    // lets the search walk the tree in one position instead of copying it at every node. 
+    #[inline(always)]
+    fn make_move_with_undo(&mut self, possible_move: Self::MoveRep) -> MoveUndo<Self::PositionRep, Self::ContentsRep> {
+        let (squares, touched_count) = match possible_move.get_move() {
+            ChessMove::NullMove => ([self.get_w_king_square(); 4], 0),
+            ChessMove::StandardMove(standard_move) => ([standard_move.from_square, standard_move.to_square, standard_move.to_square, standard_move.to_square], 2),
+            ChessMove::PromotionMove(promotion_move) => ([promotion_move.from_square, promotion_move.to_square, promotion_move.to_square, promotion_move.to_square], 2),
+            ChessMove::EnPassantMove(ep_move) => ([ep_move.from_square, ep_move.taken_square, ep_move.to_square, ep_move.to_square], 3),
+            ChessMove::CastlingMove(castling_move) => ([castling_move.king_from, castling_move.rook_from, castling_move.king_to, castling_move.rook_to], 4),
+        };
+        let undo_info = MoveUndo {
+            touched_squares: squares.map(|square| (square, self.query_square(square))),
+            touched_count: touched_count,
+            castling: [self.get_castling(EnumColor::White), self.get_castling(EnumColor::Black)],
+            ep_square: self.try_get_ep_square(),
+            ply_count: self.get_ply_count(),
+            w_king_square: self.get_w_king_square(),
+            b_king_square: self.get_b_king_square(),
+        };
+        self.make_move(possible_move);
+        undo_info
+    }
+    #[inline(always)]
+    fn unmake_move(&mut self, undo_info: MoveUndo<Self::PositionRep, Self::ContentsRep>) -> () {
+        self.set_color(self.get_opposite_color());
+        if self.get_color() == EnumColor::Black {
+            self.set_move_count(self.get_move_count() - 1)
+        }
+        for (square, contents) in undo_info.touched_squares[..undo_info.touched_count].iter() {
+            self.set_square(*square, *contents);
+        }
+        self.set_castling(EnumColor::White, undo_info.castling[0]);
+        self.set_castling(EnumColor::Black, undo_info.castling[1]);
+        self.set_ep_square(undo_info.ep_square);
+        self.set_ply_count(undo_info.ply_count);
+        self.set_w_king_square(undo_info.w_king_square);
+        self.set_b_king_square(undo_info.b_king_square);
+    }
     #[inline(always)]
     fn after_move(&self, possible_move: Self::MoveRep) -> Self {
         let mut position_after = *self;
@@ -1183,6 +1628,8 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 self.set_castling(self.get_color(), [None, None]);
                 update_king_square = Some(castling_move.king_to);
             },
+            // A pawn was never a king or a rook, so the mover's own rights can't change. Taking 
+            // a rook that hasn't moved yet still costs the opponent that side, though. 
             ChessMove::PromotionMove(promotion_move) => {
                 self.set_ep_square(None);
                 self.remove_enemy_castling(promotion_move.to_square);
@@ -1199,6 +1646,9 @@ pub(crate) trait FENnec: HasBoard + Colored + PlyCounting + MoveCounting + Sized
                 }
             },
         }
+        if self.get_color() == EnumColor::Black {
+            self.increment_move()
+        }
         self.set_color(self.get_opposite_color());
     }
 }
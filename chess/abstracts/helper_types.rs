@@ -32,6 +32,33 @@ pub(crate) enum EnumPiecesUncolored {
     King,
 }
 
+// The order promotions get generated in, best first: the queen is nearly always right, and 
+// the knight is the only underpromotion that's regularly useful (for the fork or the check). 
+// Rook and bishop promotions only ever matter for dodging stalemate. 
+pub(crate) const PROMOTION_ORDER: [EnumPiecesUncolored; 4] = [
+    EnumPiecesUncolored::Queen,
+    EnumPiecesUncolored::Knight,
+    EnumPiecesUncolored::Rook,
+    EnumPiecesUncolored::Bishop,
+];
+
+impl EnumPiecesUncolored {
+    // The one set of plain piece values, for anything that wants a single number per piece 
+    // (MVV-LVA, SEE, the naive eval). The tapered eval has its own tuned values. The king 
+    // only needs to outweigh everything else when used as an attacker. 
+    #[inline(always)]
+    pub(crate) const fn centipawn_value(&self) -> i16 {
+        match self {
+            EnumPiecesUncolored::Pawn => 100,
+            EnumPiecesUncolored::Knight => 300,
+            EnumPiecesUncolored::Bishop => 310,
+            EnumPiecesUncolored::Rook => 500,
+            EnumPiecesUncolored::Queen => 900,
+            EnumPiecesUncolored::King => 1900,
+        }
+    }
+}
+
 // The ranks on a chess board. I know I could use i8s or somthing here. 
 // I'm trusting Rust to make the smart structural decisions that I refuse 
 // to directly have anything to do with in this module. 
@@ -79,6 +106,45 @@ pub(crate) struct Ray<Pos, Dir> {
     pub(crate) direction: Dir,
 }
 
+// Which sort of slider a direction belongs to. Standing still and anything with a two in it 
+// (knight jumps) aren't lines at all. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RayKind {
+    Orthogonal,
+    Diagonal,
+    Invalid,
+}
+
+impl RayKind {
+    pub(crate) fn of_direction(direction: (SmallOffset, SmallOffset)) -> Self {
+        let is_step = |offset| matches!(offset, SmallOffset::MinusOne | SmallOffset::PlusOne);
+        match (direction.0, direction.1) {
+            (SmallOffset::Stay, other) | (other, SmallOffset::Stay) if is_step(other) => RayKind::Orthogonal,
+            (rank_offset, file_offset) if is_step(rank_offset) && is_step(file_offset) => RayKind::Diagonal,
+            _ => RayKind::Invalid,
+        }
+    }
+
+    // Whether a piece of this type slides along rays of this kind. 
+    #[inline(always)]
+    pub(crate) fn is_slid_by(&self, piece_type: EnumPiecesUncolored) -> bool {
+        match (self, piece_type) {
+            (RayKind::Invalid, _) => false,
+            (_, EnumPiecesUncolored::Queen) => true,
+            (RayKind::Orthogonal, EnumPiecesUncolored::Rook) => true,
+            (RayKind::Diagonal, EnumPiecesUncolored::Bishop) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<Pos> Ray<Pos, (SmallOffset, SmallOffset)> {
+    #[inline(always)]
+    pub(crate) fn kind(&self) -> RayKind {
+        RayKind::of_direction(self.direction)
+    }
+}
+
 // Building up to the match-statement-amenable representation of chess moves. 
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct StandardMove<PositionRep: Squarey> {
@@ -135,3 +201,43 @@ pub(crate) enum ChessMove<PositionRep: Squarey, PieceRep: Piecey> {
     PromotionMove(PromotionMove<PositionRep, PieceRep>),
     NullMove,
 }
+
+// The ways a game can end on the board itself, i.e. when the side to move has 
+// no legal moves. The 50mr and repetitions are the search's business. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum GameEnd {
+    Checkmate,
+    Stalemate,
+}
+
+// A piece that can't leave the line between its king and an enemy slider. The direction 
+// points from the king outward, through the pinned piece to the pinner. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Pin<PositionRep: Squarey> {
+    pub(crate) pinned_square: PositionRep,
+    pub(crate) pinning_square: PositionRep,
+    pub(crate) direction: (SmallOffset, SmallOffset),
+}
+
+// What a null move throws away, so that it can be taken back. The side to move and the 
+// fullmove counter can be worked out again afterwards, but these can't. 
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct NullMoveUndo<PositionRep: Squarey> {
+    pub(crate) ep_square: Option<(PositionRep, PositionRep)>,
+    pub(crate) ply_count: i16,
+}
+
+// The same for a real move. Only the squares the move touches get saved, along with what was 
+// on them beforehand; castling needs all four, everything else fewer. Squares can repeat (a 
+// 960 king that castles onto its own rook square), which is fine since they all get put back 
+// to what they held before the move either way. 
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MoveUndo<PositionRep: Squarey, ContentsRep: Contentsy> {
+    pub(crate) touched_squares: [(PositionRep, ContentsRep); 4],
+    pub(crate) touched_count: usize,
+    pub(crate) castling: [[Option<CastlingMove<PositionRep>>; 2]; 2],
+    pub(crate) ep_square: Option<(PositionRep, PositionRep)>,
+    pub(crate) ply_count: i16,
+    pub(crate) w_king_square: PositionRep,
+    pub(crate) b_king_square: PositionRep,
+}
@@ -7,3 +7,5 @@
 use super::abstracts::helper_types::*;
 
 pub(crate) mod impls_vzero;
+
+pub(crate) mod impls_v_one;
@@ -0,0 +1,11 @@
+// The second implementation of the chess traits, this time on bitboards: a u64 for each 
+// piece of each color, plus one per color for everything it has on the board. So far only 
+// the board is new and the movegen is still FENnec's default, so don't expect it to be any 
+// faster yet. It gets checked against impls_vzero with perft, which is what makes it safe 
+// to start moving the movegen over to bitboards one piece at a time. 
+
+pub(crate) mod board_rep;
+
+pub(crate) mod movegen;
+
+pub(crate) mod magic_code;
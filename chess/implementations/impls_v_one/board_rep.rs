@@ -1,21 +1,229 @@
+// Time to write bitboard code I guess 
 
-// Time to write bitboard code I guess
+// Squares and pieces are the same as in impls_vzero: a1 is 0 and h8 is 63, and a piece is 
+// 2 * type + color with -1 for an empty square. That way the i8 impls of Colored, Piecey, 
+// Contentsy, Ranked, Filed and Squarey over there get reused as they are, and the piece 
+// bitboards can be indexed by the piece encoding directly. 
+// Note to self: maybe there's an indexed_by trait I could use to replace "canonical array"? 
 
-// Colored for ___
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use crate::chess::implementations::impls_vzero::{board_rep::UnwrappedFen, io_code::FenParseError};
 
-// Piecey for ___
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct BitboardPosition {
+    // One bitboard per piece encoding, so white pawns, black pawns, white knights and so on. 
+    pub(super) pieces: [u64; 12],
+    // Everything white has, then everything black has. 
+    pub(super) occupancy: [u64; 2],
+    pub(super) moving_side: EnumColor,
+    pub(super) ply_count: i16,
+    pub(super) move_count: i16,
+    // The rest is stored exactly like UnwrappedFen stores it. 
+    pub(super) raw_castling_data: [Option<(i8, i8, i8, i8)>; 4],
+    pub(super) ep_data: i8,
+    pub(super) w_king_square: i8,
+    pub(super) b_king_square: i8,
+}
 
-// Contentsy for ___
+#[inline(always)]
+const fn square_bit(square: i8) -> u64 {
+    1u64 << square
+}
 
-// Ranked for ___
+// HasBoard for BitboardPosition. 
+impl HasBoard for BitboardPosition {
+    type PositionRep = i8;
+    type ContentsRep = i8;
+    type MoveRep = ChessMove<i8, i8>;
 
-// Filed for ___
+    const CANONICAL_ARRAY: [Self::PositionRep; 64] = <[i8; 64] as HasBoard>::CANONICAL_ARRAY;
 
-// Squarey for ___
+    // The occupancy says whose piece it is, and then there are only six boards left to ask. 
+    #[inline(always)]
+    fn query_square(&self, square: Self::PositionRep) -> Self::ContentsRep {
+        let bit = square_bit(square);
+        let color_index = match (self.occupancy[0] & bit != 0, self.occupancy[1] & bit != 0) {
+            (true, _) => 0,
+            (false, true) => 1,
+            (false, false) => return -1,
+        };
+        for piece_number in 0..6 {
+            let contents = 2 * piece_number + color_index;
+            if self.pieces[contents] & bit != 0 {
+                return contents as i8
+            }
+        }
+        // Only reachable if the occupancy has drifted from the piece boards. 
+        -1
+    }
+    #[inline(always)]
+    fn set_square(&mut self, square: Self::PositionRep, new_contents: Self::ContentsRep) -> () {
+        let bit = square_bit(square);
+        let old_contents = self.query_square(square);
+        if old_contents >= 0 {
+            self.pieces[old_contents as usize] &= !bit;
+            self.occupancy[old_contents as usize % 2] &= !bit;
+        }
+        if new_contents >= 0 {
+            self.pieces[new_contents as usize] |= bit;
+            self.occupancy[new_contents as usize % 2] |= bit;
+        }
+    }
 
-// HasBoard for ___
-// Note to self: maybe there's an indexed_by trait I could use to replace "canonical array"?
+    // Straight off the piece's bitboard. Lowest bit first is CANONICAL_ARRAY order, same as 
+    // the default. 
+    fn find_pieces(&self, color: EnumColor, piece_type: EnumPiecesUncolored) -> Vec<Self::PositionRep> {
+        let mut remaining = self.pieces[i8::build_piece(color, piece_type) as usize];
+        let mut squares = Vec::with_capacity(remaining.count_ones() as usize);
+        while remaining != 0 {
+            squares.push(remaining.trailing_zeros() as i8);
+            remaining &= remaining - 1;
+        }
+        squares
+    }
+}
+impl Colored for BitboardPosition {
+    #[inline(always)]
+    fn get_color(&self) -> EnumColor {
+        self.moving_side
+    }
+    #[inline(always)]
+    fn set_color(&mut self, color: EnumColor) -> () {
+        self.moving_side = color;
+    }
+}
+impl PlyCounting for BitboardPosition {
+    #[inline(always)]
+    fn get_ply_count(&self) -> i16 {
+        self.ply_count.get_ply_count()
+    }
+    #[inline(always)]
+    fn set_ply_count(&mut self, ply_count: i16) -> () {
+        self.ply_count.set_ply_count(ply_count)
+    }
+}
+impl MoveCounting for BitboardPosition {
+    #[inline(always)]
+    fn get_move_count(&self) -> i16 {
+        self.move_count.get_move_count()
+    }
+    #[inline(always)]
+    fn set_move_count(&mut self, move_count: i16) -> () {
+        self.move_count.set_move_count(move_count)
+    }
+}
+impl FENnec for BitboardPosition {
+    #[inline(always)]
+    fn get_castling(&self, color: EnumColor) -> [Option<CastlingMove<Self::PositionRep>>; 2] {
+        let offset = match color {
+            EnumColor::White => 0,
+            EnumColor::Black => 2,
+        };
+        [0, 1].map(|i| self.raw_castling_data[i + offset].map(|(king_from_square, rook_from_square, king_to_square, rook_to_square)| {
+            CastlingMove {king_from: king_from_square, rook_from: rook_from_square, king_to: king_to_square, rook_to: rook_to_square}
+        }))
+    }
+    #[inline(always)]
+    fn set_castling(&mut self, color: EnumColor, new_rules: [Option<CastlingMove<Self::PositionRep>>; 2]) -> () {
+        let offset = match color {
+            EnumColor::White => 0,
+            EnumColor::Black => 2,
+        };
+        for i in [0, 1] {
+            self.raw_castling_data[i + offset] = new_rules[i].map(|castling_rule| (castling_rule.king_from, castling_rule.rook_from, castling_rule.king_to, castling_rule.rook_to));
+        }
+    }
+    #[inline(always)]
+    fn get_w_king_square(&self) -> Self::PositionRep {
+        self.w_king_square
+    }
+    #[inline(always)]
+    fn set_w_king_square(&mut self, square: Self::PositionRep) -> () {
+        self.w_king_square = square;
+    }
+    #[inline(always)]
+    fn get_b_king_square(&self) -> Self::PositionRep {
+        self.b_king_square
+    }
+    #[inline(always)]
+    fn set_b_king_square(&mut self, square: Self::PositionRep) -> () {
+        self.b_king_square = square;
+    }
+    #[inline(always)]
+    fn try_get_ep_square(&self) -> Option<(Self::PositionRep, Self::PositionRep)> {
+        let ep_to_taken = match self.get_color() {
+            EnumColor::White => -8,
+            EnumColor::Black => 8,
+        };
+        match self.ep_data < 0 {
+            true => None,
+            false => Some((self.ep_data + ep_to_taken, self.ep_data))
+        }
+    }
+    #[inline(always)]
+    fn set_ep_square(&mut self, value: Option<(Self::PositionRep, Self::PositionRep)>) -> () {
+        match value {
+            None => self.ep_data = -1,
+            Some((_ep_taken, ep_square)) => self.ep_data = ep_square,
+        }
+    }
+}
 
-// PlyCounting for ___
+// There's no FEN parser of its own yet, so positions come in through UnwrappedFen. 
+impl BitboardPosition {
+    // Copies across everything FENnec can see, so the two should be indistinguishable 
+    // through the traits afterwards. 
+    pub(crate) fn from_unwrapped(position: &UnwrappedFen) -> Self {
+        let mut converted = BitboardPosition {
+            pieces: [0; 12],
+            occupancy: [0; 2],
+            moving_side: position.get_color(),
+            ply_count: position.get_ply_count(),
+            move_count: position.get_move_count(),
+            raw_castling_data: [None; 4],
+            ep_data: -1,
+            w_king_square: position.get_w_king_square(),
+            b_king_square: position.get_b_king_square(),
+        };
+        for square in Self::CANONICAL_ARRAY {
+            converted.set_square(square, position.query_square(square));
+        }
+        for color in [EnumColor::White, EnumColor::Black] {
+            converted.set_castling(color, position.get_castling(color));
+        }
+        converted.set_ep_square(position.try_get_ep_square());
+        converted
+    }
 
-// MoveCounting for ___
\ No newline at end of file
+    #[inline(always)]
+    pub(crate) fn startpos() -> Self {
+        Self::from_unwrapped(&UnwrappedFen::startpos())
+    }
+
+    #[inline(always)]
+    pub(crate) fn from_fen(fen_str: &str) -> Result<Self, FenParseError> {
+        UnwrappedFen::from_fen(fen_str).map(|position| Self::from_unwrapped(&position))
+    }
+
+    // Whether the occupancy is exactly the union of each color's piece boards, with no square 
+    // claimed by two pieces. Everything goes through set_square, so this should never fail. 
+    pub(crate) fn bitboards_agree(&self) -> bool {
+        let mut seen = 0u64;
+        let mut by_color = [0u64; 2];
+        for (contents, bitboard) in self.pieces.iter().enumerate() {
+            if seen & bitboard != 0 {
+                return false
+            }
+            seen |= bitboard;
+            by_color[contents % 2] |= bitboard;
+        }
+        by_color == self.occupancy
+    }
+}
+
+impl Default for BitboardPosition {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::startpos()
+    }
+}
@@ -0,0 +1,192 @@
+// Magic bitboards for rook and bishop attacks. The blocker masks come from the square rays, 
+// and every table entry is filled in by asking the default FENnec movegen where a slider on 
+// an otherwise empty board with those blockers could go, so the tables can only be as wrong 
+// as the movegen the perft suite already checks. The magics are found at startup with a 
+// fixed seed, which only takes a moment, and then never change. 
+
+use std::sync::OnceLock;
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use crate::chess::implementations::impls_vzero::movegen::XorShiftRng;
+use super::board_rep::*;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    // 64 minus the number of bits in the mask. 
+    shift: u32,
+    // Where this square's part of the attack table starts. 
+    offset: usize,
+}
+
+impl Magic {
+    #[inline(always)]
+    fn index(&self, occupancy: u64) -> usize {
+        self.offset + ((occupancy & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+pub(crate) struct MagicTables {
+    rook_magics: [Magic; 64],
+    bishop_magics: [Magic; 64],
+    // Every square's attacks for both pieces, back to back. 
+    attacks: Vec<u64>,
+}
+
+// Arbitrary, but fixed so the tables come out the same every run. 
+const MAGIC_SEED: u64 = 0x6D61_6769_6373_2121;
+
+static MAGIC_TABLES: OnceLock<MagicTables> = OnceLock::new();
+
+// The tables, built the first time anything asks for them. 
+#[inline(always)]
+pub(crate) fn magic_tables() -> &'static MagicTables {
+    MAGIC_TABLES.get_or_init(|| MagicTables::generate(MAGIC_SEED))
+}
+
+// Where a rook on the square attacks, given everything on the board. Blockers are included 
+// whoever they belong to, so the caller masks off its own pieces. 
+#[inline(always)]
+pub(crate) fn get_rook_attacks(square: i8, occupancy: u64) -> u64 {
+    let tables = magic_tables();
+    tables.attacks[tables.rook_magics[square as usize].index(occupancy)]
+}
+
+#[inline(always)]
+pub(crate) fn get_bishop_attacks(square: i8, occupancy: u64) -> u64 {
+    let tables = magic_tables();
+    tables.attacks[tables.bishop_magics[square as usize].index(occupancy)]
+}
+
+#[inline(always)]
+pub(crate) fn get_queen_attacks(square: i8, occupancy: u64) -> u64 {
+    get_rook_attacks(square, occupancy) | get_bishop_attacks(square, occupancy)
+}
+
+// The squares whose occupancy matters for a slider on the square: each ray without its 
+// last square, since whatever is on the edge gets attacked either way. 
+fn blocker_mask(piece_type: EnumPiecesUncolored, square: i8) -> u64 {
+    let rays = match piece_type {
+        EnumPiecesUncolored::Rook => square.get_rook_rays(),
+        _ => square.get_bishop_rays(),
+    };
+    let mut mask = 0;
+    for ray in rays {
+        let ray_squares: Vec<i8> = ray.collect();
+        for ray_square in ray_squares.iter().take(ray_squares.len().saturating_sub(1)) {
+            mask |= 1u64 << ray_square;
+        }
+    }
+    mask
+}
+
+// The oracle: a lone white slider on the square with black knights on every other occupied 
+// square, so that its pseudo-legal moves are exactly the squares it attacks. 
+pub(crate) fn slow_slider_attacks(piece_type: EnumPiecesUncolored, square: i8, occupancy: u64) -> u64 {
+    let mut position = BitboardPosition {
+        pieces: [0; 12],
+        occupancy: [0; 2],
+        moving_side: EnumColor::White,
+        ply_count: 0,
+        move_count: 1,
+        raw_castling_data: [None; 4],
+        ep_data: -1,
+        w_king_square: square,
+        b_king_square: square,
+    };
+    let mut blockers = occupancy & !(1u64 << square);
+    while blockers != 0 {
+        position.set_square(blockers.trailing_zeros() as i8, i8::build_piece(EnumColor::Black, EnumPiecesUncolored::Knight));
+        blockers &= blockers - 1;
+    }
+    position.set_square(square, i8::build_piece(EnumColor::White, piece_type));
+    let slider_moves = match piece_type {
+        EnumPiecesUncolored::Rook => position.get_likely_rook_moves(square),
+        _ => position.get_likely_bishop_moves(square),
+    };
+    slider_moves.into_iter().fold(0, |attacks, slider_move| match slider_move.get_move() {
+        ChessMove::StandardMove(standard_move) => attacks | (1u64 << standard_move.to_square),
+        _ => attacks,
+    })
+}
+
+impl MagicTables {
+    pub(crate) fn generate(seed: u64) -> Self {
+        let mut rng = XorShiftRng::new(seed);
+        let mut attacks = Vec::new();
+        let mut rook_magics = [Magic::default(); 64];
+        let mut bishop_magics = [Magic::default(); 64];
+        for (piece_type, magics) in [(EnumPiecesUncolored::Rook, &mut rook_magics), (EnumPiecesUncolored::Bishop, &mut bishop_magics)] {
+            for square in 0..64i8 {
+                let mask = blocker_mask(piece_type, square);
+                let subsets = blocker_subsets_with_attacks(piece_type, square, mask);
+                let magic = find_magic(mask, &subsets, attacks.len(), &mut rng);
+                attacks.resize(attacks.len() + (1 << (64 - magic.shift)), 0);
+                for (blockers, blocked_attacks) in subsets {
+                    attacks[magic.index(blockers)] = blocked_attacks;
+                }
+                magics[square as usize] = magic;
+            }
+        }
+        MagicTables {
+            rook_magics: rook_magics,
+            bishop_magics: bishop_magics,
+            attacks: attacks,
+        }
+    }
+
+    // Total size of the attack table, for anyone wondering what the lookups cost in memory. 
+    pub(crate) fn table_len(&self) -> usize {
+        self.attacks.len()
+    }
+}
+
+// Every subset of the mask (the carry-rippler trick walks them all) with the oracle's 
+// attacks for it. 
+fn blocker_subsets_with_attacks(piece_type: EnumPiecesUncolored, square: i8, mask: u64) -> Vec<(u64, u64)> {
+    let mut subsets = Vec::with_capacity(1 << mask.count_ones());
+    let mut blockers = 0u64;
+    loop {
+        subsets.push((blockers, slow_slider_attacks(piece_type, square, blockers)));
+        blockers = blockers.wrapping_sub(mask) & mask;
+        if blockers == 0 {
+            break;
+        }
+    }
+    subsets
+}
+
+// Random sparse candidates until one sends every blocker subset to a slot that's either 
+// free or already holds the same attacks. Slots are marked with the attempt that filled them, 
+// so there's no clearing the whole table between attempts. 
+fn find_magic(mask: u64, subsets: &[(u64, u64)], offset: usize, rng: &mut XorShiftRng) -> Magic {
+    let bits = mask.count_ones();
+    let mut slots: Vec<(u32, u64)> = vec![(0, 0); 1 << bits];
+    let mut attempt = 0u32;
+    loop {
+        let candidate = Magic {
+            mask: mask,
+            magic: rng.next_u64() & rng.next_u64() & rng.next_u64(),
+            shift: 64 - bits,
+            offset: offset,
+        };
+        // Candidates that don't spread the mask into the top bits are hopeless. 
+        if (mask.wrapping_mul(candidate.magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+        attempt += 1;
+        let works = subsets.iter().all(|(blockers, blocked_attacks)| {
+            let slot = &mut slots[candidate.index(*blockers) - offset];
+            match slot.0 == attempt {
+                false => {
+                    *slot = (attempt, *blocked_attacks);
+                    true
+                },
+                true => slot.1 == *blocked_attacks,
+            }
+        });
+        if works {
+            return candidate
+        }
+    }
+}
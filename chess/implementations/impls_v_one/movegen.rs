@@ -0,0 +1,71 @@
+// Perft for BitboardPosition, and the side-by-side check against UnwrappedFen. 
+
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use crate::chess::implementations::impls_vzero::board_rep::UnwrappedFen;
+use super::board_rep::*;
+
+// Bulk-counting perft, the same as depth_n_total_perft in impls_vzero but depth first. 
+pub(crate) fn depth_n_total_perft(position: BitboardPosition, n: i8) -> usize {
+    if n <= 0 {
+        return 1
+    }
+    let legal_moves = position.get_legal_proper_moves();
+    if n == 1 {
+        return legal_moves.len()
+    }
+    legal_moves.into_iter()
+        .map(|legal_move| depth_n_total_perft(position.after_move(legal_move), n - 1))
+        .sum()
+}
+
+// Sub-perfts for each root move, for when the totals don't match. 
+pub(crate) fn depth_n_better_perft(position: BitboardPosition, n: i8) -> (usize, Vec<(ChessMove<i8, i8>, usize)>) {
+    let sub_perfts: Vec<_> = position.get_legal_proper_moves().into_iter()
+        .map(|legal_move| (legal_move, depth_n_total_perft(position.after_move(legal_move), n - 1)))
+        .collect();
+    (sub_perfts.iter().map(|(_legal_move, count)| count).sum(), sub_perfts)
+}
+
+// Walks the tree from the same position in both representations at once. At every node the 
+// boards, the rest of the FENnec state and the move lists (in order) have to agree, and the 
+// bitboards have to agree with each other. Hands back the leaf count, or a description of 
+// the first node where something differs and the moves that got there. 
+pub(crate) fn compare_with_unwrapped(position: &UnwrappedFen, n: i8) -> Result<usize, String> {
+    compare_node(position, &BitboardPosition::from_unwrapped(position), n, &mut Vec::new())
+}
+
+fn compare_node(unwrapped: &UnwrappedFen, bitboards: &BitboardPosition, n: i8, line: &mut Vec<String>) -> Result<usize, String> {
+    let describe = |problem: &str, line: &Vec<String>| format!("{0} after [{1}]", problem, line.join(" "));
+    if !bitboards.bitboards_agree() {
+        return Err(describe("Bitboards out of sync", line))
+    }
+    if let Some(square) = UnwrappedFen::CANONICAL_ARRAY.into_iter().find(|square| unwrapped.query_square(*square) != bitboards.query_square(*square)) {
+        return Err(describe(&format!("Square {0} holds {1} instead of {2}", square, bitboards.query_square(square), unwrapped.query_square(square)), line))
+    }
+    let same_state = unwrapped.get_color() == bitboards.get_color()
+        && unwrapped.get_ply_count() == bitboards.get_ply_count()
+        && unwrapped.get_move_count() == bitboards.get_move_count()
+        && unwrapped.try_get_ep_square() == bitboards.try_get_ep_square()
+        && unwrapped.get_w_king_square() == bitboards.get_w_king_square()
+        && unwrapped.get_b_king_square() == bitboards.get_b_king_square()
+        && [EnumColor::White, EnumColor::Black].into_iter().all(|color| format!("{:?}", unwrapped.get_castling(color)) == format!("{:?}", bitboards.get_castling(color)));
+    if !same_state {
+        return Err(describe("State other than the board differs", line))
+    }
+    if n <= 0 {
+        return Ok(1)
+    }
+    let unwrapped_moves = unwrapped.get_legal_proper_moves();
+    let bitboard_moves = bitboards.get_legal_proper_moves();
+    let move_names = |moves: &[ChessMove<i8, i8>]| moves.iter().map(|legal_move| legal_move.to_uci_string()).collect::<Vec<_>>();
+    if move_names(&unwrapped_moves) != move_names(&bitboard_moves) {
+        return Err(describe(&format!("Moves {0:?} instead of {1:?}", move_names(&bitboard_moves), move_names(&unwrapped_moves)), line))
+    }
+    let mut leaves = 0;
+    for legal_move in unwrapped_moves {
+        line.push(legal_move.to_uci_string());
+        leaves += compare_node(&unwrapped.after_move(legal_move), &bitboards.after_move(legal_move), n - 1, line)?;
+        line.pop();
+    }
+    Ok(leaves)
+}
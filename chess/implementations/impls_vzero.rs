@@ -9,3 +9,19 @@ pub(crate) mod io_code;
 pub(crate) mod movegen;
 
 pub(crate) mod eval_code;
+
+pub(crate) mod search_code;
+
+pub(crate) mod ttable_code;
+
+pub(crate) mod packed_move_code;
+
+pub(crate) mod tuning_code;
+
+pub(crate) mod zobrist_code;
+
+pub(crate) mod epd_code;
+
+pub(crate) mod game_code;
+
+pub(crate) mod match_code;
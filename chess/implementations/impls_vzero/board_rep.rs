@@ -2,6 +2,7 @@
 // naive implementation one might come up with using abstracts::helper_types.
 
 use crate::chess::abstracts::{helper_traits::*, helper_types::*};
+use super::{io_code::{interpret_fen, FenParseError}, eval_code::EvalAccumulator};
 
 // Colored for i8.
 // Pairing 0 and 1 together, 2 and 3, and so forth. Last bit is color info.
@@ -186,14 +187,14 @@ impl HasBoard for [i8; 64] {
     }
 }
 
-// PlyCounting for i8.
-impl PlyCounting for i8 {
+// PlyCounting for i16.
+impl PlyCounting for i16 {
     #[inline(always)]
-    fn get_ply_count(&self) -> i8 {
+    fn get_ply_count(&self) -> i16 {
         *self
     }
     #[inline(always)]
-    fn set_ply_count(&mut self, ply_count: i8) -> () {
+    fn set_ply_count(&mut self, ply_count: i16) -> () {
         *self = ply_count;
     }
 }
@@ -216,12 +217,13 @@ impl MoveCounting for i16 {
 pub(crate) struct UnwrappedFen {
     pub(super) board: [i8; 64],
     pub(super) moving_side: EnumColor,
-    pub(super) ply_count: i8,
+    pub(super) ply_count: i16,
     pub(super) move_count: i16,
     pub(super) raw_castling_data: [Option<(i8, i8, i8, i8)>; 4],
     pub(super) ep_data: i8,
     pub(super) w_king_square: i8,
     pub(super) b_king_square: i8,
+    pub(super) accumulator: EvalAccumulator,
 }
 
 // FENnec for UnwrappedFen.
@@ -236,8 +238,12 @@ impl HasBoard for UnwrappedFen {
     fn query_square(&self, square: Self::PositionRep) -> Self::ContentsRep {
         self.board.query_square(square)
     }
+    // Every change to the board goes through here (including all of frozen_make_move), 
+    // so this is where the eval accumulator gets kept in sync. 
     #[inline(always)]
     fn set_square(&mut self, square: Self::PositionRep, new_contents: Self::ContentsRep) -> () {
+        self.accumulator.remove_contents(self.board.query_square(square), square);
+        self.accumulator.add_contents(new_contents, square);
         self.board.set_square(square, new_contents);
     }
 }
@@ -253,11 +259,11 @@ impl Colored for UnwrappedFen {
 }
 impl PlyCounting for UnwrappedFen {
     #[inline(always)]
-    fn get_ply_count(&self) -> i8 {
+    fn get_ply_count(&self) -> i16 {
         self.ply_count.get_ply_count()
     }
     #[inline(always)]
-    fn set_ply_count(&mut self, ply_count: i8) -> () {
+    fn set_ply_count(&mut self, ply_count: i16) -> () {
         self.ply_count.set_ply_count(ply_count)
     }
 }
@@ -343,17 +349,19 @@ impl FENnec for UnwrappedFen {
 
 // Probably the most important position to have on hand.
 
+const STARTPOS_BOARD: [i8; 64] = [
+    6,  2,  4,  8, 10,  4,  2,  6, 
+    0,  0,  0,  0,  0,  0,  0,  0, 
+    -1, -1, -1, -1, -1, -1, -1, -1, 
+    -1, -1, -1, -1, -1, -1, -1, -1, 
+    -1, -1, -1, -1, -1, -1, -1, -1, 
+    -1, -1, -1, -1, -1, -1, -1, -1, 
+    1,  1,  1,  1,  1,  1,  1,  1, 
+    7,  3,  5,  9, 11,  5,  3,  7, 
+];
+
 pub(crate) const STARTPOS: UnwrappedFen = UnwrappedFen {
-    board: [
-        6,  2,  4,  8, 10,  4,  2,  6, 
-        0,  0,  0,  0,  0,  0,  0,  0, 
-        -1, -1, -1, -1, -1, -1, -1, -1, 
-        -1, -1, -1, -1, -1, -1, -1, -1, 
-        -1, -1, -1, -1, -1, -1, -1, -1, 
-        -1, -1, -1, -1, -1, -1, -1, -1, 
-        1,  1,  1,  1,  1,  1,  1,  1, 
-        7,  3,  5,  9, 11,  5,  3,  7, 
-    ],
+    board: STARTPOS_BOARD,
     moving_side: EnumColor::White,
     ply_count: 0,
     move_count: 1,
@@ -386,8 +394,149 @@ pub(crate) const STARTPOS: UnwrappedFen = UnwrappedFen {
     ep_data: -1,
     w_king_square: 4,
     b_king_square: 56 + 4,
+    accumulator: EvalAccumulator::from_board(&STARTPOS_BOARD),
 };
 
+// Constructors for anyone using UnwrappedFen from the outside, so they don't
+// have to reach into the const or the parser directly.
+impl UnwrappedFen {
+    #[inline(always)]
+    pub(crate) fn startpos() -> Self {
+        STARTPOS
+    }
+
+    // Just a thin wrapper around interpret_fen: the parser's errors are passed 
+    // along untouched.
+    #[inline(always)]
+    pub(crate) fn from_fen(fen_str: &str) -> Result<Self, FenParseError> {
+        interpret_fen(fen_str.to_string())
+    }
+
+    // For FENs from sources that aren't careful about castling rights, e.g. a "K" left in 
+    // after the rook has gone. Rights that don't match the board are quietly dropped rather 
+    // than left to generate castling moves for a rook that isn't there. 
+    pub(crate) fn from_fen_repairing_castling(fen_str: &str) -> Result<Self, FenParseError> {
+        let mut position = interpret_fen(fen_str.to_string())?;
+        position.repair_castling_rights();
+        Ok(position)
+    }
+
+    // The same position with the colors swapped and the board flipped top to bottom, so 
+    // white's pieces on the first rank become black's pieces on the eighth. Anything color 
+    // symmetric (the eval, perft counts) should come out the same for both. 
+    pub(crate) fn mirror_vertical(&self) -> Self {
+        let mut mirrored_board = [-1i8; 64];
+        for square_index in 0..64i8 {
+            let contents = self.board[square_index as usize];
+            mirrored_board[vertical_flip_index(square_index) as usize] = match contents < 0 {
+                true => contents,
+                // Colors are the low bit of the piece encoding. 
+                false => contents ^ 1,
+            };
+        }
+        let flip_castling = |castling_data: Option<(i8, i8, i8, i8)>| castling_data.map(|(king_from, rook_from, king_to, rook_to)| (
+            vertical_flip_index(king_from), 
+            vertical_flip_index(rook_from), 
+            vertical_flip_index(king_to), 
+            vertical_flip_index(rook_to),
+        ));
+        let old_castling = self.raw_castling_data;
+        UnwrappedFen {
+            board: mirrored_board,
+            moving_side: self.get_opposite_color(),
+            ply_count: self.ply_count,
+            move_count: self.move_count,
+            // White's rights live in the first two entries and black's in the last two. 
+            raw_castling_data: [
+                flip_castling(old_castling[2]), 
+                flip_castling(old_castling[3]), 
+                flip_castling(old_castling[0]), 
+                flip_castling(old_castling[1]),
+            ],
+            ep_data: match self.ep_data < 0 {
+                true => -1,
+                false => vertical_flip_index(self.ep_data),
+            },
+            w_king_square: vertical_flip_index(self.b_king_square),
+            b_king_square: vertical_flip_index(self.w_king_square),
+            accumulator: EvalAccumulator::from_board(&mirrored_board),
+        }
+    }
+}
+
+// What make_move_with_fen_undo saves. Everything but the board is small enough to keep 
+// whole, so unmake_fen_move can put it all back without working anything out again. The 
+// board only keeps the squares the move touched, as in MoveUndo, and they go back in as raw 
+// bytes: the accumulator is restored from its copy, so there's no need to go through 
+// set_square. 
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FenMoveUndo {
+    touched_squares: [(i8, i8); 4],
+    touched_count: u8,
+    moving_side: EnumColor,
+    ply_count: i16,
+    move_count: i16,
+    raw_castling_data: [Option<(i8, i8, i8, i8)>; 4],
+    ep_data: i8,
+    w_king_square: i8,
+    b_king_square: i8,
+    accumulator: EvalAccumulator,
+}
+
+// make_move_with_undo and unmake_move, specialised to UnwrappedFen. The generic pair has to 
+// take a move back through the trait setters, which redo the accumulator updates square by 
+// square; this version just copies things back. 
+impl UnwrappedFen {
+    #[inline(always)]
+    pub(crate) fn make_move_with_fen_undo(&mut self, possible_move: <Self as HasBoard>::MoveRep) -> FenMoveUndo {
+        let (squares, touched_count) = match possible_move {
+            ChessMove::NullMove => ([0; 4], 0),
+            ChessMove::StandardMove(standard_move) => ([standard_move.from_square, standard_move.to_square, 0, 0], 2),
+            ChessMove::PromotionMove(promotion_move) => ([promotion_move.from_square, promotion_move.to_square, 0, 0], 2),
+            ChessMove::EnPassantMove(ep_move) => ([ep_move.from_square, ep_move.taken_square, ep_move.to_square, 0], 3),
+            ChessMove::CastlingMove(castling_move) => ([castling_move.king_from, castling_move.rook_from, castling_move.king_to, castling_move.rook_to], 4),
+        };
+        let undo_info = FenMoveUndo {
+            touched_squares: squares.map(|square| (square, self.board[square as usize])),
+            touched_count: touched_count,
+            moving_side: self.moving_side,
+            ply_count: self.ply_count,
+            move_count: self.move_count,
+            raw_castling_data: self.raw_castling_data,
+            ep_data: self.ep_data,
+            w_king_square: self.w_king_square,
+            b_king_square: self.b_king_square,
+            accumulator: self.accumulator,
+        };
+        self.make_move(possible_move);
+        undo_info
+    }
+
+    // Squares can repeat (a 960 king castling onto its own rook square), which is fine since 
+    // each one held the same thing before the move whichever copy gets written last. 
+    #[inline(always)]
+    pub(crate) fn unmake_fen_move(&mut self, undo_info: FenMoveUndo) -> () {
+        for (square, contents) in undo_info.touched_squares[..undo_info.touched_count as usize].iter() {
+            self.board[*square as usize] = *contents;
+        }
+        self.moving_side = undo_info.moving_side;
+        self.ply_count = undo_info.ply_count;
+        self.move_count = undo_info.move_count;
+        self.raw_castling_data = undo_info.raw_castling_data;
+        self.ep_data = undo_info.ep_data;
+        self.w_king_square = undo_info.w_king_square;
+        self.b_king_square = undo_info.b_king_square;
+        self.accumulator = undo_info.accumulator;
+    }
+}
+
+impl Default for UnwrappedFen {
+    #[inline(always)]
+    fn default() -> Self {
+        STARTPOS
+    }
+}
+
 // Necessary because UnwrappedFen reads off rows in reverse order from what's canonical for FENs. 
 // I'll probably change conventions for the next version in an impls_vone module to avoid this and 
 // improve readability. 
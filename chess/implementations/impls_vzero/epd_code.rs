@@ -0,0 +1,148 @@
+// EPD reading and a little test suite runner, for things like WAC and ECM. Only the 
+// operations a suite runner cares about are kept (bm, am, id); everything else on the 
+// line is skipped over. 
+
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use super::{board_rep::*, search_code::*};
+
+#[derive(Clone, Debug)]
+pub(crate) struct EpdEntry {
+    pub(crate) position: UnwrappedFen,
+    pub(crate) id: Option<String>,
+    pub(crate) best_moves: Vec<ChessMove<i8, i8>>,
+    pub(crate) avoid_moves: Vec<ChessMove<i8, i8>>,
+}
+
+// Fields are the first four FEN fields, then semicolon-terminated operations like 
+// bm Qg6; or id "WAC.001";. The move counters aren't part of an EPD, so they're taken 
+// from the hmvc and fmvn operations when present and defaulted otherwise. 
+pub(crate) fn parse_epd_line(epd_line: &str) -> Result<EpdEntry, String> {
+    let mut fields = epd_line.trim().splitn(5, ' ');
+    let mut fen_fields = Vec::new();
+    for _ in 0..4 {
+        match fields.next() {
+            None => return Err(format!("EPD line has fewer than four fields: {}", epd_line)),
+            Some(field) => fen_fields.push(field),
+        }
+    }
+    let operations_str = fields.next().unwrap_or("");
+
+    let mut id = None;
+    let mut best_move_strs = Vec::new();
+    let mut avoid_move_strs = Vec::new();
+    let mut half_moves = "0";
+    let mut full_moves = "1";
+    for operation in operations_str.split(';') {
+        let operation = operation.trim();
+        let (opcode, operands) = match operation.split_once(' ') {
+            None => (operation, ""),
+            Some(split_operation) => split_operation,
+        };
+        match opcode {
+            "id" => id = Some(operands.trim().trim_matches('"').to_string()),
+            "bm" => best_move_strs.extend(operands.split_whitespace()),
+            "am" => avoid_move_strs.extend(operands.split_whitespace()),
+            "hmvc" => half_moves = operands.trim(),
+            "fmvn" => full_moves = operands.trim(),
+            _ => {},
+        }
+    }
+
+    let fen_str = format!("{0} {1} {2}", fen_fields.join(" "), half_moves, full_moves);
+    let position = UnwrappedFen::from_fen(&fen_str)?;
+    let mut best_moves = Vec::new();
+    for move_str in best_move_strs {
+        best_moves.push(position.from_san(move_str)?);
+    }
+    let mut avoid_moves = Vec::new();
+    for move_str in avoid_move_strs {
+        avoid_moves.push(position.from_san(move_str)?);
+    }
+    Ok(EpdEntry {
+        position: position,
+        id: id,
+        best_moves: best_moves,
+        avoid_moves: avoid_moves,
+    })
+}
+
+// One entry per nonempty line. Stops at the first bad line, naming it. 
+pub(crate) fn parse_epd(epd_text: &str) -> Result<Vec<EpdEntry>, String> {
+    let mut entries = Vec::new();
+    for (line_number, line) in epd_text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue
+        }
+        match parse_epd_line(line) {
+            Ok(entry) => entries.push(entry),
+            Err(message) => return Err(format!("Line {0}: {1}", line_number + 1, message)),
+        }
+    }
+    Ok(entries)
+}
+
+// Positions from a file of FEN or EPD lines, read a line at a time so that big datasets 
+// never have to sit in memory all at once. Each line is tried as a full FEN first and then 
+// as an EPD. Blank lines are skipped; a bad line comes out as an Err naming it, and the 
+// ones after it keep coming. Only failing to open the file stops things up front. 
+pub(crate) fn stream_positions(path: &std::path::Path) -> Result<impl Iterator<Item = Result<UnwrappedFen, String>>, String> {
+    use std::io::BufRead;
+    let file = match std::fs::File::open(path) {
+        Err(error) => return Err(format!("Could not read {0}: {1}", path.display(), error)),
+        Ok(file) => file,
+    };
+    let positions = std::io::BufReader::new(file).lines().enumerate()
+        .filter_map(|(line_number, line)| {
+            let line = match line {
+                Err(error) => return Some(Err(format!("Line {0}: {1}", line_number + 1, error))),
+                Ok(line) => line,
+            };
+            if line.trim().is_empty() {
+                return None
+            }
+            let parsed = match UnwrappedFen::from_fen(line.trim()) {
+                Ok(position) => Ok(position),
+                Err(_) => parse_epd_line(&line).map(|entry| entry.position),
+            };
+            Some(parsed.map_err(|message| format!("Line {0}: {1}", line_number + 1, message)))
+        });
+    Ok(positions)
+}
+
+// A position passes if the search picks one of the bm moves (when there are any) and 
+// none of the am moves. Moves are compared by their UCI strings since ChessMove has 
+// no PartialEq. 
+fn epd_passes(entry: &EpdEntry, chosen_move: ChessMove<i8, i8>) -> bool {
+    let chosen_string = chosen_move.to_uci_string();
+    let hits_best = entry.best_moves.is_empty() || entry.best_moves.iter().any(|best_move| best_move.to_uci_string() == chosen_string);
+    let hits_avoid = entry.avoid_moves.iter().any(|avoid_move| avoid_move.to_uci_string() == chosen_string);
+    hits_best && !hits_avoid
+}
+
+// Search every position to a fixed depth and print a line per position. Returns how many 
+// passed. 
+pub(crate) fn run_epd_suite(entries: &[EpdEntry], depth: i8, contempt: i32) -> usize {
+    let mut passed = 0;
+    for (entry_number, entry) in entries.iter().enumerate() {
+        let entry_name = match &entry.id {
+            None => format!("#{}", entry_number + 1),
+            Some(id) => id.clone(),
+        };
+        let mut context = SearchContext::new(entry.position.get_color(), contempt);
+        match search_best_move(&entry.position, depth, &mut context) {
+            None => println!("{}: no legal moves, FAIL", entry_name),
+            Some((chosen_move, score)) => {
+                let success = epd_passes(entry, chosen_move);
+                if success {
+                    passed += 1;
+                }
+                println!("{0}: played {1} (score {2}), {3}", entry_name, entry.position.to_san(chosen_move), score, match success {
+                    true => "pass",
+                    false => "FAIL",
+                });
+            },
+        }
+    }
+    println!("Passed {0} of {1}", passed, entries.len());
+    passed
+}
@@ -0,0 +1,135 @@
+// A game as a position plus everything that was played to get there, so that moves can be 
+// taken back one at a time. The search has make/unmake for this already; this is the same 
+// idea a level up, for analysis and for replaying "position ... moves ..." commands. 
+
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use crate::search::searches::Searchable;
+use super::board_rep::*;
+
+// How a game can be over. The claimable draws don't end anything by themselves, and the 
+// automatic ones do, whether or not anyone asks. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum GameResult {
+    // The side to move has been mated. 
+    Checkmate,
+    Stalemate,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+}
+
+impl GameResult {
+    #[inline(always)]
+    pub(crate) fn ends_game(&self) -> bool {
+        match self {
+            GameResult::ThreefoldRepetition | GameResult::FiftyMoveRule => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Game {
+    position: UnwrappedFen,
+    // Oldest first. Each move is kept with what it takes to undo it. 
+    history: Vec<(ChessMove<i8, i8>, <UnwrappedFen as Searchable>::UndoInfo)>,
+}
+
+impl Game {
+    pub(crate) fn new(start: UnwrappedFen) -> Self {
+        Game {
+            position: start,
+            history: Vec::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_position(&self) -> UnwrappedFen {
+        self.position
+    }
+
+    // Every move played so far, oldest first. 
+    pub(crate) fn moves_played(&self) -> Vec<ChessMove<i8, i8>> {
+        self.history.iter().map(|(played_move, _undo_info)| *played_move).collect()
+    }
+
+    // The hash of every position before the current one, oldest first, which is what 
+    // SearchContext::set_game_history wants. 
+    pub(crate) fn position_hashes(&self) -> Vec<u64> {
+        self.history.iter().map(|(_played_move, position_before)| position_before.zobrist_hash()).collect()
+    }
+
+    // Only legal moves get in, so that popping always gets back to a real position. ChessMove 
+    // has no PartialEq, but UCI strings tell legal moves apart just as well. 
+    pub(crate) fn push_move(&mut self, chess_move: ChessMove<i8, i8>) -> Result<(), String> {
+        let move_string = chess_move.to_uci_string();
+        match self.position.get_legal_proper_moves().iter().any(|legal_move| legal_move.to_uci_string() == move_string) {
+            false => Err(format!("{0} isn't legal in {1}", chess_move, self.position)),
+            true => {
+                self.push_legal_move(chess_move);
+                Ok(())
+            },
+        }
+    }
+
+    // Same as push_move, but from a UCI move string like "e2e4" or "e7e8q". 
+    pub(crate) fn push_uci_move(&mut self, move_string: &str) -> Result<(), String> {
+        match self.position.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == move_string) {
+            None => Err(format!("{0} isn't legal in {1}", move_string, self.position)),
+            Some(legal_move) => {
+                self.push_legal_move(legal_move);
+                Ok(())
+            },
+        }
+    }
+
+    #[inline(always)]
+    fn push_legal_move(&mut self, legal_move: ChessMove<i8, i8>) -> () {
+        let undo_info = self.position.make(legal_move);
+        self.history.push((legal_move, undo_info));
+    }
+
+    // How many times the current position has come up, this time included. 
+    pub(crate) fn repetition_count(&self) -> usize {
+        let current_hash = self.position.zobrist_hash();
+        1 + self.history.iter()
+            .filter(|(_played_move, position_before)| position_before.zobrist_hash() == current_hash)
+            .count()
+    }
+
+    // Plies since the last capture or pawn move. 
+    pub(crate) fn halfmove_clock(&self) -> usize {
+        self.position.get_ply_count().max(0) as usize
+    }
+
+    // Mates and stalemates first, since a mate on the move that would have drawn still 
+    // counts. Then the automatic draws, then the claimable ones. 
+    pub(crate) fn game_result(&self) -> Option<GameResult> {
+        match self.position.terminal_state() {
+            Some(GameEnd::Checkmate) => return Some(GameResult::Checkmate),
+            Some(GameEnd::Stalemate) => return Some(GameResult::Stalemate),
+            None => {},
+        }
+        let repetitions = self.repetition_count();
+        let halfmove_clock = self.halfmove_clock();
+        if repetitions >= 5 {
+            Some(GameResult::FivefoldRepetition)
+        } else if halfmove_clock >= 150 {
+            Some(GameResult::SeventyFiveMoveRule)
+        } else if repetitions >= 3 {
+            Some(GameResult::ThreefoldRepetition)
+        } else if halfmove_clock >= 100 {
+            Some(GameResult::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    // Takes back the last move and hands it back, or None at the start of the game. 
+    pub(crate) fn pop_move(&mut self) -> Option<ChessMove<i8, i8>> {
+        let (played_move, undo_info) = self.history.pop()?;
+        self.position.unmake(undo_info);
+        Some(played_move)
+    }
+}
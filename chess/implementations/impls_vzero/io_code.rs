@@ -3,7 +3,7 @@
 // *parser* for UnwrappedFen isn't fully general (it can only handle standard castling rules). 
 
 use crate::chess::abstracts::{helper_types::*, helper_traits::*};
-use super::board_rep::*;
+use super::{board_rep::*, eval_code::EvalAccumulator};
 
 // *Apparently* this isn't in the standard library for Rust. It's fine, I need like zero fancy 
 // functionality from it- only so I can separate some distinct but related blocks of code without 
@@ -120,6 +120,145 @@ impl std::fmt::Display for ChessMove<i8, i8> {
     }
 }
 
+// What GUIs and reference engines actually print: lowercase, no separator before the 
+// promotion piece, and 0000 for the null move. Castling is written as the king's move. 
+impl ChessMove<i8, i8> {
+    pub(crate) fn to_uci_string(&self) -> String {
+        match *self {
+            Self::NullMove => "0000".to_string(),
+            Self::PromotionMove(some_move) => {
+                let mut uci_string = san_square(some_move.from_square);
+                uci_string.push_str(&san_square(some_move.to_square));
+                uci_string.push_str(&some_move.promotion_choice.get_piece_type().to_string().to_lowercase());
+                uci_string
+            },
+            _ => self.to_string().to_lowercase(),
+        }
+    }
+}
+
+// SAN wants lowercase files, unlike the debug output above.
+#[inline(always)]
+fn san_square(square: i8) -> String {
+    standardize(square).to_string().to_lowercase()
+}
+
+// SAN output. Unlike the Display impl for moves, this needs the position the move is made from:
+// piece letters, captures, disambiguation and the check/mate suffix all depend on it.
+impl UnwrappedFen {
+    pub(crate) fn to_san(&self, legal_move: ChessMove<i8, i8>) -> String {
+        let mut san_string = match legal_move {
+            ChessMove::NullMove => return "--".to_string(),
+            ChessMove::CastlingMove(castling_move) => {
+                // Decided by where the rook starts rather than where the king lands, so that
+                // this stays correct for 960-style castling rules too.
+                match castling_move.king_from.file_gap(&castling_move.rook_from) > 0 {
+                    true => "O-O".to_string(),
+                    false => "O-O-O".to_string(),
+                }
+            },
+            ChessMove::EnPassantMove(ep_move) => {
+                let mut ep_string = san_square(ep_move.from_square)[0..1].to_string();
+                ep_string.push('x');
+                ep_string.push_str(&san_square(ep_move.to_square));
+                ep_string
+            },
+            ChessMove::StandardMove(StandardMove { from_square, to_square })
+            | ChessMove::PromotionMove(PromotionMove { from_square, to_square, .. }) => {
+                let moved_type = match self.query_square(from_square).get_contents() {
+                    None => EnumPiecesUncolored::Pawn,
+                    Some(piece) => piece.get_piece_type(),
+                };
+                let is_capture = self.query_square(to_square).get_contents().is_some();
+                let mut move_string = moved_type.to_string();
+                match moved_type {
+                    EnumPiecesUncolored::Pawn => {
+                        if is_capture {
+                            move_string.push_str(&san_square(from_square)[0..1]);
+                        }
+                    },
+                    _ => {
+                        // Disambiguate against other pieces of the same type that could also
+                        // legally land on the target square.
+                        let mut ambiguous = false;
+                        let mut shares_file = false;
+                        let mut shares_rank = false;
+                        for other_move in self.get_legal_proper_moves() {
+                            if let ChessMove::StandardMove(other_standard_move) = other_move {
+                                if other_standard_move.to_square == to_square
+                                && other_standard_move.from_square != from_square
+                                && self.query_square(other_standard_move.from_square).get_contents().map(|piece| piece.get_piece_type()) == Some(moved_type) {
+                                    ambiguous = true;
+                                    shares_file = shares_file || other_standard_move.from_square.get_file() == from_square.get_file();
+                                    shares_rank = shares_rank || other_standard_move.from_square.get_rank() == from_square.get_rank();
+                                }
+                            }
+                        }
+                        if ambiguous {
+                            let from_string = san_square(from_square);
+                            match (shares_file, shares_rank) {
+                                (false, _) => move_string.push_str(&from_string[0..1]),
+                                (true, false) => move_string.push_str(&from_string[1..2]),
+                                (true, true) => move_string.push_str(&from_string),
+                            }
+                        }
+                    },
+                }
+                if is_capture {
+                    move_string.push('x');
+                }
+                move_string.push_str(&san_square(to_square));
+                if let ChessMove::PromotionMove(promotion_move) = legal_move {
+                    move_string.push('=');
+                    move_string.push_str(&promotion_move.promotion_choice.get_piece_type().to_string());
+                }
+                move_string
+            },
+        };
+
+        let position_after = self.after_move(legal_move);
+        if position_after.mover_in_check() {
+            match position_after.get_legal_proper_moves().len() == 0 {
+                true => san_string.push('#'),
+                false => san_string.push('+'),
+            }
+        }
+        san_string
+    }
+
+    // Every legal move in SAN, sorted so that the list comes out the same way every time.
+    pub(crate) fn legal_moves_san(&self) -> Vec<String> {
+        let mut san_moves = Vec::new();
+        for legal_move in self.get_legal_proper_moves() {
+            san_moves.push(self.to_san(legal_move));
+        }
+        san_moves.sort();
+        san_moves
+    }
+
+    // SAN input, resolved against the legal moves rather than parsed piece by piece: whatever 
+    // to_san would print for a legal move is accepted, along with the usual sloppiness 
+    // (missing or extra check marks, annotations, 0-0 for O-O, e8Q for e8=Q, a trailing e.p.). 
+    pub(crate) fn from_san(&self, san_str: &str) -> Result<ChessMove<i8, i8>, String> {
+        let wanted = normalize_san(san_str);
+        for legal_move in self.get_legal_proper_moves() {
+            if normalize_san(&self.to_san(legal_move)) == wanted {
+                return Ok(legal_move)
+            }
+        }
+        Err(format!("No legal move matches the SAN \"{}\"", san_str))
+    }
+}
+
+// Strip everything that doesn't change which move is meant. 
+fn normalize_san(san_str: &str) -> String {
+    let mut normalized = san_str.trim().replace('0', "O").replace('=', "");
+    if normalized.ends_with("e.p.") {
+        normalized.truncate(normalized.len() - 4);
+    }
+    normalized.trim_end_matches(|c| c == '+' || c == '#' || c == '!' || c == '?').trim().to_string()
+}
+
 
 // Code for parsing a true FEN string into an UnwrappedFen. 
 
@@ -130,15 +269,77 @@ enum FenInterpretationState {
     ReadingCastling,
     ReadingEPFile,
     ReadingEPRank(EnumFile),
-    ReadingHalfMove(i8),
+    FinishedEP,
+    ReadingHalfMove(i16),
     ReadingFullMove(i16),
 }
 
+// The fields of a FEN that can hold an unexpected character. Piece placement isn't here 
+// because the parser just skips anything it doesn't recognise there. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum FenField {
+    Color,
+    Castling,
+    AfterEp,
+    HalfMove,
+    FullMove,
+}
+
+// Everything interpret_fen can reject a string for, so that callers can tell the kinds of 
+// failure apart instead of matching on messages. Display still gives the old messages, and 
+// anything that only wants a String can get one through ?. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum FenParseError {
+    UnexpectedChar { field: FenField, ch: char },
+    BadEpSquare(char),
+    TooManyPieces,
+    ExtraKing(EnumColor),
+    MissingKing(EnumColor),
+    MisplacedKing(EnumColor),
+    MissingColor,
+    EndedEarly,
+    // A move counter too big to store. Only ever HalfMove or FullMove. 
+    CounterOverflow(FenField),
+}
+
+impl std::fmt::Display for FenParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let color_name = |color: &EnumColor| match color {
+            EnumColor::White => "white",
+            EnumColor::Black => "black",
+        };
+        match self {
+            FenParseError::UnexpectedChar { field, ch: _ } => match field {
+                FenField::Color => write!(f, "Couldn't read active color."),
+                FenField::Castling => write!(f, "Unexpected character when reading castling rules"),
+                FenField::AfterEp => write!(f, "Unexpected character after the ep square."),
+                FenField::HalfMove => write!(f, "Unexpected character when trying to read halfmove count."),
+                FenField::FullMove => write!(f, "Unexpected character when trying to read fullmove count."),
+            },
+            FenParseError::BadEpSquare(_) => write!(f, "Unexpected character when trying to read ep rank."),
+            FenParseError::TooManyPieces => write!(f, "Piece placement describes more than 64 squares."),
+            FenParseError::ExtraKing(color) => write!(f, "Found a second {} king.", color_name(color)),
+            FenParseError::MissingKing(EnumColor::White) => write!(f, "White king undetected."),
+            FenParseError::MissingKing(EnumColor::Black) => write!(f, "Black king undetected."),
+            FenParseError::MisplacedKing(color) => write!(f, "Recorded {0} king square doesn't hold the {0} king.", color_name(color)),
+            FenParseError::MissingColor => write!(f, "Color is somehow missing."),
+            FenParseError::EndedEarly => write!(f, "FEN ended before the ep field."),
+            FenParseError::CounterOverflow(FenField::HalfMove) => write!(f, "Halfmove count is too big."),
+            FenParseError::CounterOverflow(_) => write!(f, "Fullmove count is too big."),
+        }
+    }
+}
+
+impl From<FenParseError> for String {
+    fn from(error: FenParseError) -> String {
+        error.to_string()
+    }
+}
 
 // The finite state machine for parsing FENs. This may accept non-FEN strings, but should always 
 // parse valid FEN strings correctly. 
 #[allow(dead_code)]
-pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
+pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, FenParseError> {
 
     // Data needed to produce an UnwrappedFen, mostly with invalid default values. The real values 
     // will be filled out by the state machine, which will return an error message if something 
@@ -149,8 +350,8 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
     let mut b_king_square = -1i8;
     let mut castle_rules = [None, None, None, None];
     let mut ep_square = None;
-    let mut half_moves = 0i8;
-    let mut full_moves = 0i16;
+    let mut half_moves = 0i16;
+    let mut full_moves = None;
 
     // The mutable state variable. 
     let mut curr_state = FenInterpretationState::ReadingPieces(0);
@@ -160,7 +361,7 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
     // effectively just improve the visibility of empty fields for humans. The states correspond 
     // to the different kinds of data we might be reading at the moment, and we generally move to 
     // the next state after encountering a space. 
-    for character in fen_str.chars() {
+    for character in fen_str.trim().chars() {
         match curr_state {
             FenInterpretationState::ReadingPieces(square_index) => {
 
@@ -210,6 +411,9 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                         EnumPiecesUncolored::Queen
                     )),
                     'K' => {
+                        if w_king_square >= 0 {
+                            return Err(FenParseError::ExtraKing(EnumColor::White))
+                        }
                         w_king_square = vertical_flip_index(square_index);
                         Either::Left(i8::build_piece(
                             EnumColor::White, 
@@ -217,6 +421,9 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                         ))
                     },
                     'k' => {
+                        if b_king_square >= 0 {
+                            return Err(FenParseError::ExtraKing(EnumColor::Black))
+                        }
                         b_king_square = vertical_flip_index(square_index);
                         Either::Left(i8::build_piece(
                             EnumColor::Black, 
@@ -232,6 +439,11 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                 };
                 match read_result {
                     Either::Left(piece) => {
+                        // Too many pieces or skips in the placement field would otherwise run 
+                        // right off the end of the board. 
+                        if square_index >= 64 {
+                            return Err(FenParseError::TooManyPieces)
+                        }
                         board_state[vertical_flip_index(square_index) as usize] = piece;
                         curr_state = FenInterpretationState::ReadingPieces(square_index + 1);
                     },
@@ -256,7 +468,7 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                     ' ' => {
                         curr_state = FenInterpretationState::ReadingCastling;
                     },
-                    _ => return Err("Couldn't read active color.".to_string())
+                    _ => return Err(FenParseError::UnexpectedChar { field: FenField::Color, ch: character })
                 };
             },
             FenInterpretationState::ReadingCastling => {
@@ -293,7 +505,7 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                     )),
                     ' ' => curr_state = FenInterpretationState::ReadingEPFile,
                     '-' => {},
-                    _ => return Err("Unexpected character when reading castling rules".to_string()),
+                    _ => return Err(FenParseError::UnexpectedChar { field: FenField::Castling, ch: character }),
                 }
             },
             FenInterpretationState::ReadingEPFile => {
@@ -317,7 +529,7 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                     _ => None,
                 };
                 curr_state = match try_ep_file {
-                    None => FenInterpretationState::ReadingHalfMove(0i8),
+                    None => FenInterpretationState::ReadingHalfMove(0i16),
                     Some(ep_file) => FenInterpretationState::ReadingEPRank(ep_file),
                 }
             },
@@ -340,10 +552,19 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                     _ => None,
                 };
                 match try_ep_rank {
-                    None => return Err("Unexpected character when trying to read ep rank.".to_string()),
+                    None => return Err(FenParseError::BadEpSquare(character)),
                     Some(ep_rank) => ep_square = Some(i8::build_square(ep_rank, ep_file)),
                 };
-                curr_state = FenInterpretationState::ReadingHalfMove(0)
+                curr_state = FenInterpretationState::FinishedEP
+            },
+            FenInterpretationState::FinishedEP => {
+
+                // The ep square is two characters long, so there's still a separating space 
+                // to get through before the halfmove count starts. 
+                match character {
+                    ' ' => curr_state = FenInterpretationState::ReadingHalfMove(0),
+                    _ => return Err(FenParseError::UnexpectedChar { field: FenField::AfterEp, ch: character }),
+                }
             },
             FenInterpretationState::ReadingHalfMove(prev_digits) => {
 
@@ -351,8 +572,7 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                 // as part of the state to hold whatever number is currently in the digit-reading 
                 // accumulator thing. If we can read in another digit the prev_digits are 
                 // reinterpreted as being one more base-10 place to the left than on the previous 
-                // step. Overflows are possible, but not by passing in a FEN string that arises 
-                // from a legal game of chess starting at the canonical startpos or a 960 position. 
+                // step. A number too big for the accumulator is an error rather than a panic. 
                 match character {
                     ' ' => {
                         half_moves = prev_digits;
@@ -360,8 +580,11 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                     },
                     _ => {
                         match character.to_digit(10) {
-                            None => return Err("Unexpected character when trying to read halfmove count.".to_string()),
-                            Some(digit) => curr_state = FenInterpretationState::ReadingHalfMove(10 * prev_digits + digit as i8),
+                            None => return Err(FenParseError::UnexpectedChar { field: FenField::HalfMove, ch: character }),
+                            Some(digit) => match prev_digits.checked_mul(10).and_then(|shifted| shifted.checked_add(digit as i16)) {
+                                None => return Err(FenParseError::CounterOverflow(FenField::HalfMove)),
+                                Some(new_half_moves) => curr_state = FenInterpretationState::ReadingHalfMove(new_half_moves),
+                            },
                         }
                     }
                 }
@@ -372,26 +595,51 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                 // accumulator at each step since we aren't sure when the string will end and we 
                 // don't get a terminating space. 
                 match character.to_digit(10) {
-                    None => return Err("Unexpected character when trying to read fullmove count.".to_string()),
-                    Some(digit) => {
-                        full_moves = 10 * prev_digits + digit as i16;
-                        curr_state = FenInterpretationState::ReadingFullMove(full_moves);
+                    None => return Err(FenParseError::UnexpectedChar { field: FenField::FullMove, ch: character }),
+                    Some(digit) => match prev_digits.checked_mul(10).and_then(|shifted| shifted.checked_add(digit as i16)) {
+                        None => return Err(FenParseError::CounterOverflow(FenField::FullMove)),
+                        Some(new_full_moves) => {
+                            full_moves = Some(new_full_moves);
+                            curr_state = FenInterpretationState::ReadingFullMove(new_full_moves);
+                        },
                     },
                 }
             },
         }
     }
 
+    // Plenty of tools leave off one or both move counters. Once the ep field has been read, 
+    // running out of string is fine, and the missing counters get the startpos defaults. 
+    // Running out any earlier means a field everybody needs is missing. 
+    match curr_state {
+        FenInterpretationState::ReadingPieces(_) | FenInterpretationState::ReadingColor | FenInterpretationState::ReadingCastling => {
+            return Err(FenParseError::EndedEarly)
+        },
+        FenInterpretationState::ReadingHalfMove(prev_digits) => half_moves = prev_digits,
+        _ => {},
+    }
+    let full_moves = full_moves.unwrap_or(1);
+
+    // The king squares get recorded before the board is done being built, so double check 
+    // that the kings are actually still sitting on them. Everything downstream (mover_in_check 
+    // in particular) just trusts these squares. 
+    if w_king_square >= 0 && board_state[w_king_square as usize] != i8::build_piece(EnumColor::White, EnumPiecesUncolored::King) {
+        return Err(FenParseError::MisplacedKing(EnumColor::White))
+    }
+    if b_king_square >= 0 && board_state[b_king_square as usize] != i8::build_piece(EnumColor::Black, EnumPiecesUncolored::King) {
+        return Err(FenParseError::MisplacedKing(EnumColor::Black))
+    }
+
     // Apologies for the braces here. This code just makes sure 
     // each piece of the FEN we're building is valid. 
     match color {
-        None => Err("Color is somehow missing.".to_string()),
+        None => Err(FenParseError::MissingColor),
         Some(true_color) => {
             match w_king_square < 0 {
-                true => Err("White king undetected.".to_string()),
+                true => Err(FenParseError::MissingKing(EnumColor::White)),
                 false => {
                     match b_king_square < 0 {
-                        true => Err("Black king undetected.".to_string()),
+                        true => Err(FenParseError::MissingKing(EnumColor::Black)),
                         false => {
                             Ok(
                                 UnwrappedFen {
@@ -405,7 +653,8 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
                                         Some(square) => square,
                                     },
                                     w_king_square: w_king_square,
-                                    b_king_square: b_king_square
+                                    b_king_square: b_king_square,
+                                    accumulator: EvalAccumulator::from_board(&board_state),
                                 }
                             )
                         }
@@ -414,4 +663,87 @@ pub(crate) fn interpret_fen(fen_str: String) -> Result<UnwrappedFen, String> {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// And back the other way. Castling rights come out in the usual KQkq order whatever order 
+// they're stored in, and the ep square is printed whenever one is set, whether or not 
+// anything can actually take en passant. 
+impl UnwrappedFen {
+    pub(crate) fn to_fen(&self) -> String {
+        let mut fen_string = String::new();
+        for rank_index in (0..8i8).rev() {
+            let mut empty_run = 0;
+            for file_index in 0..8i8 {
+                match self.query_square(8 * rank_index + file_index).get_contents() {
+                    None => empty_run += 1,
+                    Some(piece) => {
+                        if empty_run > 0 {
+                            fen_string.push_str(&empty_run.to_string());
+                            empty_run = 0;
+                        }
+                        let piece_char = match piece.get_piece_type() {
+                            EnumPiecesUncolored::Pawn => 'p',
+                            EnumPiecesUncolored::Knight => 'n',
+                            EnumPiecesUncolored::Bishop => 'b',
+                            EnumPiecesUncolored::Rook => 'r',
+                            EnumPiecesUncolored::Queen => 'q',
+                            EnumPiecesUncolored::King => 'k',
+                        };
+                        fen_string.push(match piece.get_color() {
+                            EnumColor::White => piece_char.to_ascii_uppercase(),
+                            EnumColor::Black => piece_char,
+                        });
+                    },
+                }
+            }
+            if empty_run > 0 {
+                fen_string.push_str(&empty_run.to_string());
+            }
+            if rank_index > 0 {
+                fen_string.push('/');
+            }
+        }
+
+        fen_string.push_str(match self.get_color() {
+            EnumColor::White => " w ",
+            EnumColor::Black => " b ",
+        });
+
+        let mut castling_string = String::new();
+        for (color, kingside_char, queenside_char) in [(EnumColor::White, 'K', 'Q'), (EnumColor::Black, 'k', 'q')] {
+            let castling_rules = self.get_castling(color);
+            let has_side = |kingside: bool| castling_rules.iter().any(|rule| match rule {
+                None => false,
+                Some(castling_move) => (castling_move.rook_from.get_file() > castling_move.king_from.get_file()) == kingside,
+            });
+            if has_side(true) {
+                castling_string.push(kingside_char);
+            }
+            if has_side(false) {
+                castling_string.push(queenside_char);
+            }
+        }
+        match castling_string.is_empty() {
+            true => fen_string.push('-'),
+            false => fen_string.push_str(&castling_string),
+        }
+
+        // The stored ep square is already the one the pawn skipped over, which is what FEN 
+        // wants. It's written whenever there is one, capturable or not, same as the parser reads it. 
+        fen_string.push(' ');
+        match self.try_get_ep_square() {
+            None => fen_string.push('-'),
+            Some((_pawn_square, passed_square)) => fen_string.push_str(&san_square(passed_square)),
+        }
+
+        fen_string.push_str(&format!(" {0} {1}", self.get_ply_count(), self.get_move_count()));
+        fen_string
+    }
+}
+
+// Debug still dumps the raw board, this is for people and other programs. 
+impl std::fmt::Display for UnwrappedFen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_fen())
+    }
+}
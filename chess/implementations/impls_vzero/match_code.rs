@@ -0,0 +1,77 @@
+// Self-play between two search configs, for finding out whether a change actually plays 
+// better rather than just searching differently. Each game goes through Game, so the 
+// repetition and move-count rules are the real ones, and the search is told about the 
+// game so far so that it can see repetitions coming. 
+
+use crate::chess::abstracts::helper_traits::*;
+use crate::chess::abstracts::helper_types::EnumColor;
+use super::{board_rep::*, eval_code::*, game_code::*, search_code::*};
+
+// Always from the first config's point of view. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) struct MatchResult {
+    pub(crate) wins: usize,
+    pub(crate) draws: usize,
+    pub(crate) losses: usize,
+}
+
+// Plays one game to a fixed depth per move and returns the winner, or None for a draw. The 
+// claimable draws are taken as soon as they come up, since both sides would claim them 
+// sooner or later anyway. Material that can't force mate is adjudicated a draw, and so is 
+// anything still going after max_plies. 
+pub(crate) fn play_game(start: UnwrappedFen, white_config: SearchConfig, black_config: SearchConfig, depth: i8, max_plies: usize) -> Option<EnumColor> {
+    let mut game = Game::new(start);
+    for _ply in 0..max_plies {
+        let position = game.get_position();
+        match game.game_result() {
+            Some(GameResult::Checkmate) => return Some(position.get_opposite_color()),
+            Some(_) => return None,
+            None => {},
+        }
+        if position.material_outlook() != MaterialOutlook::Decisive {
+            return None
+        }
+        let config = match position.get_color() {
+            EnumColor::White => white_config,
+            EnumColor::Black => black_config,
+        };
+        let mut context = SearchContext::with_config(position.get_color(), config);
+        context.set_game_history(game.position_hashes());
+        match search_best_move(&position, depth, &mut context) {
+            // Only happens at mate or stalemate, which game_result has already caught. 
+            None => return None,
+            Some((chosen_move, _score)) => {
+                if let Err(message) = game.push_move(chosen_move) {
+                    panic!("The search played an illegal move: {}", message)
+                }
+            },
+        }
+    }
+    None
+}
+
+// Plays game_count games, two per opening (one with each color) before moving on to the 
+// next opening, so that neither config gets the better side of an opening more often. 
+pub(crate) fn play_match(openings: &[UnwrappedFen], game_count: usize, first_config: SearchConfig, second_config: SearchConfig, depth: i8, max_plies: usize) -> MatchResult {
+    let mut result = MatchResult::default();
+    if openings.is_empty() {
+        return result
+    }
+    for game_number in 0..game_count {
+        let opening = openings[(game_number / 2) % openings.len()];
+        let first_color = match game_number % 2 {
+            0 => EnumColor::White,
+            _ => EnumColor::Black,
+        };
+        let winner = match first_color {
+            EnumColor::White => play_game(opening, first_config, second_config, depth, max_plies),
+            EnumColor::Black => play_game(opening, second_config, first_config, depth, max_plies),
+        };
+        match winner {
+            None => result.draws += 1,
+            Some(winning_color) if winning_color == first_color => result.wins += 1,
+            Some(_) => result.losses += 1,
+        }
+    }
+    result
+}
@@ -2,6 +2,7 @@
 
 use crate::chess::{abstracts::helper_traits::*, implementations::*};
 use super::board_rep::*;
+use std::collections::HashMap;
 
 fn legal_successor_positions(fen: UnwrappedFen) -> Vec<UnwrappedFen> {
     let mut successors = Vec::new();
@@ -47,4 +48,509 @@ pub(crate) fn depth_n_better_perft(fen: UnwrappedFen, n: i8) -> (usize, Vec<(Che
         grand_total += successors_here;
     }
     (grand_total, sub_perfts)
-}
\ No newline at end of file
+}
+
+// For bug hunts down a particular line: play the UCI moves from start, then print and return 
+// the divide from there. Saves spelling every move out with build_square. The line is 
+// printed first so that the output makes sense on its own. 
+pub(crate) fn perft_after_moves(start: UnwrappedFen, moves: &[&str], depth: i8) -> Result<(usize, Vec<(ChessMove<i8, i8>, usize)>), String> {
+    let mut curr_pos = start;
+    for move_string in moves {
+        match curr_pos.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == *move_string) {
+            None => return Err(format!("{0} isn't legal in {1}", move_string, curr_pos)),
+            Some(legal_move) => curr_pos.make_move(legal_move),
+        }
+    }
+    println!("Perft {0} after {1}:", depth, moves.join(" "));
+    println!("{}", curr_pos);
+    let (total_num, sub_perfts) = depth_n_better_perft(curr_pos, depth);
+    println!("Total: {}", total_num);
+    for (move_made, successors_num) in &sub_perfts {
+        println!("{0} - {1}", move_made, successors_num)
+    }
+    Ok((total_num, sub_perfts))
+}
+
+// Perft bisection without editing main.rs between runs. Divides at the root, then plays the 
+// first move of the line and divides again one ply shallower, and so on down the line, so 
+// that each level shows which child to follow next when the counts disagree with another 
+// engine's. Returns one divide per level, the root's first. 
+pub(crate) fn divide_down_line(start: UnwrappedFen, depth: i8, moves: &[&str]) -> Result<Vec<(usize, Vec<(ChessMove<i8, i8>, usize)>)>, String> {
+    if moves.len() as i8 >= depth {
+        return Err(format!("Can't follow {0} moves down from depth {1}", moves.len(), depth))
+    }
+    let mut curr_pos = start;
+    let mut levels = vec![depth_n_better_perft(curr_pos, depth)];
+    for (level, move_string) in moves.iter().enumerate() {
+        match curr_pos.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == *move_string) {
+            None => return Err(format!("{0} isn't legal in {1}", move_string, curr_pos)),
+            Some(legal_move) => curr_pos.make_move(legal_move),
+        }
+        levels.push(depth_n_better_perft(curr_pos, depth - 1 - level as i8));
+    }
+    Ok(levels)
+}
+
+// The classic extended perft columns. Everything but nodes is counted over the 
+// moves made into the leaves, so at depth 1 from startpos it's 20 nodes and 
+// zeroes everywhere else. Captures include en passant captures. 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PerftStats {
+    pub(crate) nodes: usize,
+    pub(crate) captures: usize,
+    pub(crate) en_passants: usize,
+    pub(crate) castles: usize,
+    pub(crate) promotions: usize,
+    pub(crate) checks: usize,
+    pub(crate) checkmates: usize,
+}
+
+impl PerftStats {
+    #[inline(always)]
+    fn add(&mut self, other: PerftStats) -> () {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+// Tally up a single move into a leaf. 
+fn leaf_stats(fen: UnwrappedFen, leaf_move: ChessMove<i8, i8>) -> PerftStats {
+    let mut stats = PerftStats {nodes: 1, ..PerftStats::default()};
+    match leaf_move {
+        ChessMove::StandardMove(standard_move) => if fen.query_square(standard_move.to_square).get_contents().is_some() {
+            stats.captures = 1
+        },
+        ChessMove::EnPassantMove(_ep_move) => {
+            stats.captures = 1;
+            stats.en_passants = 1;
+        },
+        ChessMove::CastlingMove(_castling_move) => stats.castles = 1,
+        ChessMove::PromotionMove(promotion_move) => {
+            stats.promotions = 1;
+            if fen.query_square(promotion_move.to_square).get_contents().is_some() {
+                stats.captures = 1
+            }
+        },
+        ChessMove::NullMove => {},
+    }
+    let position_after = fen.after_move(leaf_move);
+    if position_after.mover_in_check() {
+        stats.checks = 1;
+        if position_after.get_legal_proper_moves().len() == 0 {
+            stats.checkmates = 1
+        }
+    }
+    stats
+}
+
+// A lot slower than depth_n_total_perft, since every leaf has to be made and looked at. 
+pub(crate) fn depth_n_extended_perft(fen: UnwrappedFen, n: i8) -> PerftStats {
+    let mut stats = PerftStats::default();
+    if n <= 0 {
+        stats.nodes = 1;
+        return stats
+    }
+    for legal_move in fen.get_legal_proper_moves() {
+        match n == 1 {
+            true => stats.add(leaf_stats(fen, legal_move)),
+            false => stats.add(depth_n_extended_perft(fen.after_move(legal_move), n - 1)),
+        }
+    }
+    stats
+}
+
+// Perft the slow way, making every move right down to the leaves and counting those. The 
+// others only count the moves at the last ply, so this is the one that measures make_move. 
+pub(crate) fn depth_n_plain_perft(fen: UnwrappedFen, n: i8) -> usize {
+    if n <= 0 {
+        return 1
+    }
+    fen.get_legal_proper_moves().into_iter()
+        .map(|legal_move| depth_n_plain_perft(fen.after_move(legal_move), n - 1))
+        .sum()
+}
+
+// Bulk-counting perft that remembers the count for every (position, depth) it finishes, so 
+// each transposition only gets walked once. 
+pub(crate) fn depth_n_hashed_perft(fen: UnwrappedFen, n: i8) -> usize {
+    let mut known_counts = HashMap::new();
+    hashed_perft_with(fen, n, &mut known_counts)
+}
+
+fn hashed_perft_with(fen: UnwrappedFen, n: i8, known_counts: &mut HashMap<(u64, i8), usize>) -> usize {
+    if n <= 0 {
+        return 1
+    }
+    if n == 1 {
+        return fen.get_legal_proper_moves().len()
+    }
+    let key = (fen.zobrist_hash(), n);
+    if let Some(count) = known_counts.get(&key) {
+        return *count
+    }
+    let count = fen.get_legal_proper_moves().into_iter()
+        .map(|legal_move| hashed_perft_with(fen.after_move(legal_move), n - 1, known_counts))
+        .sum();
+    known_counts.insert(key, count);
+    count
+}
+
+// Times plain, hashed and bulk-counting perft on startpos and Kiwipete at the same depth, as 
+// a baseline for movegen changes. Nodes per second are counted in leaf nodes for all three, 
+// and the totals get printed too, since a faster wrong answer doesn't count. 
+pub(crate) fn benchmark_perfts(depth: i8) -> () {
+    let kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    for (position_name, position) in [("startpos", UnwrappedFen::startpos()), ("Kiwipete", kiwipete)] {
+        let perfts: [(&str, fn(UnwrappedFen, i8) -> usize); 3] = [
+            ("plain", depth_n_plain_perft),
+            ("hashed", depth_n_hashed_perft),
+            ("bulk", depth_n_total_perft),
+        ];
+        for (perft_name, perft) in perfts {
+            let perft_start = std::time::Instant::now();
+            let nodes = perft(position, depth);
+            let elapsed = perft_start.elapsed();
+            println!("{0} depth {1}, {2:>6} perft: {3} nodes in {4:?}, {5:.0} nps", 
+                position_name, depth, perft_name, nodes, elapsed, nodes as f64 / elapsed.as_secs_f64().max(1e-9));
+        }
+    }
+}
+
+// Everything that differs between our divide output and a reference engine's. 
+// Moves are keyed by their UCI strings so the reference can come straight from 
+// another engine's "go perft" output. 
+#[derive(Debug, Default)]
+pub(crate) struct DivideDiff {
+    // Moves the reference has that we don't generate, with the reference count. 
+    pub(crate) missing: Vec<(String, usize)>,
+    // Moves we generate that the reference doesn't, with our count. 
+    pub(crate) extra: Vec<(String, usize)>,
+    // Moves both sides have, as (move, ours, reference). 
+    pub(crate) mismatched: Vec<(String, usize, usize)>,
+}
+
+impl DivideDiff {
+    #[inline(always)]
+    pub(crate) fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+impl std::fmt::Display for DivideDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_clean() {
+            return writeln!(f, "Divide output matches the reference.")
+        }
+        for (move_string, reference_count) in &self.missing {
+            writeln!(f, "missing  {0}: reference has {1}", move_string, reference_count)?;
+        }
+        for (move_string, our_count) in &self.extra {
+            writeln!(f, "extra    {0}: we have {1}", move_string, our_count)?;
+        }
+        for (move_string, our_count, reference_count) in &self.mismatched {
+            writeln!(f, "mismatch {0}: we have {1}, reference has {2}", move_string, our_count, reference_count)?;
+        }
+        Ok(())
+    }
+}
+
+// Compare the per-move results of depth_n_better_perft with a reference. The output 
+// is sorted by move string so two runs can be diffed by eye too. 
+pub(crate) fn compare_divide(ours: &[(ChessMove<i8, i8>, usize)], reference: &HashMap<String, usize>) -> DivideDiff {
+    let mut diff = DivideDiff::default();
+    let mut our_map = HashMap::new();
+    for (move_made, successors_num) in ours {
+        our_map.insert(move_made.to_uci_string(), *successors_num);
+    }
+    for (move_string, our_count) in &our_map {
+        match reference.get(move_string) {
+            None => diff.extra.push((move_string.clone(), *our_count)),
+            Some(reference_count) => if reference_count != our_count {
+                diff.mismatched.push((move_string.clone(), *our_count, *reference_count))
+            },
+        }
+    }
+    for (move_string, reference_count) in reference {
+        if !our_map.contains_key(move_string) {
+            diff.missing.push((move_string.clone(), *reference_count))
+        }
+    }
+    diff.missing.sort();
+    diff.extra.sort();
+    diff.mismatched.sort();
+    diff
+}
+
+// Divide output as plain text for diffing against another engine's: "e2e4 20" on each line, 
+// sorted by move, and nothing else, not even the total. 
+pub(crate) fn divide_text(sub_perfts: &[(ChessMove<i8, i8>, usize)]) -> String {
+    let mut lines: Vec<String> = sub_perfts.iter()
+        .map(|(move_made, successors_num)| format!("{0} {1}", move_made.to_uci_string(), successors_num))
+        .collect();
+    lines.sort();
+    lines.into_iter().map(|line| line + "\n").collect()
+}
+
+// Runs the divide and writes divide_text to the file, or to stdout without one. Returns the 
+// total, since it isn't in the text. A big run is better off in a file than in the scrollback. 
+pub(crate) fn write_divide(position: UnwrappedFen, depth: i8, file_path: Option<&std::path::Path>) -> Result<usize, String> {
+    let (total_num, sub_perfts) = depth_n_better_perft(position, depth);
+    let text = divide_text(&sub_perfts);
+    match file_path {
+        None => print!("{}", text),
+        Some(path) => std::fs::write(path, text).map_err(|error| format!("Could not write divide output to {0}: {1}", path.display(), error))?,
+    }
+    Ok(total_num)
+}
+
+// Reads divide output in the "e2e4: 20" format most engines print for "go perft". 
+// Blank lines and lines without a colon (like "Nodes searched: ...") are skipped. 
+pub(crate) fn parse_divide_reference(reference_text: &str) -> Result<HashMap<String, usize>, String> {
+    let mut reference = HashMap::new();
+    for line in reference_text.lines() {
+        let (move_part, count_part) = match line.split_once(':') {
+            None => continue,
+            Some(split_line) => split_line,
+        };
+        let move_string = move_part.trim().to_lowercase();
+        if move_string.contains(' ') {
+            continue
+        }
+        match count_part.trim().parse::<usize>() {
+            Ok(count) => {reference.insert(move_string, count);},
+            Err(_) => return Err(format!("Could not read the count in divide line \"{}\"", line)),
+        }
+    }
+    Ok(reference)
+}
+
+// One line of a perft suite file, in the usual format: a FEN (the move counters can be left 
+// off, like in an EPD) and then depth/count pairs, as in 
+// rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902 
+// The semicolons are optional. 
+#[derive(Clone, Debug)]
+pub(crate) struct PerftSuiteEntry {
+    pub(crate) fen: String,
+    pub(crate) position: UnwrappedFen,
+    pub(crate) expected_counts: Vec<(i8, usize)>,
+}
+
+pub(crate) fn parse_perft_suite_line(line: &str) -> Result<PerftSuiteEntry, String> {
+    let spaced_line = line.replace(';', " ");
+    let tokens: Vec<&str> = spaced_line.split_whitespace().collect();
+    // No FEN field looks like D followed by a number, so that's where the counts start. 
+    let is_depth_token = |token: &str| token.len() > 1 && token.starts_with('D') && token[1..].chars().all(|character| character.is_ascii_digit());
+    let fen_length = tokens.iter().position(|token| is_depth_token(token)).unwrap_or(tokens.len());
+    let fen = match fen_length {
+        4 => format!("{} 0 1", tokens[..4].join(" ")),
+        6 => tokens[..6].join(" "),
+        _ => return Err(format!("Expected a FEN with 4 or 6 fields before the counts, found {} fields", fen_length)),
+    };
+    let position = UnwrappedFen::from_fen(&fen).map_err(|error| format!("Could not parse FEN \"{0}\": {1}", fen, error))?;
+    let count_tokens = &tokens[fen_length..];
+    if count_tokens.len() % 2 != 0 {
+        return Err(format!("Depth without a count in \"{}\"", count_tokens.join(" ")))
+    }
+    let mut expected_counts = Vec::new();
+    for pair in count_tokens.chunks(2) {
+        let depth = match is_depth_token(pair[0]) {
+            true => pair[0][1..].parse::<i8>().map_err(|_| format!("Depth {} is too deep", pair[0]))?,
+            false => return Err(format!("Expected a depth like D3, found \"{}\"", pair[0])),
+        };
+        let count = pair[1].parse::<usize>().map_err(|_| format!("Could not read the count {0} for {1}", pair[1], pair[0]))?;
+        expected_counts.push((depth, count));
+    }
+    Ok(PerftSuiteEntry {
+        fen: fen,
+        position: position,
+        expected_counts: expected_counts,
+    })
+}
+
+// A whole suite, skipping blank lines and # comments. Errors say which line they're on. 
+pub(crate) fn parse_perft_suite(suite_text: &str) -> Result<Vec<PerftSuiteEntry>, String> {
+    let mut entries = Vec::new();
+    for (line_index, line) in suite_text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue
+        }
+        entries.push(parse_perft_suite_line(line).map_err(|error| format!("Line {0}: {1}", line_index + 1, error))?);
+    }
+    Ok(entries)
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct PerftMismatch {
+    pub(crate) fen: String,
+    pub(crate) depth: i8,
+    pub(crate) expected: usize,
+    pub(crate) found: usize,
+}
+
+impl std::fmt::Display for PerftMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{0} at depth {1}: expected {2}, found {3}", self.fen, self.depth, self.expected, self.found)
+    }
+}
+
+// Checks every count in the suite up to max_depth with depth_n_total_perft, since some 
+// suites go far deeper than is worth waiting for. Returns how many counts got checked and 
+// the ones that came out wrong. 
+pub(crate) fn run_perft_suite(entries: &[PerftSuiteEntry], max_depth: i8) -> (usize, Vec<PerftMismatch>) {
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+    for entry in entries {
+        for (depth, expected) in entry.expected_counts.iter().copied().filter(|(depth, _expected)| *depth <= max_depth) {
+            checked += 1;
+            let found = depth_n_total_perft(entry.position, depth);
+            if found != expected {
+                mismatches.push(PerftMismatch {
+                    fen: entry.fen.clone(),
+                    depth: depth,
+                    expected: expected,
+                    found: found,
+                });
+            }
+        }
+    }
+    (checked, mismatches)
+}
+
+pub(crate) fn run_perft_suite_file(path: &std::path::Path, max_depth: i8) -> Result<(usize, Vec<PerftMismatch>), String> {
+    let suite_text = std::fs::read_to_string(path).map_err(|error| format!("Could not read perft suite {0}: {1}", path.display(), error))?;
+    Ok(run_perft_suite(&parse_perft_suite(&suite_text)?, max_depth))
+}
+
+// The usual positions, small enough to run in a few seconds. 
+pub(crate) const SMALL_PERFT_SUITE: &str = "\
+# startpos, Kiwipete and positions 3 to 6 from the CPW perft results page 
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902 ;D4 197281
+r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - ;D1 48 ;D2 2039 ;D3 97862
+8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 ;D1 14 ;D2 191 ;D3 2812 ;D4 43238
+r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1 D1 6 D2 264 D3 9467
+rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8 ;D1 44 ;D2 1486 ;D3 62379
+r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10 ;D1 46 ;D2 2079 ;D3 89890
+";
+
+// A tiny xorshift generator, so random playouts for testing don't need a rand dependency. 
+// Nowhere near good enough for anything that actually cares about randomness. 
+pub(crate) struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // Xorshift gets stuck at zero forever. 
+        XorShiftRng { state: seed.max(1) }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // Not quite uniform, but close enough for picking moves. Takes the high bits, since 
+    // the low bits of consecutive outputs are linearly related and a plain % shows it. 
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (((self.next_u64() as u128) * (bound as u128)) >> 64) as usize
+    }
+}
+
+// Play random legal moves starting from fen, stopping early if the game ends. Returns 
+// every position passed through, including the starting one. 
+pub(crate) fn random_playout(fen: UnwrappedFen, plies: usize, rng: &mut XorShiftRng) -> Vec<UnwrappedFen> {
+    let mut positions = vec![fen];
+    let mut curr_pos = fen;
+    for _ in 0..plies {
+        let legal_moves = curr_pos.get_legal_proper_moves();
+        if legal_moves.len() == 0 {
+            break;
+        }
+        curr_pos.make_move(legal_moves[rng.below(legal_moves.len())]);
+        positions.push(curr_pos);
+    }
+    positions
+}
+
+// Every position from a batch of random games, all played out of the one seed. Each entry 
+// is a start, how many games to play from it and how many plies each game goes on for. 
+pub(crate) fn random_playout_positions(seed: u64, playouts: &[(UnwrappedFen, usize, usize)]) -> Vec<UnwrappedFen> {
+    let mut rng = XorShiftRng::new(seed);
+    let mut positions = Vec::new();
+    for (start, games, plies) in playouts.iter().copied() {
+        for _game in 0..games {
+            positions.append(&mut random_playout(start, plies, &mut rng));
+        }
+    }
+    positions
+}
+
+// The usual pile of positions for checks that go over every legal move: 30 random games 
+// from startpos, and 10 short ones from kiwipete to get more castling and en passant in. 
+pub(crate) fn random_test_positions(seed: u64) -> Vec<UnwrappedFen> {
+    let kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    random_playout_positions(seed, &[(STARTPOS, 30, 150), (kiwipete, 10, 40)])
+}
+
+// The slowest legality filter that could possibly be right, to keep the fast paths honest: 
+// make every pseudo-legal move and see whether the mover's king is left hanging. Castling 
+// is checked by actually standing the king on each square it crosses. 
+pub(crate) fn reference_legal_moves(fen: UnwrappedFen) -> Vec<ChessMove<i8, i8>> {
+    let mut legal_moves = fen.get_pseudo_legal_proper_moves();
+    legal_moves.retain(|possible_move| {
+        if let ChessMove::CastlingMove(castling_move) = possible_move {
+            if fen.mover_in_check() {
+                return false
+            }
+            let mut king_path = castling_move.king_from;
+            while king_path != castling_move.king_to {
+                king_path += match castling_move.king_to > king_path {
+                    true => 1,
+                    false => -1,
+                };
+                let king_stood_there = fen.after_frozen_move(ChessMove::StandardMove(StandardMove {
+                    from_square: castling_move.king_from,
+                    to_square: king_path,
+                }));
+                if king_stood_there.sees_obvious_attack(fen.get_color(), king_path) {
+                    return false
+                }
+            }
+        }
+        !fen.after_move(*possible_move).non_mover_in_check()
+    });
+    legal_moves
+}
+
+// Compare get_legal_proper_moves with reference_legal_moves, as sets of UCI strings, at every 
+// position of some random games from each start. Returns how many positions were checked 
+// and a description of each one that disagreed. 
+pub(crate) fn fuzz_legal_moves(starts: &[UnwrappedFen], games_per_start: usize, plies: usize, rng: &mut XorShiftRng) -> (usize, Vec<String>) {
+    let mut positions_checked = 0;
+    let mut disagreements = Vec::new();
+    for start in starts {
+        for _game in 0..games_per_start {
+            for position in random_playout(*start, plies, rng) {
+                let mut fast_moves: Vec<String> = position.get_legal_proper_moves().iter().map(|legal_move| legal_move.to_uci_string()).collect();
+                let mut reference_moves: Vec<String> = reference_legal_moves(position).iter().map(|legal_move| legal_move.to_uci_string()).collect();
+                fast_moves.sort();
+                reference_moves.sort();
+                if fast_moves != reference_moves {
+                    let missing: Vec<&String> = reference_moves.iter().filter(|uci_move| !fast_moves.contains(uci_move)).collect();
+                    let extra: Vec<&String> = fast_moves.iter().filter(|uci_move| !reference_moves.contains(uci_move)).collect();
+                    disagreements.push(format!("{0}: missing {1:?}, extra {2:?}", position, missing, extra));
+                }
+                positions_checked += 1;
+            }
+        }
+    }
+    (positions_checked, disagreements)
+}
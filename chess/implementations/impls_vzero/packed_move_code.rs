@@ -0,0 +1,95 @@
+// Moves squeezed into 16 bits, for the transposition table and anywhere else that would 
+// rather not carry a whole ChessMove around. The squares fit in 6 bits each, and the other 
+// 4 say what kind of move it was. Whatever the 4 bits can't say (the ep victim, the castling 
+// rook's destination, the promotion piece's color) comes back from the position when 
+// unpacking. 
+
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use super::board_rep::*;
+
+// Layout: bits 0-5 from, 6-11 to, 12-15 kind. Castling is stored as king square to rook 
+// square, which tells the rights apart even in 960. The null move is all zeroes, which no 
+// real move can be since a1a1 goes nowhere. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) struct PackedMove(pub(crate) u16);
+
+const STANDARD_KIND: u16 = 0;
+const EN_PASSANT_KIND: u16 = 1;
+const CASTLING_KIND: u16 = 2;
+// Promotions are this plus 0-3 for knight, bishop, rook, queen. 
+const PROMOTION_KIND: u16 = 4;
+
+impl PackedMove {
+    pub(crate) const NULL: PackedMove = PackedMove(0);
+
+    #[inline(always)]
+    fn build(from_square: i8, to_square: i8, kind: u16) -> Self {
+        PackedMove((from_square as u16) | ((to_square as u16) << 6) | (kind << 12))
+    }
+    #[inline(always)]
+    fn origin(self) -> i8 {
+        (self.0 & 0x3F) as i8
+    }
+    #[inline(always)]
+    fn destination(self) -> i8 {
+        ((self.0 >> 6) & 0x3F) as i8
+    }
+    #[inline(always)]
+    fn kind(self) -> u16 {
+        self.0 >> 12
+    }
+
+    pub(crate) fn from_move(chess_move: ChessMove<i8, i8>) -> Self {
+        match chess_move {
+            ChessMove::NullMove => PackedMove::NULL,
+            ChessMove::StandardMove(standard_move) => Self::build(standard_move.from_square, standard_move.to_square, STANDARD_KIND),
+            ChessMove::EnPassantMove(ep_move) => Self::build(ep_move.from_square, ep_move.to_square, EN_PASSANT_KIND),
+            ChessMove::CastlingMove(castling_move) => Self::build(castling_move.king_from, castling_move.rook_from, CASTLING_KIND),
+            ChessMove::PromotionMove(promotion_move) => {
+                let piece_bits = match promotion_move.promotion_choice.get_piece_type() {
+                    EnumPiecesUncolored::Knight => 0,
+                    EnumPiecesUncolored::Bishop => 1,
+                    EnumPiecesUncolored::Rook => 2,
+                    _ => 3,
+                };
+                Self::build(promotion_move.from_square, promotion_move.to_square, PROMOTION_KIND + piece_bits)
+            },
+        }
+    }
+
+    // Back to a full move for the side to move in position. Nothing here checks legality, 
+    // so a move from a hash collision can come out as nonsense; the only thing that can 
+    // fail outright is castling without a matching right. 
+    pub(crate) fn to_move(self, position: &UnwrappedFen) -> Option<ChessMove<i8, i8>> {
+        if self == PackedMove::NULL {
+            return Some(ChessMove::NullMove)
+        }
+        let (from_square, to_square) = (self.origin(), self.destination());
+        match self.kind() {
+            STANDARD_KIND => Some(ChessMove::StandardMove(StandardMove { from_square: from_square, to_square: to_square })),
+            // The victim sits beside the capturing pawn, on the file it moves to. 
+            EN_PASSANT_KIND => Some(ChessMove::EnPassantMove(EnPassantMove {
+                from_square: from_square,
+                taken_square: from_square - from_square % 8 + to_square % 8,
+                to_square: to_square,
+            })),
+            CASTLING_KIND => position.get_castling(position.get_color()).into_iter().flatten()
+                .find(|castling_move| castling_move.king_from == from_square && castling_move.rook_from == to_square)
+                .map(ChessMove::CastlingMove),
+            kind => {
+                let piece_type = match kind.checked_sub(PROMOTION_KIND) {
+                    Some(0) => EnumPiecesUncolored::Knight,
+                    Some(1) => EnumPiecesUncolored::Bishop,
+                    Some(2) => EnumPiecesUncolored::Rook,
+                    Some(3) => EnumPiecesUncolored::Queen,
+                    _ => return None,
+                };
+                Some(ChessMove::PromotionMove(PromotionMove {
+                    from_square: from_square,
+                    to_square: to_square,
+                    promotion_choice: i8::build_piece(position.get_color(), piece_type),
+                }))
+            },
+        }
+    }
+}
@@ -0,0 +1,718 @@
+// A plain alpha-beta search for UnwrappedFen. Unlike ab_best_move in eval_code, this one is
+// end-of-game aware: checkmates get mate scores and drawn nodes get a draw score, which is
+// where contempt comes in. The hope is that this is the search that eventually grows all
+// of the usual pruning and ordering tricks.
+
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use super::{board_rep::*, eval_code::*};
+use std::sync::{atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering}, Arc, Mutex};
+use std::time::Instant;
+use crate::search::searches::{self as generic_search, Searchable, TerminalState};
+use crate::value::{MATE_SCORE, MATE_THRESHOLD};
+
+
+// How far a quiet move is allowed to lift the static eval, indexed by remaining depth, before 
+// futility pruning gives up on it. Past the end of this there's no pruning. 
+const FUTILITY_MARGINS: [i32; 3] = [0, 200, 500];
+
+// Slack for delta pruning on top of the material a capture wins, to cover whatever the eval 
+// thinks of the new position beyond the material. 
+const DELTA_MARGIN: i32 = 200;
+
+// The numbers the search could be tuned on, kept together so that they can be set over UCI 
+// for test matches instead of edited into the constants above. The default is exactly the 
+// search as it was with the constants. LMR, null move and aspiration windows don't exist in 
+// the search yet, so their numbers join this when they do. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct SearchConfig {
+    // Centipawns the root side is willing to give up to avoid a draw. Positive
+    // contempt means the root side thinks it's the stronger player.
+    pub(crate) contempt: i32,
+    pub(crate) futility_margins: [i32; 3],
+    pub(crate) delta_margin: i32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            contempt: 0,
+            futility_margins: FUTILITY_MARGINS,
+            delta_margin: DELTA_MARGIN,
+        }
+    }
+}
+
+// Mate scores as UCI wants them: full moves rather than plies, negative when we're the one 
+// getting mated. Our mate in 1 ply is mate 1, and so is mate in 2 plies for the opponent 
+// (their reply being the mate), which comes out as mate -1. None for ordinary evals. 
+pub(crate) fn mate_in_moves(score: i32) -> Option<i32> {
+    match score.abs() > MATE_THRESHOLD {
+        false => None,
+        true => {
+            let plies = MATE_SCORE - score.abs();
+            Some(score.signum() * ((plies + 1) / 2))
+        },
+    }
+}
+
+// How far from the root a search is ever allowed to get. Depth alone doesn't bound the 
+// recursion once extensions give depth back, so past this every node is just a static eval. 
+// Mate scores need MAX_PLY to stay well under MATE_SCORE - MATE_THRESHOLD. 
+pub(crate) const MAX_PLY: i32 = 128;
+
+// Nodes between looks at the SearchControl. Checking the clock every node would be a waste. 
+const NODES_BETWEEN_STOP_CHECKS: u64 = 1024;
+
+// How a running search gets told to stop, shared between whoever started it and the thread 
+// doing the searching. While pondering the deadline is ignored, since the clock isn't ours 
+// yet; a ponderhit hands over a deadline and flips pondering off without touching anything 
+// else, so the search carries on from wherever it had got to. 
+#[derive(Debug)]
+pub(crate) struct SearchControl {
+    stop_requested: AtomicBool,
+    pondering: AtomicBool,
+    deadline: Mutex<Option<Instant>>,
+    // Progress reports, so the other side can see how far along the search is. 
+    completed_depth: AtomicI32,
+    nodes_searched: AtomicU64,
+}
+
+impl SearchControl {
+    pub(crate) fn new(pondering: bool, deadline: Option<Instant>) -> Self {
+        SearchControl {
+            stop_requested: AtomicBool::new(false),
+            pondering: AtomicBool::new(pondering),
+            deadline: Mutex::new(deadline),
+            completed_depth: AtomicI32::new(0),
+            nodes_searched: AtomicU64::new(0),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn request_stop(&self) -> () {
+        self.stop_requested.store(true, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub(crate) fn stop_requested(&self) -> bool {
+        self.stop_requested.load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_pondering(&self) -> bool {
+        self.pondering.load(Ordering::Acquire)
+    }
+
+    // The deadline has to be in place before pondering is switched off, or the search could 
+    // catch a glimpse of the old (missing) deadline and run forever. 
+    pub(crate) fn ponderhit(&self, deadline: Option<Instant>) -> () {
+        *self.deadline.lock().unwrap() = deadline;
+        self.pondering.store(false, Ordering::Release);
+    }
+
+    pub(crate) fn should_stop(&self) -> bool {
+        if self.stop_requested() {
+            return true
+        }
+        if self.is_pondering() {
+            return false
+        }
+        match *self.deadline.lock().unwrap() {
+            None => false,
+            Some(deadline) => Instant::now() >= deadline,
+        }
+    }
+
+    pub(crate) fn report_progress(&self, completed_depth: i32, nodes_searched: u64) -> () {
+        self.completed_depth.store(completed_depth, Ordering::Relaxed);
+        self.nodes_searched.store(nodes_searched, Ordering::Relaxed);
+    }
+
+    // (deepest completed iteration, nodes searched so far)
+    pub(crate) fn progress(&self) -> (i32, u64) {
+        (self.completed_depth.load(Ordering::Relaxed), self.nodes_searched.load(Ordering::Relaxed))
+    }
+}
+
+// UnwrappedFen for the generic searches. Undoing a move means putting back a copy of the 
+// position from before it, which so far is as quick as either unmake (see 
+// testing_make_unmake), the same as in alpha_beta. 
+impl Searchable for UnwrappedFen {
+    type MoveRep = <UnwrappedFen as HasBoard>::MoveRep;
+    type UndoInfo = UnwrappedFen;
+
+    #[inline(always)]
+    fn push_pseudolegal_moves(&self, moves: &mut Vec<Self::MoveRep>) -> () {
+        self.push_pseudo_legal_proper_moves(moves)
+    }
+    #[inline(always)]
+    fn is_legal(&self, pseudolegal_move: Self::MoveRep) -> bool {
+        self.check_remaining_legality(pseudolegal_move)
+    }
+    #[inline(always)]
+    fn make(&mut self, legal_move: Self::MoveRep) -> UnwrappedFen {
+        let position_before = *self;
+        self.make_move(legal_move);
+        position_before
+    }
+    #[inline(always)]
+    fn unmake(&mut self, undo_info: UnwrappedFen) -> () {
+        *self = undo_info;
+    }
+    // Checkmate first, since mate on the 100th ply still counts. 
+    fn game_over(&self, legal_moves: &[Self::MoveRep]) -> Option<TerminalState> {
+        match self.terminal_state_given(legal_moves) {
+            Some(GameEnd::Checkmate) => Some(TerminalState::Lost),
+            Some(GameEnd::Stalemate) => Some(TerminalState::Drawn),
+            None => match self.time_up() {
+                true => Some(TerminalState::Drawn),
+                false => None,
+            },
+        }
+    }
+}
+
+// What the drafted eval traits in search.rs want from a gamestate. The methods are FENnec's, 
+// spelled out in full since both traits name them the same. 
+impl generic_search::UpdatesOnMove for UnwrappedFen {
+    type MoveRep = <UnwrappedFen as HasBoard>::MoveRep;
+
+    #[inline(always)]
+    fn make_move(&mut self, legal_move: Self::MoveRep) -> () {
+        <UnwrappedFen as FENnec>::make_move(self, legal_move)
+    }
+    #[inline(always)]
+    fn after_move(&self, legal_move: Self::MoveRep) -> Self {
+        <UnwrappedFen as FENnec>::after_move(self, legal_move)
+    }
+}
+
+impl generic_search::PseudolegalGeneratingGamestate for UnwrappedFen {
+    #[inline(always)]
+    fn get_pseudolegal_moves(&self) -> Vec<Self::MoveRep> {
+        self.get_pseudo_legal_proper_moves()
+    }
+    #[inline(always)]
+    fn check_remaining_legality(&self, pseudolegal_move: Self::MoveRep) -> bool {
+        <UnwrappedFen as FENnec>::check_remaining_legality(self, pseudolegal_move)
+    }
+}
+
+// Anything the search needs to know that isn't the position or the window.
+pub(crate) struct SearchContext {
+    pub(crate) root_color: EnumColor,
+    pub(crate) config: SearchConfig,
+    // Hashes of every position before the current node: the game so far, then the search 
+    // path down to the parent. 
+    pub(crate) path_history: Vec<u64>,
+    // Only ever off when measuring what it saves. 
+    pub(crate) use_mate_distance_pruning: bool,
+    // Don't bother searching rook and bishop promotions, since a queen does everything they 
+    // do. The only thing lost is the odd stalemate trick. Knight promotions are always kept. 
+    pub(crate) skip_rook_bishop_promotions: bool,
+    // Search one ply deeper whenever the side to move is in check. Off until it's been shown 
+    // to be worth it; MAX_PLY is what stops a long run of checks from going on forever. 
+    pub(crate) use_check_extensions: bool,
+    // Skip quiet moves near the leaves when the static eval is too far below alpha for a 
+    // quiet move to make up the difference. 
+    pub(crate) use_futility_pruning: bool,
+    // Finish the search at depth 0 with a captures-only quiescence search instead of taking 
+    // the static eval as it is. 
+    pub(crate) use_quiescence: bool,
+    // Drop captures in the quiescence search that can't get back up to alpha even if they 
+    // win their victim for free. 
+    pub(crate) use_delta_pruning: bool,
+    // Calls to alpha_beta so far, for info output and for comparing search tweaks. 
+    pub(crate) nodes: u64,
+    // One reusable move list per ply, so the search stops allocating once it has been 
+    // as deep as it's going to go. 
+    move_buffers: Vec<Vec<<UnwrappedFen as HasBoard>::MoveRep>>,
+    // When off, every node gets a fresh Vec instead. Off by default, since in 
+    // benchmarking_move_buffers the buffers don't beat the fresh Vecs by more than the noise. 
+    pub(crate) use_move_buffers: bool,
+    // Triangular PV table: the entry for a ply is the best line found so far from the node 
+    // being searched at that ply, starting with its own move. 
+    pv_table: Vec<Vec<<UnwrappedFen as HasBoard>::MoveRep>>,
+    // Set when the search is being run on a thread that someone else might want to stop. 
+    pub(crate) control: Option<Arc<SearchControl>>,
+    // Stop once this many nodes have been searched, for "go nodes". 
+    pub(crate) node_limit: Option<u64>,
+    // Once this is set every node returns straight away, and nothing the search returns 
+    // afterwards means anything. 
+    pub(crate) stopped: bool,
+}
+
+impl SearchContext {
+    // Default settings apart from the contempt, which is the one most callers care about. 
+    #[inline(always)]
+    pub(crate) fn new(root_color: EnumColor, contempt: i32) -> Self {
+        Self::with_config(root_color, SearchConfig { contempt: contempt, ..SearchConfig::default() })
+    }
+
+    pub(crate) fn with_config(root_color: EnumColor, config: SearchConfig) -> Self {
+        SearchContext {
+            root_color: root_color,
+            config: config,
+            path_history: Vec::new(),
+            use_mate_distance_pruning: true,
+            skip_rook_bishop_promotions: true,
+            use_check_extensions: false,
+            use_futility_pruning: true,
+            use_quiescence: true,
+            use_delta_pruning: true,
+            nodes: 0,
+            move_buffers: Vec::new(),
+            use_move_buffers: false,
+            pv_table: Vec::new(),
+            control: None,
+            node_limit: None,
+            stopped: false,
+        }
+    }
+
+    // Look at the SearchControl every so often, and remember if it said to stop. The node 
+    // limit is cheap enough to check at every node, so it gets hit exactly. 
+    #[inline(always)]
+    fn check_for_stop(&mut self) -> bool {
+        if self.node_limit.is_some_and(|node_limit| self.nodes > node_limit) {
+            self.stopped = true;
+        }
+        if !self.stopped && self.nodes % NODES_BETWEEN_STOP_CHECKS == 0 {
+            if let Some(control) = &self.control {
+                self.stopped = control.should_stop();
+            }
+        }
+        self.stopped
+    }
+
+    // Positions already played in the game, oldest first, so that the search knows 
+    // about repetitions that started before the root. 
+    pub(crate) fn set_game_history(&mut self, game_hashes: Vec<u64>) -> () {
+        self.path_history = game_hashes;
+    }
+
+    // Whether the position has been seen before since the last capture or pawn move. 
+    // A single earlier occurrence is enough: if repeating was good once, it's good 
+    // again, so a two-fold inside the search is as good as a draw. 
+    pub(crate) fn is_repetition(&self, position_hash: u64, reversible_plies: i16) -> bool {
+        self.path_history.iter().rev()
+            .take(reversible_plies.max(0) as usize)
+            .any(|earlier_hash| *earlier_hash == position_hash)
+    }
+
+    // Hands over the buffer for this ply, leaving an empty (unallocated) Vec in its place 
+    // until it comes back. 
+    fn take_move_buffer(&mut self, ply: i32) -> Vec<<UnwrappedFen as HasBoard>::MoveRep> {
+        if !self.use_move_buffers {
+            return Vec::new()
+        }
+        let ply = ply as usize;
+        if self.move_buffers.len() <= ply {
+            self.move_buffers.resize_with(ply + 1, Vec::new);
+        }
+        std::mem::take(&mut self.move_buffers[ply])
+    }
+
+    #[inline(always)]
+    fn return_move_buffer(&mut self, ply: i32, buffer: Vec<<UnwrappedFen as HasBoard>::MoveRep>) -> () {
+        if self.use_move_buffers {
+            self.move_buffers[ply as usize] = buffer;
+        }
+    }
+
+    // Every node starts out with no PV, which is also what it keeps if it fails low or 
+    // returns before searching anything. 
+    #[inline(always)]
+    fn clear_pv(&mut self, ply: i32) -> () {
+        let ply = ply as usize;
+        if self.pv_table.len() <= ply + 1 {
+            self.pv_table.resize_with(ply + 2, Vec::new);
+        }
+        self.pv_table[ply].clear();
+    }
+
+    // A move that raised alpha at this ply, followed by the line it was just searched with. 
+    fn update_pv(&mut self, ply: i32, best_move: <UnwrappedFen as HasBoard>::MoveRep) -> () {
+        let ply = ply as usize;
+        let (this_ply, deeper) = self.pv_table.split_at_mut(ply + 1);
+        this_ply[ply].clear();
+        this_ply[ply].push(best_move);
+        this_ply[ply].extend_from_slice(&deeper[0]);
+    }
+
+    // The value of a drawn node from the perspective of whoever is to move there.
+    #[inline(always)]
+    pub(crate) fn draw_score(&self, side_to_move: EnumColor) -> i32 {
+        match side_to_move == self.root_color {
+            true => -self.config.contempt,
+            false => self.config.contempt,
+        }
+    }
+}
+
+// Captures and promotions first, everything else after, with the king tweaks from 
+// king_safety_order_adjustment on top. mvv_lva_sort does some extra things (legality 
+// filtering, truncation) that the search doesn't want.
+pub(crate) fn order_moves(position: &UnwrappedFen, moves_list: &mut Vec<<UnwrappedFen as HasBoard>::MoveRep>) {
+    moves_list.sort_by_key(|possible_move| -(mvv_lva_score(position, *possible_move).saturating_add(king_safety_order_adjustment(position, *possible_move))));
+}
+
+// Never empties a nonempty list, because wherever a rook or bishop can be promoted to, so 
+// can a queen. 
+fn drop_rook_bishop_promotions(moves_list: &mut Vec<<UnwrappedFen as HasBoard>::MoveRep>) {
+    moves_list.retain(|possible_move| match possible_move {
+        ChessMove::PromotionMove(promotion_move) => match promotion_move.promotion_choice.get_piece_type() {
+            EnumPiecesUncolored::Rook | EnumPiecesUncolored::Bishop => false,
+            _ => true,
+        },
+        _ => true,
+    });
+}
+
+// Fail-soft alpha-beta. Scores are from the side to move's perspective, and
+// ply is the distance from the root (used to prefer shorter mates).
+pub(crate) fn alpha_beta(position: & UnwrappedFen, depth: i8, mut alpha: i32, mut beta: i32, ply: i32, context: &mut SearchContext) -> i32 {
+    context.nodes += 1;
+    context.clear_pv(ply);
+    if context.check_for_stop() {
+        return 0
+    }
+
+    // Mate distance pruning. Nothing below this node can do better than mating on the very 
+    // next ply or worse than getting mated right here, so if a shorter mate is already known 
+    // somewhere above, there's no point looking for a longer one. 
+    if context.use_mate_distance_pruning && ply > 0 {
+        alpha = alpha.max(-MATE_SCORE + ply);
+        beta = beta.min(MATE_SCORE - ply - 1);
+        if alpha >= beta {
+            return alpha
+        }
+    }
+
+    let mut legal_moves = context.take_move_buffer(ply);
+    position.write_legal_proper_moves(&mut legal_moves);
+    let score = search_node(position, &mut legal_moves, depth, alpha, beta, ply, context);
+    context.return_move_buffer(ply, legal_moves);
+    score
+}
+
+// The game-over checks that need nothing but the node itself: mate, stalemate and dead 
+// positions. Repetitions and the 50mr depend on how the game got here, so they're left to 
+// search_node, which has the history. 
+pub(crate) fn node_terminal_score(position: &UnwrappedFen, legal_moves: &[<UnwrappedFen as HasBoard>::MoveRep], ply: i32, context: &SearchContext) -> Option<i32> {
+    match position.terminal_state_given(legal_moves) {
+        Some(GameEnd::Checkmate) => Some(-MATE_SCORE + ply),
+        Some(GameEnd::Stalemate) => Some(context.draw_score(position.get_color())),
+        None => match position.is_insufficient_material() {
+            true => Some(context.draw_score(position.get_color())),
+            false => None,
+        },
+    }
+}
+
+// Everything in alpha_beta after the moves are generated. Split out so that every early 
+// return still hands the move buffer back. 
+fn search_node(position: & UnwrappedFen, legal_moves: &mut Vec<<UnwrappedFen as HasBoard>::MoveRep>, depth: i8, mut alpha: i32, beta: i32, ply: i32, context: &mut SearchContext) -> i32 {
+    // Checkmate takes priority over the 50mr, but stalemate and the 50mr are both just draws.
+    if let Some(terminal_score) = node_terminal_score(position, legal_moves, ply, context) {
+        return terminal_score
+    }
+    if position.time_up() {
+        return context.draw_score(position.get_color())
+    }
+    let position_hash = position.zobrist_hash();
+    if ply > 0 && context.is_repetition(position_hash, position.get_ply_count()) {
+        return context.draw_score(position.get_color())
+    }
+
+    let in_check = position.mover_in_check();
+    let depth = match context.use_check_extensions && in_check {
+        true => depth.saturating_add(1),
+        false => depth,
+    };
+    if ply >= MAX_PLY {
+        return hce_stm(position)
+    }
+    if depth <= 0 {
+        return match context.use_quiescence {
+            true => quiescence_node(position, legal_moves, alpha, beta, ply, context),
+            false => hce_stm(position),
+        }
+    }
+
+    // Nothing gets pruned in check, where every evasion might be the only one, or once a mate 
+    // has been found, since the margins mean nothing next to a mate score. The margins are wide 
+    // enough that the fast eval does here. 
+    let futility_margins = context.config.futility_margins;
+    let futility_bound = match context.use_futility_pruning && !in_check && (depth as usize) < futility_margins.len() && alpha.abs() < MATE_THRESHOLD {
+        true => Some(fast_material_eval(position) + futility_margins[depth as usize]),
+        false => None,
+    };
+
+    if context.skip_rook_bishop_promotions {
+        drop_rook_bishop_promotions(legal_moves);
+    }
+    order_moves(position, legal_moves);
+
+    context.path_history.push(position_hash);
+    let mut best_score = i32::MIN + 1;
+    for legal_move in legal_moves.iter().copied() {
+        if let Some(bound) = futility_bound {
+            if bound <= alpha && position.is_quiet(legal_move) {
+                // The bound is what the move is assumed to be worth, so if everything gets 
+                // pruned the node still fails low with a sensible score. 
+                best_score = best_score.max(bound);
+                continue;
+            }
+        }
+        let score = -alpha_beta(&position.after_move(legal_move), depth - 1, -beta, -alpha, ply + 1, context);
+        if context.stopped {
+            break;
+        }
+        if score > best_score {
+            best_score = score;
+            if score > alpha {
+                alpha = score;
+                context.update_pv(ply, legal_move);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+    }
+    context.path_history.pop();
+    best_score
+}
+
+// The most a move can win in material: whatever it takes, plus the promotion if there is one. 
+fn delta_gain(position: &UnwrappedFen, possible_move: <UnwrappedFen as HasBoard>::MoveRep) -> i32 {
+    match possible_move {
+        ChessMove::StandardMove(standard_move) => match position.query_square(standard_move.to_square).get_contents() {
+            None => 0,
+            Some(piece) => get_piece_value(piece.get_piece_type()) as i32,
+        },
+        ChessMove::PromotionMove(promotion_move) => {
+            let victim_value = match position.query_square(promotion_move.to_square).get_contents() {
+                None => 0,
+                Some(piece) => get_piece_value(piece.get_piece_type()) as i32,
+            };
+            victim_value + (get_piece_value(promotion_move.promotion_choice.get_piece_type()) - get_piece_value(EnumPiecesUncolored::Pawn)) as i32
+        },
+        ChessMove::EnPassantMove(_) => get_piece_value(EnumPiecesUncolored::Pawn) as i32,
+        _ => 0,
+    }
+}
+
+// Captures and promotions only, so that the search doesn't stop and evaluate in the middle of 
+// an exchange. The side to move can always stand pat on the static eval instead, except in 
+// check, where every evasion gets searched. Same conventions as alpha_beta. 
+pub(crate) fn quiescence(position: & UnwrappedFen, alpha: i32, beta: i32, ply: i32, context: &mut SearchContext) -> i32 {
+    context.nodes += 1;
+    context.clear_pv(ply);
+    if context.check_for_stop() {
+        return 0
+    }
+    let mut legal_moves = context.take_move_buffer(ply);
+    position.write_legal_proper_moves(&mut legal_moves);
+    let score = quiescence_node(position, &mut legal_moves, alpha, beta, ply, context);
+    context.return_move_buffer(ply, legal_moves);
+    score
+}
+
+// As with search_node, everything after the moves are generated. 
+fn quiescence_node(position: & UnwrappedFen, legal_moves: &mut Vec<<UnwrappedFen as HasBoard>::MoveRep>, mut alpha: i32, beta: i32, ply: i32, context: &mut SearchContext) -> i32 {
+    if let Some(terminal_score) = node_terminal_score(position, legal_moves, ply, context) {
+        return terminal_score
+    }
+    let stand_pat = hce_stm(position);
+    if ply >= MAX_PLY {
+        return stand_pat
+    }
+
+    let in_check = position.mover_in_check();
+    let mut best_score = i32::MIN + 1;
+    if !in_check {
+        if stand_pat >= beta {
+            return stand_pat
+        }
+        best_score = stand_pat;
+        alpha = alpha.max(stand_pat);
+    }
+    position.retain_quiescence_moves(legal_moves);
+    if context.skip_rook_bishop_promotions {
+        drop_rook_bishop_promotions(legal_moves);
+    }
+    order_moves(position, legal_moves);
+
+    for legal_move in legal_moves.iter().copied() {
+        // Delta pruning. In check there's no stand pat to measure against, and a capture that 
+        // gives check can be worth a lot more than its victim. 
+        if context.use_delta_pruning && !in_check {
+            let best_case = stand_pat + delta_gain(position, legal_move) + context.config.delta_margin;
+            if best_case <= alpha && !position.gives_check(legal_move) {
+                best_score = best_score.max(best_case);
+                continue;
+            }
+        }
+        let score = -quiescence(&position.after_move(legal_move), -beta, -alpha, ply + 1, context);
+        if context.stopped {
+            break;
+        }
+        if score > best_score {
+            best_score = score;
+            if score > alpha {
+                alpha = score;
+                context.update_pv(ply, legal_move);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+    }
+    best_score
+}
+
+// Root of the search. Returns None when there's nothing to play.
+pub(crate) fn search_best_move(position: &UnwrappedFen, depth: i8, context: &mut SearchContext) -> Option<(<UnwrappedFen as HasBoard>::MoveRep, i32)> {
+    let ordered_moves = root_moves(position, None, context);
+    search_root_lines(position, &ordered_moves, depth, 1, context).first().map(|line| (line.best_move, line.score))
+}
+
+// One line of a root search: a root move, its score, and the moves the search expects to 
+// follow, starting with the root move itself. 
+#[derive(Clone, Debug)]
+pub(crate) struct SearchLine {
+    pub(crate) best_move: <UnwrappedFen as HasBoard>::MoveRep,
+    pub(crate) score: i32,
+    pub(crate) pv: Vec<<UnwrappedFen as HasBoard>::MoveRep>,
+}
+
+// Everything worth searching at the root, best guesses first. A restriction (from UCI's
+// searchmoves) keeps just the legal moves it names, underpromotions included since someone 
+// asked for them. If it names no legal moves at all it's ignored. 
+fn root_moves(position: &UnwrappedFen, root_restriction: Option<&[<UnwrappedFen as HasBoard>::MoveRep]>, context: &SearchContext) -> Vec<<UnwrappedFen as HasBoard>::MoveRep> {
+    let mut ordered_moves = position.get_legal_proper_moves();
+    let restricted = match root_restriction {
+        None => false,
+        Some(allowed_moves) => {
+            let allowed_strings: Vec<String> = allowed_moves.iter().map(|allowed_move| allowed_move.to_uci_string()).collect();
+            let restricted_moves: Vec<_> = ordered_moves.iter().copied()
+                .filter(|legal_move| allowed_strings.contains(&legal_move.to_uci_string()))
+                .collect();
+            match restricted_moves.is_empty() {
+                true => false,
+                false => {
+                    ordered_moves = restricted_moves;
+                    true
+                },
+            }
+        },
+    };
+    if context.skip_rook_bishop_promotions && !restricted {
+        drop_rook_bishop_promotions(&mut ordered_moves);
+    }
+    order_moves(position, &mut ordered_moves);
+    ordered_moves
+}
+
+// The line_count best of the given root moves, best first, in a single pass. Each move is 
+// searched with alpha at the score of the worst line kept so far, so a move only gets an 
+// exact score (and a PV) when it's good enough to be one of the lines. Until there are 
+// line_count lines every move is searched with the full window. Fewer lines come back if 
+// there aren't enough moves, or if the search gets stopped partway. 
+fn search_root_lines(position: &UnwrappedFen, root_moves: &[<UnwrappedFen as HasBoard>::MoveRep], depth: i8, line_count: usize, context: &mut SearchContext) -> Vec<SearchLine> {
+    context.path_history.push(position.zobrist_hash());
+    let mut lines: Vec<SearchLine> = Vec::new();
+    for legal_move in root_moves.iter().copied() {
+        let alpha = match lines.len() < line_count {
+            true => i32::MIN + 1,
+            false => lines[line_count - 1].score,
+        };
+        let score = -alpha_beta(&position.after_move(legal_move), depth - 1, i32::MIN + 1, -alpha, 1, context);
+        if context.stopped {
+            break;
+        }
+        if score > alpha || lines.len() < line_count {
+            let mut pv = vec![legal_move];
+            pv.extend_from_slice(&context.pv_table[1]);
+            // Ties go to whichever move was searched first, as they would with one line. 
+            let insert_at = lines.iter().take_while(|line| line.score >= score).count();
+            lines.insert(insert_at, SearchLine { best_move: legal_move, score: score, pv: pv });
+            lines.truncate(line_count);
+        }
+    }
+    context.path_history.pop();
+    lines
+}
+
+// The pv_count best root moves with their PVs, best first, for MultiPV. 
+pub(crate) fn search_best_moves(position: &UnwrappedFen, depth: i8, pv_count: usize, root_restriction: Option<&[<UnwrappedFen as HasBoard>::MoveRep]>, context: &mut SearchContext) -> Vec<SearchLine> {
+    let ordered_moves = root_moves(position, root_restriction, context);
+    search_root_lines(position, &ordered_moves, depth, pv_count, context)
+}
+
+// Search at depth 1, 2, ... up to max_depth, or until the context is stopped. An iteration 
+// that gets cut off partway is thrown away, so the answer always comes from the deepest 
+// iteration that finished. report gets told about each finished iteration as 
+// (depth, best move, score, nodes so far). If not even depth 1 finishes, any legal move 
+// is better than nothing. With a root restriction only those moves are considered, as in 
+// root_moves. 
+pub(crate) fn iterative_deepening<F>(position: &UnwrappedFen, max_depth: i8, root_restriction: Option<&[<UnwrappedFen as HasBoard>::MoveRep]>, context: &mut SearchContext, mut report: F) -> Option<(<UnwrappedFen as HasBoard>::MoveRep, i32)>
+where F: FnMut(i8, <UnwrappedFen as HasBoard>::MoveRep, i32, u64) -> () {
+    iterative_deepening_multipv(position, max_depth, 1, root_restriction, context, |depth, lines, nodes| report(depth, lines[0].best_move, lines[0].score, nodes))
+        .first().map(|line| (line.best_move, line.score))
+}
+
+// The same, keeping the pv_count best root moves at each depth. report gets every line of 
+// each finished iteration at once, best first. 
+pub(crate) fn iterative_deepening_multipv<F>(position: &UnwrappedFen, max_depth: i8, pv_count: usize, root_restriction: Option<&[<UnwrappedFen as HasBoard>::MoveRep]>, context: &mut SearchContext, mut report: F) -> Vec<SearchLine>
+where F: FnMut(i8, &[SearchLine], u64) -> () {
+    let mut best_found = Vec::new();
+    for depth in 1..=max_depth.max(1) {
+        let iteration_result = search_best_moves(position, depth, pv_count.max(1), root_restriction, context);
+        if context.stopped || iteration_result.is_empty() {
+            break;
+        }
+        best_found = iteration_result;
+        if let Some(control) = &context.control {
+            control.report_progress(depth as i32, context.nodes);
+        }
+        report(depth, &best_found, context.nodes);
+    }
+    match best_found.is_empty() {
+        false => best_found,
+        true => root_moves(position, root_restriction, context).first().map(|legal_move| vec![SearchLine { best_move: *legal_move, score: 0, pv: vec![*legal_move] }]).unwrap_or_default(),
+    }
+}
+
+// "go mate": iterative deepening that stops as soon as an iteration proves a mate in at most 
+// max_moves, which can need up to 2 * max_moves - 1 plies. Anything that could skip the 
+// defender's only way out (futility pruning, leaving out underpromotions) is off for the 
+// duration, so a mate score reported here really is forced. If there's no such mate, or the 
+// search gets stopped first, this hands back the deepest iteration's answer, the same as 
+// iterative_deepening; check mate_in_moves on the score to tell. 
+pub(crate) fn search_for_mate<F>(position: &UnwrappedFen, max_moves: i32, root_restriction: Option<&[<UnwrappedFen as HasBoard>::MoveRep]>, context: &mut SearchContext, mut report: F) -> Option<(<UnwrappedFen as HasBoard>::MoveRep, i32)>
+where F: FnMut(i8, <UnwrappedFen as HasBoard>::MoveRep, i32, u64) -> () {
+    let saved_settings = (context.use_futility_pruning, context.skip_rook_bishop_promotions);
+    context.use_futility_pruning = false;
+    context.skip_rook_bishop_promotions = false;
+    let max_depth = (2 * max_moves - 1).clamp(1, MAX_PLY - 1) as i8;
+    let mut best_found = None;
+    for depth in 1..=max_depth {
+        let iteration_result = search_best_moves(position, depth, 1, root_restriction, context);
+        if context.stopped || iteration_result.is_empty() {
+            break;
+        }
+        let (best_move, score) = (iteration_result[0].best_move, iteration_result[0].score);
+        best_found = Some((best_move, score));
+        if let Some(control) = &context.control {
+            control.report_progress(depth as i32, context.nodes);
+        }
+        report(depth, best_move, score, context.nodes);
+        if matches!(mate_in_moves(score), Some(moves) if moves > 0 && moves <= max_moves) {
+            break;
+        }
+    }
+    (context.use_futility_pruning, context.skip_rook_bishop_promotions) = saved_settings;
+    best_found.or_else(|| root_moves(position, root_restriction, context).first().map(|legal_move| (*legal_move, 0)))
+}
@@ -0,0 +1,135 @@
+// Transposition table. Nothing here shares a lock, so a later Lazy SMP can hand the same 
+// table to every thread. Each entry is a pair of AtomicU64s, one holding the packed data and 
+// the other holding the key xored with that data (the old "lockless hashing" trick). A read 
+// that races a write sees a key and data that don't belong together and just misses, rather 
+// than handing back somebody else's half-written entry. 
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use super::packed_move_code::PackedMove;
+
+// Whether the stored score is the real value, or just a bound on it. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+// What actually gets stored. The table never looks inside best_move; unpacking it needs 
+// the position, which the table doesn't have. 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct TTData {
+    pub(crate) score: i32,
+    pub(crate) depth: i8,
+    pub(crate) bound: Bound,
+    pub(crate) best_move: PackedMove,
+}
+
+// Layout: bits 0-15 move, 16-23 depth, 24-25 bound, 32-63 score. The bound bits are 
+// never zero for a real entry, so an all-zero data word always means an empty slot. 
+impl TTData {
+    #[inline(always)]
+    fn pack(&self) -> u64 {
+        let bound_bits: u64 = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+        (self.best_move.0 as u64) 
+            | ((self.depth as u8 as u64) << 16) 
+            | (bound_bits << 24) 
+            | ((self.score as u32 as u64) << 32)
+    }
+    #[inline(always)]
+    fn unpack(packed: u64) -> Option<Self> {
+        let bound = match (packed >> 24) & 3 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        Some(TTData {
+            score: (packed >> 32) as u32 as i32,
+            depth: ((packed >> 16) & 0xFF) as u8 as i8,
+            bound: bound,
+            best_move: PackedMove((packed & 0xFFFF) as u16),
+        })
+    }
+}
+
+#[derive(Default)]
+struct TTEntry {
+    checked_key: AtomicU64,
+    data: AtomicU64,
+}
+
+// Always-replace for now. Depth-preferred buckets can come once the search actually 
+// leans on the table. 
+pub(crate) struct TranspositionTable {
+    entries: Box<[TTEntry]>,
+}
+
+const ENTRY_BYTES: usize = std::mem::size_of::<TTEntry>();
+
+// Printing every entry would be no use to anyone. 
+impl std::fmt::Debug for TranspositionTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TranspositionTable {{ capacity: {} }}", self.capacity())
+    }
+}
+
+impl TranspositionTable {
+    // Rounds down to a power of two entries so indexing is just a mask. 
+    pub(crate) fn new(size_mb: usize) -> Self {
+        let wanted_entries = (size_mb * 1024 * 1024 / ENTRY_BYTES).max(1);
+        Self::with_entries(1usize << (usize::BITS - 1 - wanted_entries.leading_zeros()))
+    }
+
+    // entry_count had better be a power of two. 
+    pub(crate) fn with_entries(entry_count: usize) -> Self {
+        let mut entries = Vec::with_capacity(entry_count);
+        entries.resize_with(entry_count, TTEntry::default);
+        TranspositionTable { entries: entries.into_boxed_slice() }
+    }
+
+    #[inline(always)]
+    pub(crate) fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline(always)]
+    fn entry_for(&self, key: u64) -> &TTEntry {
+        &self.entries[(key as usize) & (self.entries.len() - 1)]
+    }
+
+    // Relaxed is enough: the xor check is what catches torn entries, not the ordering. 
+    pub(crate) fn store(&self, key: u64, data: TTData) -> () {
+        let packed = data.pack();
+        let entry = self.entry_for(key);
+        entry.checked_key.store(key ^ packed, Ordering::Relaxed);
+        entry.data.store(packed, Ordering::Relaxed);
+    }
+
+    pub(crate) fn probe(&self, key: u64) -> Option<TTData> {
+        let entry = self.entry_for(key);
+        let packed = entry.data.load(Ordering::Relaxed);
+        let checked_key = entry.checked_key.load(Ordering::Relaxed);
+        match checked_key ^ packed == key {
+            true => TTData::unpack(packed),
+            false => None,
+        }
+    }
+
+    // Takes &mut so nobody can be searching while it happens. 
+    pub(crate) fn clear(&mut self) -> () {
+        for entry in self.entries.iter_mut() {
+            *entry.checked_key.get_mut() = 0;
+            *entry.data.get_mut() = 0;
+        }
+    }
+
+    // How many entries are in use, for debugging and for tests. 
+    pub(crate) fn occupied(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.data.load(Ordering::Relaxed) != 0).count()
+    }
+}
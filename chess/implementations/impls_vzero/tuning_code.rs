@@ -0,0 +1,131 @@
+// Texel-style tuning support for EvalWeights. A dataset is a list of positions labeled with 
+// how the game they came from ended, and the loss is the mean squared error between those 
+// results and a logistic squashing of hce_stm_weighted. Nothing here needs gradients, so 
+// any tuner that can call tuning_loss can use it. The little coordinate descent over 
+// material values at the bottom is mostly there to show that it works. 
+
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use crate::value::WIN_PROBABILITY_SCALE;
+use super::{board_rep::*, eval_code::*};
+
+// A few obviously-decided (and obviously-drawn) positions, for sanity checks rather than 
+// for tuning anything real. 
+pub(crate) const SAMPLE_TUNING_DATA: &str = "\
+4k3/8/8/8/8/8/8/3QK3 w - - 0 1 [1.0]
+3qk3/8/8/8/8/8/8/4K3 w - - 0 1 [0.0]
+4k3/8/8/8/8/8/8/R3K3 b - - 0 1 [1.0]
+r3k3/8/8/8/8/8/8/4K3 b - - 0 1 [0.0]
+4k3/ppp5/8/8/8/8/PPPPP3/4K3 w - - 0 1 [1.0]
+4k3/ppppp3/8/8/8/8/PPP5/4K3 b - - 0 1 [0.0]
+4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1 [0.5]
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.5]
+";
+
+// Result is from white's perspective: 1 for a white win, 0.5 for a draw, 0 for a loss. 
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TuningPosition {
+    pub(crate) position: UnwrappedFen,
+    pub(crate) result: f64,
+}
+
+// Accepts the usual spellings: [1.0], [0.5], [0.0], and "1-0"/"1/2-1/2"/"0-1" with or 
+// without quotes and a trailing semicolon. 
+fn parse_result(result_str: &str) -> Option<f64> {
+    match result_str.trim_matches(|c| c == '[' || c == ']' || c == '"' || c == ';') {
+        "1.0" | "1" | "1-0" => Some(1.0),
+        "0.5" | "1/2-1/2" => Some(0.5),
+        "0.0" | "0" | "0-1" => Some(0.0),
+        _ => None,
+    }
+}
+
+// One labeled position per line, FEN first and result last. Blank lines and lines 
+// starting with # are skipped. 
+pub(crate) fn load_tuning_positions(dataset_text: &str) -> Result<Vec<TuningPosition>, String> {
+    let mut dataset = Vec::new();
+    for (line_number, line) in dataset_text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue
+        }
+        let (fen_part, result_part) = match line.rsplit_once(char::is_whitespace) {
+            None => return Err(format!("Line {}: expected a FEN followed by a result", line_number + 1)),
+            Some(split_line) => split_line,
+        };
+        let result = match parse_result(result_part) {
+            None => return Err(format!("Line {0}: could not read the result \"{1}\"", line_number + 1, result_part)),
+            Some(result) => result,
+        };
+        let position = match UnwrappedFen::from_fen(fen_part.trim()) {
+            Err(message) => return Err(format!("Line {0}: {1}", line_number + 1, message)),
+            Ok(position) => position,
+        };
+        dataset.push(TuningPosition { position: position, result: result });
+    }
+    Ok(dataset)
+}
+
+pub(crate) fn load_tuning_file(path: &str) -> Result<Vec<TuningPosition>, String> {
+    match std::fs::read_to_string(path) {
+        Err(error) => Err(format!("Could not read {0}: {1}", path, error)),
+        Ok(dataset_text) => load_tuning_positions(&dataset_text),
+    }
+}
+
+// Expected score for white given a white-perspective eval. K stretches the curve to 
+// fit whatever dataset is being used. 
+#[inline(always)]
+fn expected_result(white_eval: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * (white_eval as f64) / WIN_PROBABILITY_SCALE))
+}
+
+// Mean squared error of the eval's predictions over the dataset. Lower is better. 
+pub(crate) fn tuning_loss(dataset: &[TuningPosition], weights: &EvalWeights, k: f64) -> f64 {
+    if dataset.is_empty() {
+        return 0.0
+    }
+    let mut total_error = 0.0;
+    for tuning_position in dataset {
+        let stm_eval = hce_stm_weighted(&tuning_position.position, weights);
+        let white_eval = match tuning_position.position.get_color() {
+            EnumColor::White => stm_eval,
+            EnumColor::Black => -stm_eval,
+        };
+        let error = tuning_position.result - expected_result(white_eval, k);
+        total_error += error * error;
+    }
+    total_error / (dataset.len() as f64)
+}
+
+// Plain coordinate descent on the piece values (kings excluded): nudge each one up or 
+// down by step and keep the change if the loss went down, until a whole pass changes 
+// nothing or max_passes runs out. 
+pub(crate) fn tune_material_values(dataset: &[TuningPosition], start: &EvalWeights, k: f64, step: i32, max_passes: usize) -> EvalWeights {
+    let mut best_weights = *start;
+    let mut best_loss = tuning_loss(dataset, &best_weights, k);
+    for _pass in 0..max_passes {
+        let mut improved = false;
+        for piece_number in 0..5 {
+            for is_endgame in [false, true] {
+                for delta in [step, -step] {
+                    let mut candidate = best_weights;
+                    match is_endgame {
+                        false => candidate.mg_values[piece_number] += delta,
+                        true => candidate.eg_values[piece_number] += delta,
+                    }
+                    let candidate_loss = tuning_loss(dataset, &candidate, k);
+                    if candidate_loss < best_loss {
+                        best_weights = candidate;
+                        best_loss = candidate_loss;
+                        improved = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    best_weights
+}
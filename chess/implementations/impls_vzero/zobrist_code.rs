@@ -0,0 +1,100 @@
+// Zobrist hashing for UnwrappedFen. The hash only covers what makes two positions the same 
+// for repetition purposes: pieces, side to move, castling rights and a real en passant 
+// capture. The ply and move counters are deliberately left out. 
+
+use crate::chess::abstracts::{helper_types::*, helper_traits::*};
+use super::board_rep::*;
+
+// Same xorshift as the one in movegen, but usable in a const context so the keys can be 
+// baked in at compile time. The seed is arbitrary. 
+const fn next_key(state: u64) -> u64 {
+    let mut state = state;
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    state
+}
+
+struct ZobristKeys {
+    // Indexed by the raw i8 piece encoding, then by square. 
+    pieces: [[u64; 64]; 12],
+    // Castling rights are keyed by the rook's starting square, which is different for every 
+    // right even in 960. That way the order the rights are stored in doesn't matter. 
+    castling: [u64; 64],
+    ep_files: [u64; 8],
+    black_to_move: u64,
+}
+
+const fn build_keys() -> ZobristKeys {
+    let mut keys = ZobristKeys {
+        pieces: [[0; 64]; 12],
+        castling: [0; 64],
+        ep_files: [0; 8],
+        black_to_move: 0,
+    };
+    let mut state = 0x2545_F491_4F6C_DD1D;
+    let mut piece_number = 0;
+    while piece_number < 12 {
+        let mut square_index = 0;
+        while square_index < 64 {
+            state = next_key(state);
+            keys.pieces[piece_number][square_index] = state;
+            square_index += 1;
+        }
+        piece_number += 1;
+    }
+    let mut square_index = 0;
+    while square_index < 64 {
+        state = next_key(state);
+        keys.castling[square_index] = state;
+        square_index += 1;
+    }
+    let mut file_index = 0;
+    while file_index < 8 {
+        state = next_key(state);
+        keys.ep_files[file_index] = state;
+        file_index += 1;
+    }
+    keys.black_to_move = next_key(state);
+    keys
+}
+
+const ZOBRIST_KEYS: ZobristKeys = build_keys();
+
+impl UnwrappedFen {
+    // Recomputed from scratch every time. Fine for now, since nothing calls it per node 
+    // more than once. 
+    pub(crate) fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for square_index in 0..64 {
+            let contents = self.board[square_index];
+            if contents >= 0 {
+                hash ^= ZOBRIST_KEYS.pieces[contents as usize][square_index];
+            }
+        }
+        for castling_data in self.raw_castling_data {
+            if let Some((_king_from, rook_from, _king_to, _rook_to)) = castling_data {
+                hash ^= ZOBRIST_KEYS.castling[rook_from as usize];
+            }
+        }
+        // An ep square nobody can capture onto doesn't change anything about the position, 
+        // and hashing it anyway would hide repetitions after every double push. 
+        if self.has_likely_ep_capture() {
+            hash ^= ZOBRIST_KEYS.ep_files[(self.ep_data % 8) as usize];
+        }
+        if self.get_color() == EnumColor::Black {
+            hash ^= ZOBRIST_KEYS.black_to_move;
+        }
+        hash
+    }
+
+    // The same test as push_likely_ep_moves, a pawn of the side to move right next to the 
+    // pawn that just double pushed, but without building the moves. 
+    fn has_likely_ep_capture(&self) -> bool {
+        let Some((taken_square, _ep_square)) = self.try_get_ep_square() else { return false };
+        let own_pawn = i8::build_piece(self.get_color(), EnumPiecesUncolored::Pawn);
+        let taken_file = taken_square % 8;
+        (taken_file > 0 && self.board[(taken_square - 1) as usize] == own_pawn)
+            || (taken_file < 7 && self.board[(taken_square + 1) as usize] == own_pawn)
+    }
+}
@@ -1,6 +1,9 @@
 
 #![allow(dead_code)]
-use std::{str::FromStr, time::Duration};
+use std::{str::FromStr, sync::{Arc, atomic::{AtomicBool, Ordering}}, thread::JoinHandle, time::{Duration, Instant}};
+
+use super::abstracts::{helper_types::*, helper_traits::*};
+use super::implementations::impls_vzero::{board_rep::UnwrappedFen, search_code::*, ttable_code::TranspositionTable};
 
 #[derive(Debug, Clone, Copy)]
 struct Uci {} // Dummy type so I can do stuff like `Uci::parse_command`. 
@@ -19,14 +22,14 @@ enum UciRegisterOption {
 }
 
 #[derive(Debug, Clone, Default)]
-enum UciPositionOption {
+pub(crate) enum UciPositionOption {
     #[default]
     StartPos,
     FEN(Box<str>),
 }
 
 #[derive(Debug, Clone, Copy)]
-enum UciMove {
+pub(crate) enum UciMove {
     NullMove,
     StdMove(char,char,char,char),
     Promotion(char,char,char,char,char),
@@ -42,7 +45,7 @@ impl FromStr for UciMove {
         let mut char2 = 'z';
         let mut char3 = 'z';
         let mut char4 = 'z';
-        while let Some((place, char)) = s.char_indices().next() {
+        for (place, char) in s.char_indices() {
             match place {
                 0 => match 'a' <= char && char <= 'h' {
                     true => char0 = char,
@@ -75,6 +78,55 @@ impl FromStr for UciMove {
     }
 }
 
+impl ToString for UciMove {
+    fn to_string(&self) -> String {
+        match *self {
+            UciMove::NullMove => "0000".to_string(),
+            UciMove::StdMove(char0, char1, char2, char3) => [char0, char1, char2, char3].iter().collect(),
+            UciMove::Promotion(char0, char1, char2, char3, char4) => [char0, char1, char2, char3, char4].iter().collect(),
+        }
+    }
+}
+
+// Find the legal move the GUI means. Going through the legal move list rather than 
+// building the move directly means illegal moves get caught for free. 
+fn uci_move_to_chess_move(position: &UnwrappedFen, uci_move: &UciMove) -> Result<ChessMove<i8, i8>, String> {
+    let move_string = uci_move.to_string();
+    for legal_move in position.get_legal_proper_moves() {
+        if legal_move.to_uci_string() == move_string {
+            return Ok(legal_move)
+        }
+    }
+    Err(format!("Illegal move {} in the moves list", move_string))
+}
+
+// Play out the "moves ..." part of a position command. 
+pub(crate) fn apply_uci_moves(position: UnwrappedFen, uci_moves: &[UciMove]) -> Result<UnwrappedFen, String> {
+    let mut curr_pos = position;
+    for uci_move in uci_moves {
+        let chess_move = uci_move_to_chess_move(&curr_pos, uci_move)?;
+        curr_pos.make_move(chess_move);
+    }
+    Ok(curr_pos)
+}
+
+// The whole position command: starting position, then moves. Also gives the hashes of every 
+// position the moves went through before the last one, oldest first, which is the game 
+// history the search wants for spotting repetitions. 
+pub(crate) fn position_from_uci(position_option: &UciPositionOption, uci_moves: &[UciMove]) -> Result<(UnwrappedFen, Vec<u64>), String> {
+    let mut curr_pos = match position_option {
+        UciPositionOption::StartPos => UnwrappedFen::startpos(),
+        UciPositionOption::FEN(fen_str) => UnwrappedFen::from_fen(fen_str)?,
+    };
+    let mut game_history = Vec::with_capacity(uci_moves.len());
+    for uci_move in uci_moves {
+        let chess_move = uci_move_to_chess_move(&curr_pos, uci_move)?;
+        game_history.push(curr_pos.zobrist_hash());
+        curr_pos.make_move(chess_move);
+    }
+    Ok((curr_pos, game_history))
+}
+
 #[derive(Debug, Clone, Default)]
 enum UciSearchMoveSetting {
     #[default]
@@ -112,6 +164,40 @@ struct UciGoSettings{
     search_mode: UciSearchMode,
     time_info: UciTimeInfo,
     search_limiter: UciSearchLimiter, // All fields will be `None` if "infinite" search is selected. 
+    // No bestmove until "stop", even if the search runs out of things to do first. 
+    infinite: bool,
+}
+
+impl UciTimeInfo {
+    // Nothing clever yet: an even share of the clock over the moves left (guessing 30 when the 
+    // GUI doesn't say), plus most of the increment, and never more than half of what's left. 
+    fn time_budget(&self, color: EnumColor) -> Option<Duration> {
+        let (time_left, increment) = match color {
+            EnumColor::White => (self.white_time, self.white_increment),
+            EnumColor::Black => (self.black_time, self.black_increment),
+        };
+        let time_left = time_left?;
+        let moves_left = self.moves_to_go.filter(|moves_to_go| *moves_to_go > 0).unwrap_or(30) as u32;
+        let budget = time_left / moves_left + increment.unwrap_or(Duration::ZERO) * 3 / 4;
+        Some(budget.min(time_left / 2))
+    }
+}
+
+impl UciGoSettings {
+    // movetime wins over the clock if the GUI sends both, and infinite wins over both. 
+    fn time_budget(&self, color: EnumColor) -> Option<Duration> {
+        if self.infinite {
+            return None
+        }
+        self.search_limiter.time.or_else(|| self.time_info.time_budget(color))
+    }
+
+    fn max_depth(&self) -> i8 {
+        match self.search_limiter.depth {
+            None => MAX_UCI_DEPTH,
+            Some(depth) => depth.min(MAX_UCI_DEPTH as u16) as i8,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -199,13 +285,40 @@ impl FromStr for UciGuiCommand {
                                 register_code += code_word;
                                 register_code += " ";
                             }
-                            return Ok(UciGuiCommand::Register(UciRegisterOption::Name(register_code.trim().to_string().into_boxed_str())));
+                            return Ok(UciGuiCommand::Register(UciRegisterOption::Code(register_code.trim().to_string().into_boxed_str())));
                         },
                         _ => {},
                     }
                 },
                 "ucinewgame" => return Ok(UciGuiCommand::NewGame),
-                "position" => todo!(),
+                "position" => {
+                    let position_option = match words.next() {
+                        Some("startpos") => UciPositionOption::StartPos,
+                        Some("fen") => {
+                            let mut fen_string = String::from("");
+                            while let Some(&fen_word) = words.peek() {
+                                if fen_word == "moves" {
+                                    break;
+                                }
+                                fen_string += fen_word;
+                                fen_string += " ";
+                                words.next();
+                            }
+                            UciPositionOption::FEN(fen_string.trim().to_string().into_boxed_str())
+                        },
+                        _ => return Err(()),
+                    };
+                    let mut position_moves = Vec::new();
+                    if words.next() == Some("moves") {
+                        while let Some(move_string) = words.next() {
+                            match UciMove::from_str(move_string) {
+                                Ok(position_move) => position_moves.push(position_move),
+                                Err(_) => return Err(()),
+                            }
+                        }
+                    }
+                    return Ok(UciGuiCommand::Position(position_option, Box::new(position_moves)));
+                },
                 "go" => {
                     let mut go_settings = UciGoSettings::default();
                     while let Some(&position_command) = words.peek() {
@@ -301,7 +414,11 @@ impl FromStr for UciGuiCommand {
                                     }
                                 }
                             },
-                            "infinite" => go_settings.search_limiter = UciSearchLimiter::default(),
+                            "infinite" => {
+                                words.next();
+                                go_settings.search_limiter = UciSearchLimiter::default();
+                                go_settings.infinite = true;
+                            },
                             _ => _ = words.next(),
                         }
                     }
@@ -320,4 +437,382 @@ impl FromStr for UciGuiCommand {
 impl CommunicationProtocol for Uci {
     type ProtocolState = UciState;
     type ProtocolGUICommands = UciGuiCommand;
-}
\ No newline at end of file
+}
+// Bounds for the Contempt option, in centipawns. 
+const CONTEMPT_DEFAULT: i32 = 0;
+const CONTEMPT_MIN: i32 = -100;
+const CONTEMPT_MAX: i32 = 100;
+
+// Bounds for the MultiPV option: how many of the best root moves get reported. 
+const MULTI_PV_DEFAULT: usize = 1;
+const MULTI_PV_MAX: usize = 64;
+
+// Bounds for the Hash option, in MB. The table rounds down to a power of two entries. 
+const HASH_DEFAULT: usize = 16;
+const HASH_MIN: usize = 1;
+const HASH_MAX: usize = 1024;
+
+// Bounds for the search margin options, which are only there for tuning. 
+const MARGIN_MIN: i32 = 0;
+const MARGIN_MAX: i32 = 2000;
+
+// Deepest a "go" will ever search. There's no hope of actually getting there. 
+const MAX_UCI_DEPTH: i8 = 64;
+
+// A search running on its own thread, which prints its own bestmove when it's done. 
+//
+// The states, from the engine's side: 
+//  - idle: no search, or one that has already printed its bestmove. 
+//  - searching: "go" started it, and it ends by itself (depth or time) or on "stop". 
+//    After "go infinite" it only ever ends on "stop". 
+//  - pondering: "go ponder" started it on the position after the move we expect the 
+//    opponent to play. The clock is ignored and no bestmove may be printed, even if the 
+//    search runs out of depth. Then either 
+//     - "ponderhit": they played the expected move. The search is now searching, with the 
+//       deadline the go ponder command's clock allows counted from now. It's the same 
+//       thread and the same iterative deepening loop, so nothing searched so far is lost. 
+//     - "stop": they played something else. The bestmove gets printed (and ignored), and 
+//       the GUI follows up with the real position and a fresh "go". 
+#[derive(Debug)]
+struct RunningSearch {
+    control: Arc<SearchControl>,
+    handle: JoinHandle<()>,
+    ponderhit_budget: Option<Duration>,
+}
+
+// The score part of an info line. 
+pub(crate) fn uci_score_string(score: i32) -> String {
+    match mate_in_moves(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
+    }
+}
+
+// Engine-side state that has to survive between commands, such as option values. 
+#[derive(Debug)]
+pub(crate) struct UciEngine {
+    search_config: SearchConfig,
+    multi_pv: usize,
+    hash_mb: usize,
+    // Not probed by the search yet, but sized and owned here the way the options say. 
+    transposition_table: TranspositionTable,
+    position: UnwrappedFen,
+    // Hashes of the positions the last position command went through on the way to this one. 
+    game_history: Vec<u64>,
+    search: Option<RunningSearch>,
+    // Shared with the search thread, so "debug on" in the middle of a search shows up from 
+    // the next iteration on. 
+    debug: Arc<AtomicBool>,
+}
+
+impl Default for UciEngine {
+    fn default() -> Self {
+        UciEngine {
+            search_config: SearchConfig { contempt: CONTEMPT_DEFAULT, ..SearchConfig::default() },
+            multi_pv: MULTI_PV_DEFAULT,
+            hash_mb: HASH_DEFAULT,
+            transposition_table: TranspositionTable::new(HASH_DEFAULT),
+            position: UnwrappedFen::startpos(),
+            game_history: Vec::new(),
+            search: None,
+            debug: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl UciEngine {
+    #[inline(always)]
+    pub(crate) fn get_contempt(&self) -> i32 {
+        self.search_config.contempt
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_search_config(&self) -> SearchConfig {
+        self.search_config
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_multi_pv(&self) -> usize {
+        self.multi_pv
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_debug(&self) -> bool {
+        self.debug.load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_hash_mb(&self) -> usize {
+        self.hash_mb
+    }
+
+    #[inline(always)]
+    pub(crate) fn hash_capacity(&self) -> usize {
+        self.transposition_table.capacity()
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_transposition_table(&self) -> &TranspositionTable {
+        &self.transposition_table
+    }
+
+    // Forget everything from the last game: the transposition table, which is the only search 
+    // state that outlives a "go" so far, and the position along with its game history. 
+    fn new_game(&mut self) -> () {
+        self.stop_search();
+        self.transposition_table.clear();
+        self.position = UnwrappedFen::startpos();
+        self.game_history.clear();
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_position(&self) -> UnwrappedFen {
+        self.position
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_game_history(&self) -> &[u64] {
+        &self.game_history
+    }
+
+    // The search context a "go" from this engine state would use. 
+    pub(crate) fn search_context(&self, root_color: EnumColor) -> SearchContext {
+        SearchContext::with_config(root_color, self.search_config)
+    }
+
+    // Option names are case-insensitive per the UCI spec. Unknown options and 
+    // unparseable values are ignored, which is also what the spec asks for. 
+    pub(crate) fn set_option(&mut self, name: &str, value: &str) -> () {
+        if name.eq_ignore_ascii_case("Contempt") {
+            if let Ok(new_contempt) = i32::from_str(value) {
+                self.search_config.contempt = new_contempt.clamp(CONTEMPT_MIN, CONTEMPT_MAX);
+            }
+        } else if name.eq_ignore_ascii_case("FutilityMargin1") || name.eq_ignore_ascii_case("FutilityMargin2") {
+            if let Ok(new_margin) = i32::from_str(value) {
+                let depth_index = match name.eq_ignore_ascii_case("FutilityMargin1") {
+                    true => 1,
+                    false => 2,
+                };
+                self.search_config.futility_margins[depth_index] = new_margin.clamp(MARGIN_MIN, MARGIN_MAX);
+            }
+        } else if name.eq_ignore_ascii_case("DeltaMargin") {
+            if let Ok(new_margin) = i32::from_str(value) {
+                self.search_config.delta_margin = new_margin.clamp(MARGIN_MIN, MARGIN_MAX);
+            }
+        } else if name.eq_ignore_ascii_case("MultiPV") {
+            if let Ok(new_multi_pv) = usize::from_str(value) {
+                self.multi_pv = new_multi_pv.clamp(1, MULTI_PV_MAX);
+            }
+        } else if name.eq_ignore_ascii_case("Hash") {
+            // A fresh table comes back empty, so resizing always clears it too. 
+            if let Ok(new_hash_mb) = usize::from_str(value) {
+                self.hash_mb = new_hash_mb.clamp(HASH_MIN, HASH_MAX);
+                self.transposition_table = TranspositionTable::new(self.hash_mb);
+            }
+        }
+    }
+
+    fn start_search(&mut self, go_settings: UciGoSettings) -> () {
+        self.stop_search();
+        let position = self.position;
+
+        // Nothing to search, and no move to give. "(none)" is what GUIs expect here. 
+        if let Some(game_end) = position.terminal_state() {
+            match game_end {
+                GameEnd::Checkmate => println!("info depth 0 score mate 0"),
+                GameEnd::Stalemate => println!("info depth 0 score cp 0"),
+            }
+            println!("bestmove (none)");
+            return
+        }
+
+        let pondering = matches!(go_settings.search_mode, UciSearchMode::Ponder);
+        let infinite = go_settings.infinite;
+        let time_budget = go_settings.time_budget(position.get_color());
+        let deadline = match pondering {
+            true => None,
+            false => time_budget.map(|budget| Instant::now() + budget),
+        };
+        let max_depth = go_settings.max_depth();
+        // "go mate 0" has nothing to look for, so it's searched like a plain go. 
+        let mate_moves = go_settings.search_limiter.mate.filter(|mate_moves| *mate_moves > 0);
+        let multi_pv = self.multi_pv;
+        // Moves in searchmoves that aren't legal here are dropped, the same as unparseable ones. 
+        let root_restriction: Option<Vec<_>> = match &go_settings.moves_to_search {
+            UciSearchMoveSetting::None => None,
+            UciSearchMoveSetting::SearchMove(search_moves) => Some(search_moves.iter()
+                .filter_map(|search_move| uci_move_to_chess_move(&position, search_move).ok())
+                .collect()),
+        };
+        let control = Arc::new(SearchControl::new(pondering, deadline));
+        let mut context = self.search_context(position.get_color());
+        context.set_game_history(self.game_history.clone());
+        context.control = Some(control.clone());
+        context.node_limit = go_settings.search_limiter.nodes.map(|nodes| nodes.min(u64::MAX as u128) as u64);
+        if self.get_debug() {
+            println!("info string searching {0} to depth {1}, {2:?}", position.to_fen(), max_depth, context.config);
+            if let Some(restricted_moves) = &root_restriction {
+                println!("info string searchmoves leaves {} legal moves", restricted_moves.len());
+            }
+        }
+
+        let thread_control = control.clone();
+        let debug = self.debug.clone();
+        let handle = std::thread::spawn(move || {
+            let search_start = Instant::now();
+            let mut previous_nodes = 0;
+            let mut report_debug = |depth: i8, nodes: u64| {
+                if debug.load(Ordering::Relaxed) {
+                    let elapsed = search_start.elapsed();
+                    let nps = (nodes as f64 / elapsed.as_secs_f64().max(1e-6)) as u64;
+                    println!("info string depth {0} took {1} nodes, {2} in total, {3} ms so far, {4} nps", depth, nodes - previous_nodes, nodes, elapsed.as_millis(), nps);
+                }
+                previous_nodes = nodes;
+            };
+            let best_found = match mate_moves {
+                // A mate search only ever has the one line. 
+                Some(mate_moves) => {
+                    let best_found = search_for_mate(&position, mate_moves as i32, root_restriction.as_deref(), &mut context, |depth, best_move, score, nodes| {
+                        report_debug(depth, nodes);
+                        println!("info depth {0} score {1} nodes {2} pv {3}", depth, uci_score_string(score), nodes, best_move.to_uci_string());
+                    });
+                    if !matches!(best_found.and_then(|(_best_move, score)| mate_in_moves(score)), Some(moves) if moves > 0 && moves <= mate_moves as i32) {
+                        println!("info string no mate in {} found", mate_moves);
+                    }
+                    best_found
+                },
+                // The multipv field only shows up when there's more than one line to tell apart. 
+                None => iterative_deepening_multipv(&position, max_depth, multi_pv, root_restriction.as_deref(), &mut context, |depth, lines, nodes| {
+                    report_debug(depth, nodes);
+                    for (line_index, line) in lines.iter().enumerate() {
+                        let pv_string = line.pv.iter().map(|pv_move| pv_move.to_uci_string()).collect::<Vec<String>>().join(" ");
+                        match multi_pv > 1 {
+                            true => println!("info depth {0} multipv {1} score {2} nodes {3} pv {4}", depth, line_index + 1, uci_score_string(line.score), nodes, pv_string),
+                            false => println!("info depth {0} score {1} nodes {2} pv {3}", depth, uci_score_string(line.score), nodes, pv_string),
+                        }
+                    }
+                }).first().map(|line| (line.best_move, line.score)),
+            };
+            while (thread_control.is_pondering() || infinite) && !thread_control.stop_requested() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            match best_found {
+                None => println!("bestmove (none)"),
+                Some((best_move, _score)) => println!("bestmove {}", best_move.to_uci_string()),
+            }
+        });
+        self.search = Some(RunningSearch {
+            control: control,
+            handle: handle,
+            ponderhit_budget: time_budget,
+        });
+    }
+
+    // Stops the search if there is one, and doesn't come back until its bestmove is out. 
+    fn stop_search(&mut self) -> () {
+        if let Some(running_search) = self.search.take() {
+            running_search.control.request_stop();
+            let _ = running_search.handle.join();
+        }
+    }
+
+    // Waits for the search to finish by itself. Never comes back from a ponder search that 
+    // doesn't get a ponderhit, or from an untimed one with no depth limit. 
+    pub(crate) fn wait_for_search(&mut self) -> () {
+        if let Some(running_search) = self.search.take() {
+            let _ = running_search.handle.join();
+        }
+    }
+
+    // (deepest completed iteration, nodes searched) for the current or last search. 
+    pub(crate) fn search_progress(&self) -> Option<(i32, u64)> {
+        self.search.as_ref().map(|running_search| running_search.control.progress())
+    }
+
+    pub(crate) fn is_pondering(&self) -> bool {
+        match &self.search {
+            None => false,
+            Some(running_search) => running_search.control.is_pondering() && !running_search.handle.is_finished(),
+        }
+    }
+
+    pub(crate) fn search_finished(&self) -> bool {
+        match &self.search {
+            None => true,
+            Some(running_search) => running_search.handle.is_finished(),
+        }
+    }
+
+    // Unparseable lines are ignored. Returns false once the GUI has asked us to quit. 
+    pub(crate) fn handle_line(&mut self, line: &str) -> bool {
+        match Uci::parse_command(line) {
+            Some(command) => self.handle_command(command),
+            None => true,
+        }
+    }
+
+    // Returns false once the GUI has asked us to quit. 
+    fn handle_command(&mut self, command: UciGuiCommand) -> bool {
+        match command {
+            UciGuiCommand::Uci => {
+                println!("id name Cladonia");
+                println!("id author JakkobMath");
+                println!("option name Contempt type spin default {} min {} max {}", CONTEMPT_DEFAULT, CONTEMPT_MIN, CONTEMPT_MAX);
+                println!("option name MultiPV type spin default {} min 1 max {}", MULTI_PV_DEFAULT, MULTI_PV_MAX);
+                println!("option name Hash type spin default {} min {} max {}", HASH_DEFAULT, HASH_MIN, HASH_MAX);
+                let default_config = SearchConfig::default();
+                for (option_name, default_value) in [
+                    ("FutilityMargin1", default_config.futility_margins[1]),
+                    ("FutilityMargin2", default_config.futility_margins[2]),
+                    ("DeltaMargin", default_config.delta_margin),
+                ] {
+                    println!("option name {} type spin default {} min {} max {}", option_name, default_value, MARGIN_MIN, MARGIN_MAX);
+                }
+                println!("uciok");
+            },
+            UciGuiCommand::IsReady => println!("readyok"),
+            UciGuiCommand::Debug(debug_on) => self.debug.store(debug_on, Ordering::Relaxed),
+            UciGuiCommand::SetOption(name, value) => self.set_option(&name, &value),
+            UciGuiCommand::NewGame => self.new_game(),
+            // A bad position command leaves the old position alone, since there's no 
+            // sensible partial result to fall back on. 
+            UciGuiCommand::Position(position_option, position_moves) => match position_from_uci(&position_option, &position_moves) {
+                Ok((new_position, game_history)) => {
+                    self.position = new_position;
+                    self.game_history = game_history;
+                },
+                Err(message) => println!("info string {}", message),
+            },
+            UciGuiCommand::Go(go_settings) => self.start_search(go_settings),
+            UciGuiCommand::Stop => self.stop_search(),
+            UciGuiCommand::PonderHit => if let Some(running_search) = &self.search {
+                running_search.control.ponderhit(running_search.ponderhit_budget.map(|budget| Instant::now() + budget));
+            },
+            UciGuiCommand::Quit => {
+                self.stop_search();
+                return false
+            },
+            // Nothing here needs registering. 
+            UciGuiCommand::Register(_) => {},
+        }
+        true
+    }
+}
+
+// The parse of a line in Debug form, or None if it doesn't parse. Lets the parser be checked 
+// from outside without making the command types public. 
+pub(crate) fn parsed_command_debug(line: &str) -> Option<String> {
+    Uci::parse_command(line).map(|command| format!("{:?}", command))
+}
+
+// Read commands from stdin until the GUI says to quit. 
+pub(crate) fn run_uci_loop() -> () {
+    let mut engine = UciEngine::default();
+    for line in std::io::stdin().lines() {
+        let Ok(line) = line else { break };
+        if !engine.handle_line(&line) {
+            break;
+        }
+    }
+    engine.stop_search();
+}
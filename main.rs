@@ -22,6 +22,7 @@
 // functionality for alpha-beta to function. 
 
 use crate::chess::implementations::impls_vzero::{io_code::*, movegen::*, eval_code::*, board_rep::*};
+use crate::value::{MATE_SCORE, MATE_THRESHOLD};
 
 pub(crate) mod chess;
 pub mod value;
@@ -62,6 +63,13 @@ fn main() {
     //     }
     // }
 
+    // Hand control over to a GUI instead of running any of the tests below. 
+    let running_uci = false;
+    if running_uci {
+        chess::uci::run_uci_loop();
+        return;
+    }
+
     let start = std::time::Instant::now();
 
     let trying_startpos_perft = false;
@@ -96,6 +104,105 @@ fn main() {
     let testing_for_hce_sign_error = false;
     let deeper_ab_test = false;
 
+    let testing_san_output = false;
+    let testing_score_ordering = false;
+    let testing_contempt = false;
+    let testing_eval_accumulator = false;
+    let testing_terminal_state = false;
+    let testing_divide_diff = false;
+    let testing_uci_moves = false;
+    let testing_extended_perft = false;
+    let testing_lockless_tt = false;
+    let testing_eval_weights = false;
+    let testing_tuning_loss = false;
+    let testing_perpetual_check = false;
+    let testing_obvious_attackers = false;
+    let testing_attack_ignoring = false;
+    let testing_fen_king_checks = false;
+    let testing_mirror_symmetry = false;
+    let testing_mate_distance_pruning = false;
+    let testing_epd_suite = false;
+    let benchmarking_move_buffers = false;
+    let testing_truncated_fens = false;
+    let testing_gives_check = false;
+    let testing_null_move_unmake = false;
+    let testing_promotion_see = false;
+    let testing_piece_values = false;
+    let testing_fen_display = false;
+    let fuzzing_legal_moves = false;
+    let testing_ponder_flow = false;
+    let testing_zobrist_counters = false;
+    let testing_perft_after_moves = false;
+    let testing_legal_moves_into = false;
+    let testing_promotion_order = false;
+    let testing_generic_negamax = false;
+    let testing_uci_terminal_go = false;
+    let testing_rook_files = false;
+    let testing_bishop_pair = false;
+    let testing_tempo = false;
+    let testing_likely_draws = false;
+    let testing_fen_errors = false;
+    let testing_attackers_of = false;
+    let testing_king_tracking = false;
+    let testing_static_move_order = false;
+    let testing_max_ply = false;
+    let testing_fen_ep_output = false;
+    let testing_attack_geometry = false;
+    let testing_uci_scores = false;
+    let testing_game_stack = false;
+    let testing_go_infinite = false;
+    let testing_squares_between = false;
+    let testing_out_style_search = false;
+    let testing_automatic_draws = false;
+    let testing_piece_movegen = false;
+    let testing_quiet_moves = false;
+    let testing_futility_pruning = false;
+    let testing_delta_pruning = false;
+    let testing_multi_pv = false;
+    let testing_search_moves = false;
+    let testing_ray_kinds = false;
+    let benchmarking_perfts = false;
+    let testing_promotion_castling = false;
+    let testing_capture_analysis = false;
+    let testing_hash_option = false;
+    let testing_new_game = false;
+    let testing_knight_outposts = false;
+    let testing_outpost_scaling = false;
+    let testing_moves_by_piece_type = false;
+    let testing_search_config = false;
+    let testing_divide_file = false;
+    let testing_counter_overflow = false;
+    let testing_wide_halfmove_clock = false;
+    let testing_quiescence_evasions = false;
+    let testing_eval_sign = false;
+    let testing_checkers = false;
+    let testing_position_stream = false;
+    let testing_divide_down_line = false;
+    let testing_g2g1_promotions = false;
+    let testing_castling_validation = false;
+    let testing_packed_moves = false;
+    let testing_blocked_promotions = false;
+    let testing_node_terminals = false;
+    let testing_white_tables = false;
+    let testing_pins = false;
+    let testing_pinned_ep = false;
+    let testing_material_outlook = false;
+    let testing_self_play = false;
+    let testing_uci_debug = false;
+    let testing_uci_register = false;
+    let testing_material_key = false;
+    let testing_eval_clamp = false;
+    let benchmarking_fast_eval = false;
+    let testing_incremental_eval = false;
+    let testing_legal_moves_from = false;
+    let testing_go_mate = false;
+    let testing_go_nodes = false;
+    let testing_bitboard_perft = false;
+    let testing_magic_bitboards = false;
+    let testing_perft_suite = false;
+    let testing_castling_order = false;
+    let testing_make_unmake = false;
+
     if trying_startpos_perft {
         println!("Perft from STARTPOS:");
         let (total_num, sub_perfts) = depth_n_better_perft(STARTPOS, 4);
@@ -284,6 +391,8 @@ fn main() {
     } else if testing_fen_builder {
         println!("Hopefully the parser correctly interprets the startpos fen... Here it is: \n{:?}", interpret_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string()));
         println!("Startpos for comparison: \nOk({:?})", STARTPOS);
+        println!("Same thing through the constructors: \n{:?}", UnwrappedFen::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        println!("And the defaults: \n{:?}\n{:?}", UnwrappedFen::startpos(), UnwrappedFen::default());
     } 
     if trying_kiwipete_perft {
         println!("Perft from Kiwipete:");
@@ -529,7 +638,7 @@ fn main() {
         for valid_move in move_options {
             println!("After {}", valid_move);
             let negamax_results = negamax_best_move(&STARTPOS.after_move(valid_move), 5);
-            println!("Best move found and its evaluation: {0} gives evaluation {1}", negamax_results.0, negamax_results.1)
+            println!("Best move found and its evaluation: {0} gives evaluation {1:?}", negamax_results.0, negamax_results.1)
         }
     }
     if trying_ab_search {
@@ -538,11 +647,11 @@ fn main() {
         mvv_lva_sort(&STARTPOS, &mut move_options);
         for valid_move in move_options {
             println!("After {}", valid_move);
-            let ab_results = ab_best_move(&STARTPOS.after_move(valid_move), 5, i32::MIN + 1, i32::MIN + 1, 15, true);
+            let ab_results = ab_best_move(&STARTPOS.after_move(valid_move), 5, value::OutStyleScore::MatedIn(0), value::OutStyleScore::MatedIn(0), 15, true);
             match ab_results {
                 None => println!("Search failed somehow"),
                 Some((bestmove, score)) => {
-                    println!("Best move found and its evaluation: {0} gives evaluation {1} for white.", bestmove, -score)
+                    println!("Best move found and its evaluation: {0} gives evaluation {1:?} for white.", bestmove, -score)
                 }
             }
         }
@@ -553,18 +662,18 @@ fn main() {
         mvv_lva_sort(&STARTPOS, &mut move_options);
         for depth in 0..9 {
             println!("Starting search at depth {0}", depth);
-            let search_results = ab_best_move(&STARTPOS, depth, i32::MIN + 1, i32::MIN + 1, 15, true);
+            let search_results = ab_best_move(&STARTPOS, depth, value::OutStyleScore::MatedIn(0), value::OutStyleScore::MatedIn(0), 15, true);
             match search_results {
                 None => println!("Search failed somehow"),
                 Some((bestmove,score)) => {
-                    println!("Best move found and its evaluation: {0} gives evaluation {1} for white.",bestmove,score);
+                    println!("Best move found and its evaluation: {0} gives evaluation {1:?} for white.",bestmove,score);
                     if depth >0 {
                         let follow_up_position = &STARTPOS.after_move(bestmove);
-                        let responding_search_results = ab_best_move(follow_up_position, depth-1, i32::MIN + 1, i32::MIN + 1, 15, true);
+                        let responding_search_results = ab_best_move(follow_up_position, depth-1, value::OutStyleScore::MatedIn(0), value::OutStyleScore::MatedIn(0), 15, true);
                         match responding_search_results {
                             None => println!("Search failed somehow"),
                             Some((bestresponse,opp_score)) => {
-                                println!("The recommended follow-up is {0} to keep white advangate at {1}",bestresponse,-opp_score);
+                                println!("The recommended follow-up is {0} to keep white advangate at {1:?}",bestresponse,-opp_score);
                             }
                         }
                     }
@@ -583,11 +692,2703 @@ fn main() {
         }
     }
     if deeper_ab_test {
-        let search_depth_ten = ab_best_move(&STARTPOS, 10, i32::MIN + 1, i32::MIN + 1, 15, true);
+        let search_depth_ten = ab_best_move(&STARTPOS, 10, value::OutStyleScore::MatedIn(0), value::OutStyleScore::MatedIn(0), 15, true);
         match search_depth_ten {
             None => println!("Search failed! :("),
-            Some((bestmove,score)) => println!("AB eval at depth 10: {0} gives eval {1}.",bestmove,score),
+            Some((bestmove,score)) => println!("AB eval at depth 10: {0} gives eval {1:?}.",bestmove,score),
+        }
+    }
+
+    if testing_san_output {
+        let expected_san = [
+            "Na3", "Nc3", "Nf3", "Nh3", "a3", "a4", "b3", "b4", "c3", "c4", 
+            "d3", "d4", "e3", "e4", "f3", "f4", "g3", "g4", "h3", "h4", 
+        ];
+        let startpos_san = STARTPOS.legal_moves_san();
+        println!("Startpos moves in SAN: {:?}", startpos_san);
+        println!("Matches the expected 20 moves: {}", startpos_san == expected_san);
+        match UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1") {
+            Err(some_error) => println!("Error with parsing Kiwipete: {}", some_error),
+            Ok(kiwipete) => println!("Kiwipete moves in SAN: {:?}", kiwipete.legal_moves_san()),
+        }
+    }
+
+    if testing_score_ordering {
+        use value::OutStyleScore;
+        // Should already be sorted from worst to best. 
+        let increasing_scores = [
+            OutStyleScore::MatedIn(1),
+            OutStyleScore::MatedIn(6),
+            OutStyleScore::Centipawn(-80),
+            OutStyleScore::Centipawn(-1),
+            OutStyleScore::Draw,
+            OutStyleScore::Centipawn(0),
+            OutStyleScore::Centipawn(35),
+            OutStyleScore::MatingIn(9),
+            OutStyleScore::MatingIn(2),
+        ];
+        let mut sorted_scores = increasing_scores;
+        sorted_scores.sort();
+        println!("Ordering is as expected: {}", sorted_scores == increasing_scores);
+        for score in increasing_scores {
+            println!("{:?} wins with probability {:.3}", score, score.win_probability());
+        }
+    }
+
+    if testing_contempt {
+        use chess::{uci::UciEngine, implementations::impls_vzero::search_code::*};
+        // Every king move here is a draw by the 50mr, the pawn move is slightly worse than even 
+        // for white. Without contempt white should take the draw, with it white should play on. 
+        match UnwrappedFen::from_fen("4k3/8/2p5/8/8/2P5/8/4K3 w - - 99 80") {
+            Err(some_error) => println!("Error with parsing the contempt position: {}", some_error),
+            Ok(position) => {
+                let mut engine = UciEngine::default();
+                for contempt_setting in ["0", "30"] {
+                    engine.set_option("Contempt", contempt_setting);
+                    let mut context = engine.search_context(position.get_color());
+                    match search_best_move(&position, 1, &mut context) {
+                        None => println!("Search failed somehow"),
+                        Some((bestmove, score)) => println!("Contempt {0}: plays {1} with score {2}", engine.get_contempt(), bestmove, score),
+                    }
+                }
+            }
+        }
+    }
+
+    if testing_eval_accumulator {
+        // The incrementally updated eval sums should agree with a from-scratch 
+        // recomputation no matter which moves are made. 
+        let mut positions_checked = 0;
+        let mut mismatches = 0;
+        for position in random_playout_positions(1582, &[(STARTPOS, 200, 200)]) {
+            positions_checked += 1;
+            if !position.accumulator_matches_board() {
+                mismatches += 1;
+                println!("Accumulator mismatch in {:?}", position);
+            }
+        }
+        println!("Checked {0} positions, {1} accumulator mismatches.", positions_checked, mismatches);
+    }
+
+    if testing_terminal_state {
+        // terminal_state should agree with the old one-question-at-a-time definitions. 
+        let fools_mate = UnwrappedFen::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        let stalemate = UnwrappedFen::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        println!("Fool's mate: {:?}", fools_mate.terminal_state());
+        println!("Stalemate: {:?}", stalemate.terminal_state());
+        println!("Startpos: {:?}", STARTPOS.terminal_state());
+
+        let mut disagreements = 0;
+        let mut endings_seen = 0;
+        for position in random_playout_positions(1583, &[(STARTPOS, 300, 300)]) {
+            let no_moves = position.get_legal_proper_moves().len() == 0;
+            let old_checkmate = no_moves && position.mover_in_check();
+            let old_stalemate = no_moves && !position.mover_in_check();
+            if position.terminal_state().is_some() {
+                endings_seen += 1;
+            }
+            if old_checkmate != position.is_checkmate() || old_stalemate != position.is_stalemate() {
+                disagreements += 1;
+                println!("terminal_state disagrees in {:?}", position);
+            }
+        }
+        println!("Saw {0} finished games, {1} disagreements.", endings_seen, disagreements);
+    }
+
+    if testing_divide_diff {
+        // Reference divide from another engine's "go perft 3" at startpos. 
+        let startpos_reference = "a2a3: 380\nb2b3: 420\nc2c3: 420\nd2d3: 539\ne2e3: 599\nf2f3: 380\ng2g3: 420\nh2h3: 380\n\
+            a2a4: 420\nb2b4: 421\nc2c4: 441\nd2d4: 560\ne2e4: 600\nf2f4: 401\ng2g4: 421\nh2h4: 420\n\
+            b1a3: 400\nb1c3: 440\ng1f3: 440\ng1h3: 400\n\nNodes searched: 8902";
+        let reference = parse_divide_reference(startpos_reference).unwrap();
+        let (total_num, sub_perfts) = depth_n_better_perft(STARTPOS, 3);
+        println!("Total: {}", total_num);
+        print!("{}", compare_divide(&sub_perfts, &reference));
+
+        // Same thing against a doctored reference, to see what each kind of problem looks like. 
+        let mut doctored_reference = reference.clone();
+        doctored_reference.remove("e2e4");
+        doctored_reference.insert("e1e2".to_string(), 1);
+        doctored_reference.insert("d2d4".to_string(), 559);
+        print!("{}", compare_divide(&sub_perfts, &doctored_reference));
+    }
+
+    if testing_uci_moves {
+        use std::str::FromStr;
+        use chess::uci::*;
+        // The Italian, played out from startpos, should land on the same position as its FEN. 
+        let italian_moves: Vec<UciMove> = ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5"].iter()
+            .map(|move_string| UciMove::from_str(move_string).unwrap()).collect();
+        let italian_fen = UnwrappedFen::from_fen("r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap();
+        let played_out = apply_uci_moves(STARTPOS, &italian_moves).unwrap();
+        // The castling rights can come out listed in a different order, so compare what matters. 
+        let same_position = |first: UnwrappedFen, second: UnwrappedFen| {
+            (0..64i8).all(|square| first.query_square(square) == second.query_square(square))
+                && first.get_color() == second.get_color()
+                && first.get_ply_count() == second.get_ply_count()
+                && first.get_move_count() == second.get_move_count()
+                && first.legal_moves_san() == second.legal_moves_san()
+        };
+        println!("Italian matches its FEN: {}", same_position(played_out, italian_fen));
+        // Three moves each, so the fullmove counter should have gone from 1 to 4, and only on 
+        // Black's moves. 
+        let counters: Vec<i16> = (0..=italian_moves.len()).map(|move_number| apply_uci_moves(STARTPOS, &italian_moves[..move_number]).unwrap().get_move_count()).collect();
+        println!("Fullmove counters along the way: {:?}", counters);
+
+        // An illegal move partway through should be rejected by name. 
+        let bad_moves: Vec<UciMove> = ["e2e4", "e7e5", "e1e3"].iter()
+            .map(|move_string| UciMove::from_str(move_string).unwrap()).collect();
+        println!("Bad moves: {:?}", apply_uci_moves(STARTPOS, &bad_moves).map(|_| ()));
+
+        // Same game through the full position command. 
+        let mut engine = UciEngine::default();
+        engine.handle_line("position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 f8c5");
+        println!("Position command matches: {}", same_position(engine.get_position(), italian_fen));
+        engine.handle_line("position fen r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4 moves e1g1");
+        println!("Castled from FEN: {:?}", engine.get_position().get_castling(EnumColor::White));
+    }
+
+    if testing_extended_perft {
+        // Published numbers for Kiwipete at depth 3. 
+        let kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let expected = PerftStats {
+            nodes: 97862,
+            captures: 17102,
+            en_passants: 45,
+            castles: 3162,
+            promotions: 0,
+            checks: 993,
+            checkmates: 1,
+        };
+        let kiwipete_stats = depth_n_extended_perft(kiwipete, 3);
+        println!("{:?}", kiwipete_stats);
+        println!("Matches published numbers: {}", kiwipete_stats == expected);
+        println!("Startpos depth 4: {:?}", depth_n_extended_perft(STARTPOS, 4));
+    }
+
+    if testing_lockless_tt {
+        use chess::implementations::impls_vzero::ttable_code::*;
+        use chess::implementations::impls_vzero::packed_move_code::PackedMove;
+        // Every key gets its own recognizable data, so a reader can tell whether what it got 
+        // back really belongs to the key it asked for. 
+        let data_for_key = |key: u64| TTData {
+            score: (key >> 32) as i32,
+            depth: (key >> 8) as i8,
+            bound: match key % 3 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+            best_move: PackedMove((key >> 16) as u16),
+        };
+        let mut key_rng = XorShiftRng::new(1587);
+        let keys: Vec<u64> = (0..1024).map(|_| key_rng.next_u64()).collect();
+
+        // A tiny table so the threads are constantly fighting over the same entries. 
+        let table = TranspositionTable::with_entries(64);
+        let bad_reads = std::sync::atomic::AtomicUsize::new(0);
+        let good_reads = std::sync::atomic::AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for thread_number in 0..4u64 {
+                let (table, keys, bad_reads, good_reads) = (&table, &keys, &bad_reads, &good_reads);
+                scope.spawn(move || {
+                    let mut rng = XorShiftRng::new(thread_number + 1);
+                    for _ in 0..1_000_000 {
+                        let key = keys[rng.below(keys.len())];
+                        match rng.below(2) {
+                            0 => table.store(key, data_for_key(key)),
+                            _ => match table.probe(key) {
+                                None => {},
+                                Some(data) => match data == data_for_key(key) {
+                                    true => {good_reads.fetch_add(1, std::sync::atomic::Ordering::Relaxed);},
+                                    false => {bad_reads.fetch_add(1, std::sync::atomic::Ordering::Relaxed);},
+                                },
+                            },
+                        }
+                    }
+                });
+            }
+        });
+        println!("Good reads: {0}, torn reads accepted: {1}", good_reads.into_inner(), bad_reads.into_inner());
+
+        let mut table = table;
+        println!("Occupied before clearing: {0} of {1}", table.occupied(), table.capacity());
+        table.clear();
+        println!("Occupied after clearing: {}", table.occupied());
+        println!("16 MB table holds {} entries", TranspositionTable::new(16).capacity());
+    }
+
+    if testing_eval_weights {
+        // hce_stm_weighted with the default weights has to be exactly hce_stm. 
+        let mut positions_checked = 0;
+        let mut mismatches = 0;
+        for position in random_playout_positions(1588, &[(STARTPOS, 200, 200)]) {
+            positions_checked += 1;
+            if hce_stm_weighted(&position, &DEFAULT_WEIGHTS) != hce_stm(&position) {
+                mismatches += 1;
+                println!("Weighted eval mismatch in {:?}", position);
+            }
+        }
+        println!("Checked {0} positions, {1} weighted eval mismatches.", positions_checked, mismatches);
+
+        // And swapping the weights should actually do something. 
+        let mut no_knights = DEFAULT_WEIGHTS;
+        no_knights.mg_values[1] = 0;
+        no_knights.eg_values[1] = 0;
+        let knight_up = UnwrappedFen::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        println!("Knight up: {0} by default, {1} with worthless knights", hce_stm(&knight_up), hce_stm_weighted(&knight_up, &no_knights));
+    }
+
+    if testing_tuning_loss {
+        use chess::implementations::impls_vzero::tuning_code::*;
+        let dataset = load_tuning_positions(SAMPLE_TUNING_DATA).unwrap();
+        let k = 1.0;
+        println!("Loaded {} positions", dataset.len());
+        println!("Bad line: {:?}", load_tuning_positions("4k3/8/8/8/8/8/8/3QK3 w - - 0 1 [2.0]").map(|_| ()));
+
+        // The same data through a file, and a file that isn't there. 
+        let dataset_path = std::env::temp_dir().join("cladonia_sample_tuning_data.txt");
+        std::fs::write(&dataset_path, SAMPLE_TUNING_DATA).unwrap();
+        let file_dataset = load_tuning_file(dataset_path.to_str().unwrap()).unwrap();
+        println!("Loaded {} positions from a file", file_dataset.len());
+        assert!(file_dataset.len() == dataset.len());
+        println!("Missing file: {:?}", load_tuning_file("no_such_tuning_file.txt").map(|_| ()));
+
+        // Material-blind weights should do worse than the defaults, and every step back 
+        // toward sensible piece values should help. 
+        let mut blind_weights = DEFAULT_WEIGHTS;
+        blind_weights.mg_values = [0; 6];
+        blind_weights.eg_values = [0; 6];
+        let mut previous_loss = tuning_loss(&dataset, &blind_weights, k);
+        println!("Material-blind loss: {}", previous_loss);
+        for fraction in [0.25, 0.5, 0.75, 1.0] {
+            let mut partial_weights = blind_weights;
+            for piece_number in 0..6 {
+                partial_weights.mg_values[piece_number] = (DEFAULT_WEIGHTS.mg_values[piece_number] as f64 * fraction) as i32;
+                partial_weights.eg_values[piece_number] = (DEFAULT_WEIGHTS.eg_values[piece_number] as f64 * fraction) as i32;
+            }
+            let partial_loss = tuning_loss(&dataset, &partial_weights, k);
+            println!("{0} of the default values: loss {1}", fraction, partial_loss);
+            assert!(partial_loss < previous_loss);
+            previous_loss = partial_loss;
+        }
+
+        // The local search should find its own way out of the material-blind weights. 
+        let tuned_weights = tune_material_values(&dataset, &blind_weights, k, 20, 50);
+        let tuned_loss = tuning_loss(&dataset, &tuned_weights, k);
+        println!("Tuned values: mg {0:?}, eg {1:?}, loss {2}", tuned_weights.mg_values, tuned_weights.eg_values, tuned_loss);
+        assert!(tuned_loss < tuning_loss(&dataset, &blind_weights, k));
+    }
+
+    if testing_perpetual_check {
+        use chess::implementations::impls_vzero::search_code::*;
+        // White is down a queen and two rooks, but Qe8+ Kh7 Qh5+ Kg8 Qe8+ goes on forever. 
+        // Anything else loses, so the right answer is a check and a score of exactly zero. 
+        let perpetual = UnwrappedFen::from_fen("6k1/6p1/8/8/8/1q6/rr2Q3/6K1 w - - 0 1").unwrap();
+        println!("Static eval: {}", hce_stm(&perpetual));
+        for depth in [3, 5] {
+            let mut context = SearchContext::new(perpetual.get_color(), 0);
+            match search_best_move(&perpetual, depth, &mut context) {
+                None => println!("No move found at depth {}", depth),
+                Some((best_move, score)) => println!("Depth {0}: plays {1} with score {2}", depth, perpetual.to_san(best_move), score),
+            }
+        }
+
+        // Once Qe8+ Kh7 Qh5+ Kg8 has actually been played, the next Qe8+ repeats a position 
+        // from the game, so even a shallow search should see the draw if it's told the history. 
+        let mut history_pos = perpetual;
+        let mut game_hashes = Vec::new();
+        for uci_move in ["e2e8", "g8h7", "e8h5", "h7g8"] {
+            game_hashes.push(history_pos.zobrist_hash());
+            let next_move = history_pos.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == uci_move).unwrap();
+            history_pos.make_move(next_move);
+        }
+        let mut context = SearchContext::new(history_pos.get_color(), 0);
+        match search_best_move(&history_pos, 2, &mut context) {
+            None => println!("No move found without game history"),
+            Some((best_move, score)) => println!("Without game history: plays {0} with score {1}", history_pos.to_san(best_move), score),
+        }
+        context.set_game_history(game_hashes.clone());
+        match search_best_move(&history_pos, 2, &mut context) {
+            None => println!("No move found with game history"),
+            Some((best_move, score)) => println!("With game history: plays {0} with score {1}", history_pos.to_san(best_move), score),
+        }
+
+        // The same moves through a position command. The engine should keep the same history 
+        // and its depth 2 search should end on bestmove h5e8 with score cp 0. 
+        use chess::uci::UciEngine;
+        let mut engine = UciEngine::default();
+        engine.handle_line("position fen 6k1/6p1/8/8/8/1q6/rr2Q3/6K1 w - - 0 1 moves e2e8 g8h7 e8h5 h7g8");
+        println!("UCI game history matches: {}", engine.get_game_history() == &game_hashes[..]);
+        engine.handle_line("go depth 2");
+        engine.wait_for_search();
+    }
+
+    if testing_obvious_attackers {
+        // Pawns about to capture-promote onto the back ranks. The promotion pieces should 
+        // belong to the side doing the attacking, not the side defending the square. 
+        let promoting_pawns = UnwrappedFen::from_fen("3r3k/4P3/8/8/8/8/4p3/K2R4 w - - 0 1").unwrap();
+        for (defending_color, target_square) in [(EnumColor::White, 3i8), (EnumColor::Black, 59i8)] {
+            let attackers = promoting_pawns.get_obvious_attackers(defending_color, target_square);
+            let mut promotion_count = 0;
+            let mut wrong_colors = 0;
+            for attacking_move in &attackers {
+                if let ChessMove::PromotionMove(promotion_move) = attacking_move {
+                    promotion_count += 1;
+                    if promotion_move.promotion_choice.get_color() == defending_color {
+                        wrong_colors += 1;
+                    }
+                }
+            }
+            println!("{0:?} defending: {1} attackers, {2} promotions, {3} with the wrong color", defending_color, attackers.len(), promotion_count, wrong_colors);
+        }
+
+        // The attacker list should be nonempty exactly when sees_obvious_attack says so. 
+        let mut disagreements = 0;
+        for position in random_playout_positions(1591, &[(STARTPOS, 100, 150)]) {
+            for square in 0..64i8 {
+                for defending_color in [EnumColor::White, EnumColor::Black] {
+                    let has_attackers = position.get_obvious_attackers(defending_color, square).len() != 0;
+                    if has_attackers != position.sees_obvious_attack(defending_color, square) {
+                        disagreements += 1;
+                    }
+                }
+            }
+        }
+        println!("get_obvious_attackers vs sees_obvious_attack disagreements: {}", disagreements);
+    }
+
+    if testing_attack_ignoring {
+        // After c7c5, bxc6 ep would take both pawns off the fifth rank at once and leave 
+        // the white king staring at the rook on h5. 
+        let mut ep_pin = UnwrappedFen::from_fen("8/2p5/8/KP5r/8/8/8/7k b - - 0 1").unwrap();
+        let double_push = ep_pin.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == "c7c5").unwrap();
+        ep_pin.make_move(double_push);
+        let (a5, b5, c5) = (32i8, 33i8, 34i8);
+        println!("King attacked as is: {}", ep_pin.sees_obvious_attack(EnumColor::White, a5));
+        println!("King attacked with b5 and c5 ignored: {}", ep_pin.sees_obvious_attack_ignoring(EnumColor::White, a5, &[b5, c5]));
+        println!("King attacked with only c5 ignored: {}", ep_pin.sees_obvious_attack_ignoring(EnumColor::White, a5, &[c5]));
+        println!("Pseudolegal ep moves: {}", ep_pin.get_likely_ep_moves().len());
+        println!("bxc6 is legal: {}", ep_pin.get_legal_proper_moves().iter().any(|legal_move| legal_move.to_uci_string() == "b5c6"));
+    }
+
+    if testing_fen_king_checks {
+        for bad_fen in [
+            "4k3/8/8/8/8/8/8/K3K3 w - - 0 1",
+            "4k2k/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3p w - - 0 1",
+        ] {
+            println!("{0}: {1:?}", bad_fen, UnwrappedFen::from_fen(bad_fen).map(|_| ()));
+        }
+        println!("Startpos still parses: {}", UnwrappedFen::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_ok());
+    }
+
+    if testing_mirror_symmetry {
+        // The eval is from the side to move's perspective, so a position and its mirror 
+        // should get exactly the same score. 
+        let kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let mut positions_checked = 0;
+        let mut asymmetries = 0;
+        let mut bad_round_trips = 0;
+        for position in random_playout_positions(1594, &[(STARTPOS, 100, 200), (kiwipete, 100, 200)]) {
+            positions_checked += 1;
+            let mirrored = position.mirror_vertical();
+            if hce_stm(&position) != hce_stm(&mirrored) {
+                asymmetries += 1;
+                println!("Eval asymmetry in {:?}", position);
+            }
+            if format!("{:?}", mirrored.mirror_vertical()) != format!("{:?}", position) {
+                bad_round_trips += 1;
+            }
+        }
+        println!("Checked {0} positions: {1} eval asymmetries, {2} bad round trips.", positions_checked, asymmetries, bad_round_trips);
+
+        // Movegen should be symmetric too. 
+        println!("Kiwipete depth 3: {0}, mirrored: {1}", depth_n_total_perft(kiwipete, 3), depth_n_total_perft(kiwipete.mirror_vertical(), 3));
+    }
+
+    if testing_mate_distance_pruning {
+        use chess::implementations::impls_vzero::search_code::*;
+        // Mate in 3 with two rooks. Searching deeper than necessary shouldn't change the 
+        // answer, and with the pruning on it shouldn't cost nearly as much either. 
+        let mate_in_three = UnwrappedFen::from_fen("3k4/8/8/8/8/8/8/1R2K1R1 w - - 0 1").unwrap();
+        for depth in [5, 6] {
+            for use_pruning in [false, true] {
+                let mut context = SearchContext::new(mate_in_three.get_color(), 0);
+                context.use_mate_distance_pruning = use_pruning;
+                match search_best_move(&mate_in_three, depth, &mut context) {
+                    None => println!("No move found"),
+                    Some((best_move, score)) => println!(
+                        "Depth {0}, pruning {1}: plays {2}, mate in {3} plies, {4} nodes", 
+                        depth, use_pruning, mate_in_three.to_san(best_move), MATE_SCORE - score, context.nodes
+                    ),
+                }
+            }
+        }
+    }
+
+    if testing_epd_suite {
+        use chess::implementations::impls_vzero::epd_code::*;
+        // from_san should undo to_san for every legal move. 
+        let mut san_failures = 0;
+        for position in random_playout_positions(1596, &[(STARTPOS, 10, 100)]) {
+            for legal_move in position.get_legal_proper_moves() {
+                match position.from_san(&position.to_san(legal_move)) {
+                    Ok(parsed_move) => if parsed_move.to_uci_string() != legal_move.to_uci_string() {
+                        san_failures += 1;
+                    },
+                    Err(_) => san_failures += 1,
+                }
+            }
+        }
+        println!("SAN round trip failures: {}", san_failures);
+        for sloppy_san in ["e4", "Nf3!", "Nf3+", "0-0", "Qh5??"] {
+            println!("{0}: {1:?}", sloppy_san, STARTPOS.from_san(sloppy_san).map(|parsed_move| parsed_move.to_uci_string()));
+        }
+
+        let wac_sample = "\
+            2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";
+            8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - - bm Rxb2; id \"WAC.002\";
+            r1bq2rk/pp3pbp/2p1p1pQ/7P/3P4/2PB1N2/PP3PPR/2KR4 w - - bm Qxh7+; id \"WAC.004\";
+        ";
+        let entries = parse_epd(wac_sample).unwrap();
+        run_epd_suite(&entries, 3, 0);
+        println!("Bad EPD: {:?}", parse_epd("4k3/8/8/8/8/8/8/4K3 w - - bm Qh5;").map(|_| ()));
+    }
+
+    if benchmarking_move_buffers {
+        use chess::implementations::impls_vzero::search_code::*;
+        // Same positions, same moves, once with a fresh Vec per call and once reusing a buffer. 
+        let positions = random_playout_positions(1597, &[(STARTPOS, 100, 100)]);
+        let mut allocating_total = 0;
+        let allocating_start = std::time::Instant::now();
+        for _pass in 0..10 {
+            for position in &positions {
+                allocating_total += position.get_legal_proper_moves().len();
+            }
+        }
+        let allocating_time = allocating_start.elapsed();
+        let mut buffered_total = 0;
+        let mut move_buffer = Vec::new();
+        let buffered_start = std::time::Instant::now();
+        for _pass in 0..10 {
+            for position in &positions {
+                position.write_legal_proper_moves(&mut move_buffer);
+                buffered_total += move_buffer.len();
+            }
+        }
+        let buffered_time = buffered_start.elapsed();
+        println!("Allocating movegen: {0} moves in {1:?}", allocating_total, allocating_time);
+        println!("Buffered movegen: {0} moves in {1:?}", buffered_total, buffered_time);
+
+        // And what that's worth in an actual fixed-depth search, with a fresh Vec at every node 
+        // against one buffer per ply. The trees have to come out the same size. 
+        for use_move_buffers in [false, true] {
+            let search_start = std::time::Instant::now();
+            let mut context = SearchContext::new(STARTPOS.get_color(), 0);
+            context.use_move_buffers = use_move_buffers;
+            let best_found = search_best_move(&STARTPOS, 5, &mut context);
+            println!("Depth 5 from startpos, buffers {0}: {1:?}, {2} nodes in {3:?}", use_move_buffers, best_found.map(|(best_move, score)| (best_move.to_uci_string(), score)), context.nodes, search_start.elapsed());
+        }
+    }
+
+    if testing_truncated_fens {
+        // Four, five and six fields should all give startpos, counters included. 
+        for startpos_fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ",
+        ] {
+            match UnwrappedFen::from_fen(startpos_fen) {
+                Err(message) => println!("{0}: {1}", startpos_fen, message),
+                Ok(position) => println!("{0}: halfmove {1}, fullmove {2}, perft 3 = {3}", 
+                    startpos_fen, position.get_ply_count(), position.get_move_count(), depth_n_total_perft(position, 3)),
+            }
+        }
+
+        // An ep square used to throw the counters off by a field. 
+        for ep_fen in [
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        ] {
+            match UnwrappedFen::from_fen(ep_fen) {
+                Err(message) => println!("{0}: {1}", ep_fen, message),
+                Ok(position) => println!("{0}: halfmove {1}, fullmove {2}, ep moves {3}", 
+                    ep_fen, position.get_ply_count(), position.get_move_count(), position.get_likely_ep_moves().len()),
+            }
+        }
+        println!("Three fields: {:?}", UnwrappedFen::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq").map(|_| ()));
+    }
+
+    if testing_gives_check {
+        // Known moves, with whether they check and whether they capture. 
+        for (fen, san, expected_check, expected_capture) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e4", false, false),
+            ("4k3/8/8/3N4/8/8/8/4K3 w - - 0 1", "Nc7", true, false),
+            ("4k3/8/8/3N4/8/8/8/4K3 w - - 0 1", "Nb4", false, false),
+            ("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1", "Nc3", true, false),
+            ("4k3/8/8/8/8/8/4P3/4RK2 w - - 0 1", "e4", false, false),
+            ("8/8/8/1k1pP2R/8/8/8/4K3 w - d6 0 1", "exd6", true, true),
+            ("2k5/4P3/8/8/8/8/8/4K3 w - - 0 1", "e8=Q", true, false),
+            ("2k5/4P3/8/8/8/8/8/4K3 w - - 0 1", "e8=N", false, false),
+            ("2k5/4P3/8/8/8/8/8/4K3 w - - 0 1", "e8=B", false, false),
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "O-O", true, false),
+            ("4k3/8/8/8/8/3p4/8/4K3 b - - 0 1", "d2", true, false),
+            ("4r1k1/4n3/8/8/8/8/8/4K3 b - - 0 1", "Nc6", true, false),
+            ("4k3/8/8/8/8/3p4/4Q3/4K3 b - - 0 1", "dxe2", false, true),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            match position.from_san(san) {
+                Err(message) => println!("{0} in {1}: {2}", san, fen, message),
+                Ok(known_move) => println!("{0} in {1}: check {2} (slow {3}, expected {4}), capture {5} (expected {6})", 
+                    san, fen, position.gives_check(known_move), position.gives_check_by_making(known_move), expected_check, 
+                    position.is_capture(known_move), expected_capture),
+            }
+        }
+
+        // Against making the move, and against the piece count going down. 
+        let mut check_disagreements = 0;
+        let mut capture_disagreements = 0;
+        let mut checks_seen = 0;
+        for position in random_playout_positions(1599, &[(STARTPOS, 200, 200)]) {
+            let piece_count = (0..64i8).filter(|square| position.query_square(*square).get_contents().is_some()).count();
+            for legal_move in position.get_legal_proper_moves() {
+                let position_after = position.after_move(legal_move);
+                if position.gives_check_by_making(legal_move) {
+                    checks_seen += 1;
+                }
+                if position.gives_check(legal_move) != position.gives_check_by_making(legal_move) {
+                    check_disagreements += 1;
+                }
+                let captured = (0..64i8).filter(|square| position_after.query_square(*square).get_contents().is_some()).count() < piece_count;
+                if position.is_capture(legal_move) != captured {
+                    capture_disagreements += 1;
+                }
+            }
+        }
+        println!("{0} checking moves seen, {1} gives_check disagreements, {2} is_capture disagreements", checks_seen, check_disagreements, capture_disagreements);
+    }
+
+    if testing_null_move_unmake {
+        // make_null_move then unmake_null_move should be a no-op, ep squares and counters included, 
+        // and the position in between should be the same as after_null_move's. 
+        let mut positions_tried = 0;
+        let mut ep_positions_tried = 0;
+        let mut mismatches = 0;
+        for position in random_playout_positions(1600, &[(STARTPOS, 200, 200)]) {
+            let mut null_moved = position;
+            let undo_info = null_moved.make_null_move();
+            if format!("{:?}", null_moved) != format!("{:?}", position.after_null_move()) {
+                mismatches += 1;
+            }
+            null_moved.unmake_null_move(undo_info);
+            if format!("{:?}", null_moved) != format!("{:?}", position) {
+                mismatches += 1;
+            }
+            positions_tried += 1;
+            if position.try_get_ep_square().is_some() {
+                ep_positions_tried += 1;
+            }
+        }
+        println!("Null move make/unmake: {0} positions ({1} with an ep square), {2} mismatches", positions_tried, ep_positions_tried, mismatches);
+    }
+
+    if testing_promotion_see {
+        // The promoting pawn is a queen by the time anyone recaptures it, so the promotion has 
+        // to be part of the gain. Otherwise bxa8=Q below looks like losing a queen for a knight. 
+        for (fen, san, expected_see) in [
+            ("n3r1k1/1P6/8/8/8/8/8/K7 w - - 0 1", "bxa8=Q", 200),
+            ("n5k1/1P6/8/8/8/8/8/K7 w - - 0 1", "bxa8=Q", 1100),
+            ("n3r1k1/1P6/8/8/8/8/8/K7 w - - 0 1", "b8=Q", -100),
+            ("4k3/8/8/8/8/8/1p6/n2RK3 w - - 0 1", "Rxa1", -1000),
+            ("4k3/8/3p4/4p3/8/8/8/4KB2 w - - 0 1", "Bb5+", 0),
+            ("3rk3/8/3p4/4p3/3R4/8/8/3QK3 w - - 0 1", "Rxd6", 100),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            match position.from_san(san) {
+                Err(message) => println!("{0} in {1}: {2}", san, fen, message),
+                Ok(known_move) => println!("{0} in {1}: SEE {2} (expected {3})", san, fen, static_exchange_eval(&position, known_move), expected_see),
+            }
+        }
+
+        // bxa8=Q should be ordered ahead of even dxe5, pawn takes queen. 
+        let promotion_or_queen = UnwrappedFen::from_fen("r3k3/1P6/8/4q3/3P4/8/8/7K w - - 0 1").unwrap();
+        let mut ordered_moves = promotion_or_queen.get_legal_proper_moves();
+        ordered_moves.sort_by_key(|possible_move| -mvv_lva_score(&promotion_or_queen, *possible_move));
+        println!("Ordered first: {:?}", ordered_moves.iter().take(3).map(|possible_move| promotion_or_queen.to_san(*possible_move)).collect::<Vec<_>>());
+    }
+
+    if testing_piece_values {
+        // MVV-LVA and SEE both lean on these, so changing them should be a deliberate act. 
+        let expected_values = [
+            (EnumPiecesUncolored::Pawn, 100),
+            (EnumPiecesUncolored::Knight, 300),
+            (EnumPiecesUncolored::Bishop, 310),
+            (EnumPiecesUncolored::Rook, 500),
+            (EnumPiecesUncolored::Queen, 900),
+            (EnumPiecesUncolored::King, 1900),
+        ];
+        for (piece_type, expected_value) in expected_values {
+            println!("{0:?}: {1} (expected {2})", piece_type, piece_type.centipawn_value(), expected_value);
+        }
+        println!("All as expected: {}", expected_values.iter().all(|(piece_type, expected_value)| piece_type.centipawn_value() == *expected_value));
+    }
+
+    if testing_fen_display {
+        let canonical_startpos = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        println!("{0} (matches canonical: {1})", STARTPOS, format!("{}", STARTPOS) == canonical_startpos);
+
+        // Printing a parsed FEN should give back the same string. 
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k3/8/8/8/8/8/8/4K2R b Kq - 37 80",
+        ] {
+            let printed_fen = UnwrappedFen::from_fen(fen).unwrap().to_string();
+            println!("{0} (round trips: {1})", printed_fen, printed_fen == fen);
+        }
+
+        // And so should printing, parsing and printing again, counters and all. 
+        let mut mismatches = 0;
+        for position in random_playout_positions(1603, &[(STARTPOS, 100, 200)]) {
+            let printed_fen = position.to_fen();
+            match UnwrappedFen::from_fen(&printed_fen) {
+                Err(message) => {
+                    println!("{0}: {1}", printed_fen, message);
+                    mismatches += 1;
+                },
+                Ok(reparsed) => if reparsed.to_fen() != printed_fen || reparsed.zobrist_hash() != position.zobrist_hash() {
+                    mismatches += 1;
+                },
+            }
+        }
+        println!("to_fen round trip mismatches over random games: {}", mismatches);
+    }
+
+    if fuzzing_legal_moves {
+        // Random games from the usual perft positions, checking the real legality filter against 
+        // making every move. Pins and en passant are where this has gone wrong before. 
+        let fuzz_starts: Vec<UnwrappedFen> = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ].iter().map(|fen| UnwrappedFen::from_fen(fen).unwrap()).collect();
+        let mut rng = XorShiftRng::new(1604);
+        let (positions_checked, disagreements) = fuzz_legal_moves(&fuzz_starts, 50, 150, &mut rng);
+        for disagreement in disagreements.iter().take(10) {
+            println!("{}", disagreement);
+        }
+        println!("Legal move fuzzing: {0} positions, {1} disagreements", positions_checked, disagreements.len());
+    }
+
+    if testing_ponder_flow {
+        use chess::uci::UciEngine;
+        let mut engine = UciEngine::default();
+        engine.handle_line("position startpos moves e2e4 e7e5");
+
+        // 3000ms on the clock gives a 100ms budget, which a ponder search should ignore. 
+        engine.handle_line("go ponder wtime 3000 btime 3000");
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        let before_ponderhit = engine.search_progress();
+        println!("After 300ms of pondering: pondering {0}, finished {1}, progress {2:?}", engine.is_pondering(), engine.search_finished(), before_ponderhit);
+        let ponderhit_time = std::time::Instant::now();
+        engine.handle_line("ponderhit");
+        println!("After ponderhit: pondering {}", engine.is_pondering());
+        while !engine.search_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        let after_ponderhit = engine.search_progress();
+        println!("Bestmove {0:?} after ponderhit, progress {1:?}", ponderhit_time.elapsed(), after_ponderhit);
+        match (before_ponderhit, after_ponderhit) {
+            (Some((depth_before, nodes_before)), Some((depth_after, nodes_after))) => 
+                println!("Ponder work kept: {}", depth_after >= depth_before && nodes_after >= nodes_before),
+            _ => println!("No progress to compare"),
+        }
+        engine.wait_for_search();
+
+        // Running out of depth while pondering still mustn't produce a bestmove. 
+        engine.handle_line("go ponder depth 2");
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        println!("Depth 2 ponder after 200ms: finished {}", engine.search_finished());
+        engine.handle_line("ponderhit");
+        engine.wait_for_search();
+
+        // The opponent played something else: stop, then start over on the real position. 
+        engine.handle_line("go ponder wtime 3000 btime 3000");
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let stop_time = std::time::Instant::now();
+        engine.handle_line("stop");
+        println!("Stopped after {:?}", stop_time.elapsed());
+        engine.handle_line("position startpos moves e2e4 c7c5");
+        engine.handle_line("go movetime 100");
+        engine.wait_for_search();
+    }
+
+    if testing_zobrist_counters {
+        let hash_of = |fen: &str| UnwrappedFen::from_fen(fen).unwrap().zobrist_hash();
+        let italian = "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3";
+        let italian_later = "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 11 7";
+
+        // The counters shouldn't matter to the hash. 
+        println!("Counters ignored: {}", hash_of(italian) == hash_of(italian_later));
+
+        // Castling rights and real ep captures should. 
+        println!("Castling rights matter: {}", hash_of(italian) != hash_of("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b Qkq - 3 3"));
+        println!("A capturable ep square matters: {}", 
+            hash_of("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3") != hash_of("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3"));
+        println!("An uncapturable ep square doesn't: {}", 
+            hash_of("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1") == hash_of("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"));
+        println!("Nor does a pawn on the far side of the board: {}", 
+            hash_of("rnbqkbnr/1ppppppp/8/p7/7P/8/PPPPPPP1/RNBQKBNR w KQkq a6 0 3") == hash_of("rnbqkbnr/1ppppppp/8/p7/7P/8/PPPPPPP1/RNBQKBNR w KQkq - 0 3"));
+    }
+
+    if testing_perft_after_moves {
+        // The first_test_pos_two offender, in one line instead of forty. Should match that 
+        // block's total of 50, all four g2g1 promotions included. 
+        let kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        match perft_after_moves(kiwipete, &["a1b1", "h3g2", "e5c6"], 1) {
+            Err(message) => println!("{}", message),
+            Ok((total_num, sub_perfts)) => println!("Total {0}, g2g1 promotions: {1}", total_num, 
+                sub_perfts.iter().filter(|(move_made, _)| move_made.to_uci_string().starts_with("g2g1")).count()),
+        }
+        println!("{:?}", perft_after_moves(kiwipete, &["a1b1", "a1b1"], 1).map(|(total_num, _)| total_num));
+    }
+
+    if testing_legal_moves_into {
+        // Appending after moves that are already in the buffer should leave them be and add 
+        // exactly what get_legal_proper_moves would give. 
+        let positions = random_playout_positions(1608, &[(STARTPOS, 100, 200)]);
+        let mut mismatches = 0;
+        let mut buffer = Vec::new();
+        for window in positions.windows(2) {
+            buffer.clear();
+            window[0].get_legal_proper_moves_into(&mut buffer);
+            let earlier_count = buffer.len();
+            window[1].get_legal_proper_moves_into(&mut buffer);
+            let expected: Vec<String> = window[0].get_legal_proper_moves().iter().chain(window[1].get_legal_proper_moves().iter())
+                .map(|legal_move| legal_move.to_uci_string()).collect();
+            let got: Vec<String> = buffer.iter().map(|legal_move| legal_move.to_uci_string()).collect();
+            if got != expected || earlier_count != window[0].get_legal_proper_moves().len() {
+                mismatches += 1;
+            }
+        }
+        println!("get_legal_proper_moves_into mismatches: {}", mismatches);
+        println!("Startpos perft 5: {0}, Kiwipete perft 4: {1}", depth_n_total_perft(STARTPOS, 5), 
+            depth_n_total_perft(UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap(), 4));
+    }
+
+    if testing_promotion_order {
+        use chess::implementations::impls_vzero::search_code::*;
+        // Perft still has to see every promotion, in PROMOTION_ORDER. 
+        let promoting = UnwrappedFen::from_fen("2r5/1P6/8/8/8/8/6k1/K7 w - - 0 1").unwrap();
+        let promotion_sans: Vec<String> = promoting.get_legal_proper_moves().iter()
+            .filter(|legal_move| matches!(legal_move, ChessMove::PromotionMove(_)))
+            .map(|legal_move| promoting.to_san(*legal_move)).collect();
+        println!("Promotions generated: {:?}", promotion_sans);
+        println!("Perft 1: {0}, perft 3: {1}", depth_n_total_perft(promoting, 1), depth_n_total_perft(promoting, 3));
+
+        // The search should find the same move either way, just with fewer nodes. 
+        for skipping in [false, true] {
+            let mut context = SearchContext::new(promoting.get_color(), 0);
+            context.skip_rook_bishop_promotions = skipping;
+            match search_best_move(&promoting, 4, &mut context) {
+                None => println!("Search failed somehow"),
+                Some((best_move, score)) => println!("Skipping rook/bishop promotions {0}: {1} ({2}) in {3} nodes", 
+                    skipping, promoting.to_san(best_move), score, context.nodes),
+            }
+        }
+    }
+
+    if testing_generic_negamax {
+        use chess::implementations::impls_vzero::search_code::*;
+        // The generic negamax on UnwrappedFen should agree with alpha-beta on the score of the 
+        // best move, mates included. Mate distance pruning, promotion skipping, futility pruning 
+        // and quiescence are switched off so that alpha-beta searches exactly the same tree. 
+        for (fen, depth) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 3),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3),
+            ("3k4/8/8/8/8/8/8/1R2K1R1 w - - 0 1", 4),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2),
+        ] {
+            let mut position = UnwrappedFen::from_fen(fen).unwrap();
+            let generic_result = search::searches::negamax_best_move(&mut position, depth, &hce_stm);
+            let mut context = SearchContext::new(position.get_color(), 0);
+            context.use_mate_distance_pruning = false;
+            context.skip_rook_bishop_promotions = false;
+            context.use_futility_pruning = false;
+            context.use_quiescence = false;
+            let ab_result = search_best_move(&position, depth, &mut context);
+            println!("{0} at depth {1}: negamax {2:?}, alpha-beta {3:?}, same score: {4}", fen, depth, 
+                generic_result.map(|(best_move, score)| (best_move.to_uci_string(), score)), 
+                ab_result.map(|(best_move, score)| (best_move.to_uci_string(), score)),
+                generic_result.map(|(_, score)| score) == ab_result.map(|(_, score)| score));
+        }
+    }
+
+    if testing_uci_terminal_go {
+        use chess::uci::UciEngine;
+        // Both should print "bestmove (none)" straight away, without starting a search. 
+        let mut engine = UciEngine::default();
+        for position_command in [
+            "position fen rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+            "position fen 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+        ] {
+            engine.handle_line(position_command);
+            engine.handle_line("go depth 3");
+            println!("Search started: {}", !engine.search_finished());
+        }
+        // A normal position still gets a real bestmove. 
+        engine.handle_line("position startpos");
+        engine.handle_line("go depth 2");
+        engine.wait_for_search();
+    }
+
+    if testing_rook_files {
+        // Same rook on d1 each time. The d-file is open, then half-open (black has a pawn 
+        // more there), then blocked by white's own pawn. 
+        let mut evals = Vec::new();
+        for fen in [
+            "4k3/ppp2ppp/8/8/8/8/PPP2PPP/3R2K1 w - - 0 1",
+            "4k3/pppp1ppp/8/8/8/8/PPP2PPP/3R2K1 w - - 0 1",
+            "4k3/pppp1ppp/8/8/8/8/PPPP1PPP/3R2K1 w - - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            evals.push(hce_stm(&position));
+            println!("{0}: d-file open {1}, half-open for white {2}, eval {3}", fen, 
+                position.file_is_open(EnumFile::D), position.file_is_half_open(EnumFile::D, EnumColor::White), hce_stm(&position));
+        }
+        // The first and last have the same material, so only the file separates them. 
+        println!("Open file beats being behind our own pawn: {}", evals[0] > evals[2]);
+    }
+
+    if testing_bishop_pair {
+        // a1 is dark and h1 is light. 
+        println!("a1 {0:?}, h1 {1:?}, d4 {2:?}, e4 {3:?}", 0i8.square_color(), 7i8.square_color(), 27i8.square_color(), 28i8.square_color());
+
+        // The pieces sit on the same squares each time, only what they are changes, so the 
+        // tables can't make the difference. Bishops on c1 and f1 are a real pair, and the 
+        // pair is worth more than the usual 10cp gap between bishop and knight. 
+        let bishop_pair = UnwrappedFen::from_fen("2b1kn2/pppppppp/8/8/8/8/PPPPPPPP/2B1KB2 w - - 0 1").unwrap();
+        let bishop_knight = UnwrappedFen::from_fen("2b1kn2/pppppppp/8/8/8/8/PPPPPPPP/2B1KN2 w - - 0 1").unwrap();
+        let knight_bishop = UnwrappedFen::from_fen("2b1kb2/pppppppp/8/8/8/8/PPPPPPPP/2B1KN2 w - - 0 1").unwrap();
+        println!("White bishops on {:?}", bishop_pair.find_pieces(EnumColor::White, EnumPiecesUncolored::Bishop));
+        println!("B+B vs B+N: {0}, B+N vs B+N: {1}, B+N vs B+B: {2}", hce_stm(&bishop_pair), hce_stm(&bishop_knight), hce_stm(&knight_bishop));
+        println!("Pair side is better: {}", hce_stm(&bishop_pair) > hce_stm(&bishop_knight) && hce_stm(&knight_bishop) < hce_stm(&bishop_knight));
+
+        // Two bishops on the same color aren't a pair. 
+        let same_color_bishops = UnwrappedFen::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/2B1K1B1 w - - 0 1").unwrap();
+        let same_color_weights = EvalWeights { bishop_pair: [0, 0], ..DEFAULT_WEIGHTS };
+        println!("Same-colored bishops get no bonus: {}", hce_stm(&same_color_bishops) == hce_stm_weighted(&same_color_bishops, &same_color_weights));
+    }
+
+    if testing_tempo {
+        // The startpos is level, so whoever is to move should be exactly a tempo up. 
+        let mut black_to_move = STARTPOS;
+        black_to_move.make_null_move();
+        println!("Startpos: {0} with white to move, {1} with black to move, tempo is {2}", hce_stm(&STARTPOS), hce_stm(&black_to_move), DEFAULT_WEIGHTS.tempo);
+
+        // More generally, handing the move over flips everything but the tempo, so the two 
+        // scores should add up to two tempi. The mirror symmetry test covers the rest. 
+        let mut positions_checked = 0;
+        let mut tempo_mismatches = 0;
+        for position in random_playout_positions(1614, &[(STARTPOS, 100, 200)]) {
+            if position.mover_in_check() {
+                continue
+            }
+            positions_checked += 1;
+            let mut passed = position;
+            passed.make_null_move();
+            if hce_stm(&position) + hce_stm(&passed) != 2 * DEFAULT_WEIGHTS.tempo {
+                tempo_mismatches += 1;
+                println!("Tempo mismatch in {:?}", position);
+            }
+        }
+        println!("Checked {0} positions, {1} tempo mismatches.", positions_checked, tempo_mismatches);
+        let no_tempo = EvalWeights { tempo: 0, ..DEFAULT_WEIGHTS };
+        println!("Startpos without tempo: {}", hce_stm_weighted(&STARTPOS, &no_tempo));
+    }
+
+    if testing_likely_draws {
+        // The knight side is up on the board but can't win. The tempo stays on top of the 
+        // scaled score, so compare with it taken out. 
+        let no_tempo = EvalWeights { tempo: 0, ..DEFAULT_WEIGHTS };
+        for (name, fen, should_be_drawn) in [
+            ("KNvKN", "4k3/8/2n5/8/8/8/3N4/4K3 w - - 0 1", true),
+            ("KNvK", "4k3/8/8/8/8/8/3N4/4K3 w - - 0 1", true),
+            ("KNNvK", "4k3/8/8/8/8/8/3NN3/4K3 w - - 0 1", true),
+            ("KRvKR", "4k3/r7/8/8/8/8/7R/4K3 w - - 0 1", true),
+            ("KQvK", "4k3/8/8/8/8/8/3Q4/4K3 w - - 0 1", false),
+            ("KQvKR", "4k3/r7/8/8/8/8/3Q4/4K3 w - - 0 1", false),
+            ("KRvK", "4k3/8/8/8/8/8/7R/4K3 w - - 0 1", false),
+            ("KBNvK", "4k3/8/8/8/8/8/3NB3/4K3 w - - 0 1", false),
+            ("KBBvK", "4k3/8/8/8/8/8/3BB3/4K3 w - - 0 1", false),
+            ("KPvKN", "4k3/8/2n5/8/8/8/3P4/4K3 w - - 0 1", false),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            println!(
+                "{0}: likely drawn {1} (expected {2}), eval {3} with material {4}", 
+                name, position.is_likely_drawn(), should_be_drawn, hce_stm_weighted(&position, &no_tempo), position.get_accumulator().material
+            );
+        }
+    }
+
+    if testing_fen_errors {
+        // Each kind of mistake should come back as its own variant, with the same message 
+        // as before when displayed. 
+        for bad_fen in [
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w KX - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - e9 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - e3x 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - z 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 z",
+            "4k3/8/8/8/8/8/8/4K3p w - - 0 1",
+            "4k3/8/8/8/8/8/8/K3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w",
+        ] {
+            match UnwrappedFen::from_fen(bad_fen) {
+                Ok(_) => println!("{}: parsed?!", bad_fen),
+                Err(error) => println!("{0}: {1:?} / {2}", bad_fen, error, error),
+            }
+        }
+
+        // The point of all this: callers can match on what went wrong. 
+        let truncated = UnwrappedFen::from_fen("4k3/8/8/8/8/8/8/4K3 w");
+        println!("Truncated FEN detected by kind: {}", matches!(truncated, Err(FenParseError::EndedEarly)));
+        let as_string: Result<UnwrappedFen, String> = UnwrappedFen::from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1").map_err(String::from);
+        println!("As a String: {:?}", as_string.map(|_| ()));
+    }
+
+    if testing_attackers_of {
+        // e5 is contested: the d4 pawn and the f3 knight hit it for white, the d6 pawn and 
+        // the e8 rook (through nothing) defend it for black. The bishop on b2 is behind the 
+        // d4 pawn, so it doesn't count yet. 
+        let contested = UnwrappedFen::from_fen("4r1k1/8/3p4/4p3/3P4/5N2/1B6/4K3 w - - 0 1").unwrap();
+        let e5 = 36i8;
+        let attackers = contested.attackers_of(e5);
+        for (square, color) in attackers.iter() {
+            println!("{0:?} piece on {1} bears on e5", color, square);
+        }
+        println!(
+            "Found {0} white and {1} black pieces, expected 2 and 2", 
+            attackers.iter().filter(|(_, color)| *color == EnumColor::White).count(), 
+            attackers.iter().filter(|(_, color)| *color == EnumColor::Black).count()
+        );
+
+        // A pawn that could promote four ways on the square only counts once. 
+        let promotion_square = UnwrappedFen::from_fen("3r2k1/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        println!("Attackers of d8: {:?}", promotion_square.attackers_of(59i8));
+    }
+
+    if testing_king_tracking {
+        // make_move only touches the king squares for standard moves and castling, so check 
+        // that they always agree with where the kings actually are. 
+        let kings_tracked = |position: &UnwrappedFen| {
+            position.find_pieces(EnumColor::White, EnumPiecesUncolored::King) == vec![position.get_w_king_square()]
+                && position.find_pieces(EnumColor::Black, EnumPiecesUncolored::King) == vec![position.get_b_king_square()]
+        };
+        let kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        for (name, moves) in [
+            ("King moves", vec!["e1d1", "e8d8", "d1c1", "d8c8"]),
+            ("Castling", vec!["e1g1", "e8c8"]),
+            ("Both", vec!["e1c1", "e8g8", "c1b1", "g8h8", "b1a1", "h8g8"]),
+            ("Other pieces only", vec!["a2a3", "b4a3", "e2a6", "e6d5"]),
+        ] {
+            let mut position = kiwipete;
+            let mut all_tracked = kings_tracked(&position);
+            for move_string in moves.iter() {
+                match position.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == *move_string) {
+                    None => {
+                        println!("{0}: {1} isn't legal in {2}", name, move_string, position);
+                        all_tracked = false;
+                        break
+                    },
+                    Some(legal_move) => position.make_move(legal_move),
+                }
+                all_tracked &= kings_tracked(&position);
+            }
+            println!("{0}: kings tracked {1}, ending at {2} and {3}", name, all_tracked, position.get_w_king_square(), position.get_b_king_square());
+        }
+
+        // And across a pile of random games, which get promotions and en passant in too. 
+        let mut positions_checked = 0;
+        let mut lost_kings = 0;
+        for position in random_playout_positions(1618, &[(STARTPOS, 100, 200), (kiwipete, 100, 200)]) {
+            positions_checked += 1;
+            if !kings_tracked(&position) {
+                lost_kings += 1;
+                println!("King squares out of date in {}", position);
+            }
+        }
+        println!("Checked {0} positions, {1} with the king squares out of date.", positions_checked, lost_kings);
+    }
+
+    if testing_static_move_order {
+        // The successor evals should match evaluating each move by hand, and come out in 
+        // ascending order for the opponent, so the free queen gets taken first. 
+        let hanging_queen = UnwrappedFen::from_fen("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1").unwrap();
+        let legal_moves = hanging_queen.get_legal_proper_moves();
+        let scored_moves = moves_with_static_evals(&hanging_queen, &legal_moves);
+        let evals_match = scored_moves.iter().all(|(scored_move, successor_eval)| *successor_eval == fast_material_eval(&hanging_queen.after_move(*scored_move)));
+        let ascending = scored_moves.windows(2).all(|pair| pair[0].1 <= pair[1].1);
+        println!("{0} moves scored, evals match {1}, ascending {2}", scored_moves.len(), evals_match, ascending);
+        for (scored_move, successor_eval) in scored_moves.iter().take(3) {
+            println!("{0}: {1}", scored_move, successor_eval);
+        }
+
+        // ab_best_move goes through it now, so make sure it still finds the capture. 
+        println!("ab_best_move: {:?}", ab_best_move(&hanging_queen, 2, value::OutStyleScore::MatedIn(0), value::OutStyleScore::MatedIn(0), 50, true).map(|(best_move, score)| (best_move.to_string(), score)));
+    }
+
+    if testing_max_ply {
+        use chess::implementations::impls_vzero::search_code::*;
+        // Queens on both sides and nothing in the way, so almost every line is a run of 
+        // checks. With check extensions on, the depth barely goes down along those lines. 
+        let checks_everywhere = UnwrappedFen::from_fen("7k/8/8/2q5/8/5Q2/8/K7 w - - 0 1").unwrap();
+        for use_check_extensions in [false, true] {
+            let mut context = SearchContext::new(checks_everywhere.get_color(), 0);
+            context.use_check_extensions = use_check_extensions;
+            let search_start = std::time::Instant::now();
+            let found = search_best_move(&checks_everywhere, 4, &mut context);
+            println!(
+                "Check extensions {0}: {1:?} after {2} nodes in {3:?}", 
+                use_check_extensions, found.map(|(best_move, score)| (best_move.to_string(), score)), context.nodes, search_start.elapsed()
+            );
+        }
+
+        // However much depth is left, a node at MAX_PLY is just a static eval. 
+        let in_check = UnwrappedFen::from_fen("7k/8/8/8/8/3q4/8/K7 w - - 0 1").unwrap();
+        let mut context = SearchContext::new(in_check.get_color(), 0);
+        context.use_check_extensions = true;
+        let capped_score = alpha_beta(&in_check, 100, i32::MIN + 1, i32::MAX, MAX_PLY, &mut context);
+        println!("At MAX_PLY with depth 100: {0} in {1} node(s), static eval {2}", capped_score, context.nodes, hce_stm(&in_check));
+    }
+
+    if testing_fen_ep_output {
+        // The ep field is the square the pawn skipped over, not the one it landed on, and it 
+        // should only last for the one move. 
+        for (moves, expected_fen) in [
+            (vec!["e2e4"], "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            (vec!["e2e4", "c7c5"], "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"),
+            (vec!["e2e4", "c7c5", "g1f3"], "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"),
+            (vec!["e2e3"], "rnbqkbnr/pppppppp/8/8/8/4P3/PPPP1PPP/RNBQKBNR b KQkq - 0 1"),
+        ] {
+            let mut position = STARTPOS;
+            for move_string in moves.iter() {
+                let legal_move = position.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == *move_string).unwrap();
+                position.make_move(legal_move);
+            }
+            let printed_fen = position.to_fen();
+            let round_trips = UnwrappedFen::from_fen(&printed_fen).map(|reparsed| reparsed.to_fen() == printed_fen).unwrap_or(false);
+            println!("{0}: {1} (as expected: {2}, round trips: {3})", moves.join(" "), printed_fen, printed_fen == expected_fen, round_trips);
+        }
+    }
+
+    if testing_attack_geometry {
+        // Every attacker type and color on every square, against every target, on an 
+        // otherwise empty board. The expected answers come straight from the geometry, so 
+        // nothing here leans on the ray or offset code that sees_obvious_attack uses. 
+        let piece_types = [
+            EnumPiecesUncolored::Pawn, EnumPiecesUncolored::Knight, EnumPiecesUncolored::Bishop, 
+            EnumPiecesUncolored::Rook, EnumPiecesUncolored::Queen, EnumPiecesUncolored::King,
+        ];
+        let should_attack = |piece_type: EnumPiecesUncolored, color: EnumColor, from_square: i8, target_square: i8| {
+            let rank_gap = target_square / 8 - from_square / 8;
+            let file_gap = target_square % 8 - from_square % 8;
+            let forwards = match color {
+                EnumColor::White => 1,
+                EnumColor::Black => -1,
+            };
+            match piece_type {
+                EnumPiecesUncolored::Pawn => rank_gap == forwards && file_gap.abs() == 1,
+                EnumPiecesUncolored::Knight => (rank_gap.abs() == 1 && file_gap.abs() == 2) || (rank_gap.abs() == 2 && file_gap.abs() == 1),
+                EnumPiecesUncolored::Bishop => rank_gap.abs() == file_gap.abs(),
+                EnumPiecesUncolored::Rook => rank_gap == 0 || file_gap == 0,
+                EnumPiecesUncolored::Queen => rank_gap.abs() == file_gap.abs() || rank_gap == 0 || file_gap == 0,
+                EnumPiecesUncolored::King => rank_gap.abs() <= 1 && file_gap.abs() <= 1,
+            }
+        };
+        let mut cases_checked = 0;
+        let mut failures = 0;
+        for target_square in 0..64i8 {
+            for from_square in (0..64i8).filter(|from_square| *from_square != target_square) {
+                for piece_type in piece_types {
+                    for color in [EnumColor::White, EnumColor::Black] {
+                        let mut board = [-1i8; 64];
+                        board.set_square(from_square, i8::build_piece(color, piece_type));
+                        let expected = should_attack(piece_type, color, from_square, target_square);
+                        cases_checked += 2;
+
+                        // Only the other side's pieces ever count as attacking. 
+                        if board.sees_obvious_attack(color.get_opposite_color(), target_square) != expected {
+                            failures += 1;
+                            println!("{0:?} {1:?} on {2} vs {3}: expected {4}", color, piece_type, from_square, target_square, expected);
+                        }
+                        if board.sees_obvious_attack(color, target_square) {
+                            failures += 1;
+                            println!("{0:?} {1:?} on {2} counted against its own side on {3}", color, piece_type, from_square, target_square);
+                        }
+                    }
+                }
+            }
+        }
+        println!("Lone attackers: checked {0} cases, {1} failures.", cases_checked, failures);
+
+        // Sliders further than a square away get blocked by anything on the square next to 
+        // the target. The blocker is a knight, which can't hit the target from next door 
+        // itself, and it's tried in both colors. 
+        let mut blocked_cases = 0;
+        let mut blocked_failures = 0;
+        for target_square in 0..64i8 {
+            for from_square in 0..64i8 {
+                let rank_gap = target_square / 8 - from_square / 8;
+                let file_gap = target_square % 8 - from_square % 8;
+                let distance = rank_gap.abs().max(file_gap.abs());
+                if distance < 2 || !(rank_gap.abs() == file_gap.abs() || rank_gap == 0 || file_gap == 0) {
+                    continue
+                }
+                let blocking_square = target_square - 8 * rank_gap.signum() - file_gap.signum();
+                for piece_type in [EnumPiecesUncolored::Bishop, EnumPiecesUncolored::Rook, EnumPiecesUncolored::Queen, EnumPiecesUncolored::King] {
+                    for color in [EnumColor::White, EnumColor::Black] {
+                        for blocker_color in [EnumColor::White, EnumColor::Black] {
+                            let mut board = [-1i8; 64];
+                            board.set_square(from_square, i8::build_piece(color, piece_type));
+                            board.set_square(blocking_square, i8::build_piece(blocker_color, EnumPiecesUncolored::Knight));
+                            blocked_cases += 1;
+                            if board.sees_obvious_attack(color.get_opposite_color(), target_square) {
+                                blocked_failures += 1;
+                                println!("{0:?} {1:?} on {2} sees through {3} to {4}", color, piece_type, from_square, blocking_square, target_square);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        println!("Blocked sliders (and far-away kings): checked {0} cases, {1} failures.", blocked_cases, blocked_failures);
+    }
+
+    if testing_uci_scores {
+        use chess::implementations::impls_vzero::search_code::*;
+        use chess::uci::uci_score_string;
+        for (name, score, expected) in [
+            ("We mate next ply", MATE_SCORE - 1, "mate 1"),
+            ("We mate in 3 plies", MATE_SCORE - 3, "mate 2"),
+            ("We get mated in 2 plies", -MATE_SCORE + 2, "mate -1"),
+            ("We get mated in 4 plies", -MATE_SCORE + 4, "mate -2"),
+            ("Mated at the root", -MATE_SCORE, "mate 0"),
+            ("Big but not a mate", MATE_THRESHOLD, "cp 999000"),
+            ("Ordinary", -35, "cp -35"),
+        ] {
+            println!("{0}: {1} (as expected: {2})", name, uci_score_string(score), uci_score_string(score) == expected);
+        }
+
+        // And out of real searches: the rooks mate in one, and the other side is getting 
+        // mated in one whatever it does. 
+        for (fen, depth) in [("3k4/8/3K4/8/8/8/8/R7 w - - 0 1", 2), ("7k/1R6/6K1/8/8/8/8/8 b - - 0 1", 3)] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let mut context = SearchContext::new(position.get_color(), 0);
+            match search_best_move(&position, depth, &mut context) {
+                None => println!("{}: no move found", fen),
+                Some((best_move, score)) => println!("{0}: {1} with score {2}", fen, best_move.to_uci_string(), uci_score_string(score)),
+            }
+        }
+    }
+
+    if testing_game_stack {
+        use chess::implementations::impls_vzero::game_code::Game;
+        let mut game = Game::new(STARTPOS);
+        for move_string in ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"] {
+            if let Err(message) = game.push_uci_move(move_string) {
+                println!("{}", message);
+            }
+        }
+        println!("After {0} moves: {1}", game.moves_played().len(), game.get_position());
+        println!("Illegal move rejected: {:?}", game.push_uci_move("e1g1"));
+
+        // Popping everything should give back STARTPOS exactly, hash and all. 
+        let mut popped = Vec::new();
+        while let Some(popped_move) = game.pop_move() {
+            popped.push(popped_move.to_uci_string());
+        }
+        println!("Popped {}", popped.join(" "));
+        println!(
+            "Back to STARTPOS: {0}, same hash: {1}, nothing left to pop: {2}", 
+            format!("{:?}", game.get_position()) == format!("{:?}", STARTPOS), 
+            game.get_position().zobrist_hash() == STARTPOS.zobrist_hash(), 
+            game.pop_move().is_none()
+        );
+    }
+
+    if testing_go_infinite {
+        use chess::uci::UciEngine;
+        let mut engine = UciEngine::default();
+        engine.handle_line("position startpos moves e2e4 e7e5");
+
+        // This used to spin forever in the parser. Now info lines should keep coming until 
+        // the stop, and the bestmove only after it. 
+        engine.handle_line("go infinite");
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        println!("After 500ms: finished {0}, progress {1:?}", engine.search_finished(), engine.search_progress());
+        let stop_time = std::time::Instant::now();
+        engine.handle_line("stop");
+        println!("Stopped after {:?}", stop_time.elapsed());
+
+        // Even when there's nothing left to search, the bestmove has to wait for the stop. 
+        engine.handle_line("position fen 7k/8/8/8/8/8/8/K7 w - - 0 1");
+        engine.handle_line("go infinite");
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        println!("KvK after 300ms: finished {0}, progress {1:?}", engine.search_finished(), engine.search_progress());
+        engine.handle_line("stop");
+
+        // The other go options after infinite still get read. 
+        engine.handle_line("position startpos");
+        engine.handle_line("go infinite searchmoves e2e4 d2d4");
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        engine.handle_line("stop");
+    }
+
+    if testing_squares_between {
+        let a1 = 0i8;
+        for (name, other_square, expected) in [
+            ("a1 to e1", 4i8, vec![1i8, 2, 3]),
+            ("a1 to b3", 17i8, vec![]),
+            ("a1 to h8", 63i8, vec![9i8, 18, 27, 36, 45, 54]),
+            ("a1 to a2", 8i8, vec![]),
+            ("a1 to a1", 0i8, vec![]),
+        ] {
+            let between = a1.squares_between(other_square);
+            println!("{0}: {1:?} (as expected: {2})", name, between, between == expected);
+        }
+        // Which way round shouldn't matter beyond the order. 
+        println!("e1 to a1: {:?}", 4i8.squares_between(a1));
+    }
+
+    if testing_out_style_search {
+        use value::OutStyleScore;
+        // Negating twice changes nothing, and negating flips the order, except between a draw 
+        // and an even score, which sit the same way round for both sides on purpose. 
+        let scores = [
+            OutStyleScore::MatedIn(1), OutStyleScore::MatedIn(6), OutStyleScore::Centipawn(-80), OutStyleScore::Draw, 
+            OutStyleScore::Centipawn(0), OutStyleScore::Centipawn(35), OutStyleScore::MatingIn(9), OutStyleScore::MatingIn(2),
+        ];
+        let mut neg_failures = 0;
+        for score in scores {
+            if -(-score) != score {
+                neg_failures += 1;
+            }
+            for other_score in scores {
+                let draw_pair = matches!((score, other_score), (OutStyleScore::Draw, OutStyleScore::Centipawn(0)) | (OutStyleScore::Centipawn(0), OutStyleScore::Draw));
+                if !draw_pair && (score < other_score) != (-other_score < -score) {
+                    neg_failures += 1;
+                }
+            }
+        }
+        println!("Neg failures: {}", neg_failures);
+        println!("Mated here, seen from the parent: {:?}", -OutStyleScore::MatedIn(0).ply_earlier());
+        println!("Big centipawn scores fit now: {:?}", -OutStyleScore::Centipawn(900).plus_centipawns(15));
+
+        // ab_best_move gets mates and stalemates right now. 
+        for (name, fen, depth) in [
+            ("Mate in 1", "3k4/8/3K4/8/8/8/8/R7 w - - 0 1", 2),
+            ("Stalemated", "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3),
+            ("Plain position", "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", 3),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            match ab_best_move(&position, depth, OutStyleScore::MatedIn(0), OutStyleScore::MatedIn(0), 15, true) {
+                None => println!("{}: search failed", name),
+                Some((best_move, score)) => println!("{0}: {1} with score {2:?}", name, best_move, score),
+            }
+        }
+
+        // The OutStyleScore negamax against the i32 one in search.rs, which goes through the 
+        // moves in the same order and also keeps the first of any equally good ones, so away 
+        // from mates they should pick the same moves with the same scores. The one exception 
+        // is a game ending on the last ply, which the i32 one looks for before evaluating and 
+        // negamax_evaluate doesn't, so positions where that can happen are skipped. 
+        fn game_ends_on_last_ply(position: &UnwrappedFen, depth: i8) -> bool {
+            let legal_moves = position.get_legal_proper_moves();
+            match depth <= 0 {
+                true => legal_moves.is_empty(),
+                false => legal_moves.into_iter().any(|legal_move| game_ends_on_last_ply(&position.after_move(legal_move), depth - 1)),
+            }
+        }
+        let positions: Vec<_> = random_playout_positions(1627, &[(STARTPOS, 8, 40)]).into_iter().step_by(8).collect();
+        let mut compared = 0;
+        let mut skipped = 0;
+        let mut move_differences = 0;
+        let mut score_differences = 0;
+        for position in positions.iter() {
+            if game_ends_on_last_ply(position, 3) {
+                skipped += 1;
+                continue
+            }
+            let (out_style_move, out_style_score) = negamax_best_move(position, 3);
+            let out_style_centipawns = match out_style_score {
+                OutStyleScore::Centipawn(centipawns) => centipawns,
+                OutStyleScore::Draw => 0,
+                // Mates are what OutStyleScore does differently, so they don't count here. 
+                _ => continue,
+            };
+            let i32_found = search::searches::negamax_best_move(&mut { *position }, 3, &hce_stm);
+            compared += 1;
+            match i32_found {
+                None => move_differences += 1,
+                Some((i32_move, i32_score)) => {
+                    if i32_move.to_uci_string() != out_style_move.to_uci_string() {
+                        move_differences += 1;
+                    }
+                    if i32_score != out_style_centipawns {
+                        score_differences += 1;
+                    }
+                },
+            }
+        }
+        println!("Compared {0} positions at depth 3, skipped {1}: {2} different moves, {3} different scores", compared, skipped, move_differences, score_differences);
+        assert!(move_differences == 0 && score_differences == 0);
+    }
+
+    if testing_automatic_draws {
+        use chess::implementations::impls_vzero::game_code::*;
+        // Knights out and back, so startpos comes around again every four plies. 
+        let mut game = Game::new(STARTPOS);
+        for round in 1..=4 {
+            for move_string in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                game.push_uci_move(move_string).unwrap();
+            }
+            let result = game.game_result();
+            println!(
+                "Startpos seen {0} times: {1:?}, ends the game: {2}", 
+                round + 1, result, result.map(|game_result| game_result.ends_game()).unwrap_or(false)
+            );
+        }
+
+        // The 75-move rule, counted by the position itself now that its clock goes past 100. 
+        // The white king takes the long way round, so nothing repeats. 
+        let mut game = Game::new(UnwrappedFen::from_fen("7k/8/8/8/8/8/8/K6N w - - 120 100").unwrap());
+        let white_king_walk = ["a1a2", "a2a3", "a3a4", "a4a5", "a5a6", "a6a7", "a7a8", "a8b8", "b8b7", "b7b6", "b6b5", "b5b4", "b4b3", "b3b2", "b2c2"];
+        for (index, white_move) in white_king_walk.iter().enumerate() {
+            game.push_uci_move(white_move).unwrap();
+            let black_move = match index % 2 == 0 {
+                true => "h8g8",
+                false => "g8h8",
+            };
+            if index == white_king_walk.len() - 1 {
+                println!("Before the last move: clock {0}, {1:?}", game.halfmove_clock(), game.game_result());
+            }
+            game.push_uci_move(black_move).unwrap();
+        }
+        println!("After it: clock {0}, position clock {1}, {2:?}", game.halfmove_clock(), game.get_position().get_ply_count(), game.game_result());
+
+        // A capture starts the count over. 
+        let mut game = Game::new(UnwrappedFen::from_fen("7k/8/8/8/8/8/1n6/K7 w - - 99 100").unwrap());
+        game.push_uci_move("a1a2").unwrap();
+        println!("At 100 plies: {:?}", game.game_result());
+        game.push_uci_move("h8g8").unwrap();
+        game.push_uci_move("a2b2").unwrap();
+        println!("After Kxb2: clock {0}, {1:?}", game.halfmove_clock(), game.game_result());
+    }
+
+    if testing_piece_movegen {
+        // Exact move sets for each of the get_likely_*_moves methods on small positions. These 
+        // are pseudo-legal, so pins and attacked squares don't come into it. 
+        for (name, fen, generator, square, expected) in [
+            ("Pawn pushes", "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "P", 12i8, vec!["e2e3", "e2e4"]),
+            ("Double push blocked", "4k3/8/8/8/4n3/8/4P3/4K3 w - - 0 1", "P", 12, vec!["e2e3"]),
+            ("Single push blocked", "4k3/8/8/8/8/4n3/4P3/4K3 w - - 0 1", "P", 12, vec![]),
+            ("Pawn captures", "4k3/8/8/8/8/3p1n2/4P3/4K3 w - - 0 1", "P", 12, vec!["e2d3", "e2e3", "e2e4", "e2f3"]),
+            ("No capturing our own", "4k3/8/8/8/8/3P1N2/4P3/4K3 w - - 0 1", "P", 12, vec!["e2e3", "e2e4"]),
+            ("No wrapping round the board", "4k3/8/8/8/8/1p5p/P7/4K3 w - - 0 1", "P", 8, vec!["a2a3", "a2a4", "a2b3"]),
+            ("Promotions", "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "P", 49, vec!["b7b8b", "b7b8n", "b7b8q", "b7b8r"]),
+            ("Capture promotions", "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "P", 49, vec!["b7a8b", "b7a8n", "b7a8q", "b7a8r", "b7b8b", "b7b8n", "b7b8q", "b7b8r"]),
+            ("Black pawn pushes", "4k3/4p3/8/8/8/8/8/4K3 b - - 0 1", "P", 52, vec!["e7e5", "e7e6"]),
+            ("Black pawn captures", "4k3/4p3/3N1P2/8/8/8/8/4K3 b - - 0 1", "P", 52, vec!["e7d6", "e7e5", "e7e6", "e7f6"]),
+            ("Black promotions", "4k3/8/8/8/8/8/6p1/4K2R b - - 0 1", "P", 14, vec!["g2g1b", "g2g1n", "g2g1q", "g2g1r", "g2h1b", "g2h1n", "g2h1q", "g2h1r"]),
+            ("Knight in the corner", "4k3/8/8/8/8/8/8/N3K3 w - - 0 1", "N", 0, vec!["a1b3", "a1c2"]),
+            ("Knight next to its own piece", "4k3/8/8/8/8/8/2P5/N3K3 w - - 0 1", "N", 0, vec!["a1b3"]),
+            ("Knight next to an enemy piece", "4k3/8/8/8/8/8/2p5/N3K3 w - - 0 1", "N", 0, vec!["a1b3", "a1c2"]),
+            ("Knight in the other corner", "4k2N/8/8/8/8/8/8/4K3 w - - 0 1", "N", 63, vec!["h8f7", "h8g6"]),
+            ("Rook between friend and foe", "4k3/8/8/8/8/P7/8/R2n2K1 w - - 0 1", "R", 0, vec!["a1a2", "a1b1", "a1c1", "a1d1"]),
+            ("Bishop between friend and foe", "4k3/8/8/8/8/4n3/1P6/2B1K3 w - - 0 1", "B", 2, vec!["c1d2", "c1e3"]),
+            ("Queen boxed in", "4k3/8/8/8/8/8/2PPn3/2BQK3 w - - 0 1", "Q", 3, vec!["d1e2"]),
+            ("King", "4k3/8/8/8/8/8/3Pp3/3QK3 w - - 0 1", "K", 4, vec!["e1e2", "e1f1", "e1f2"]),
+            ("En passant", "4k3/8/8/2pPp3/8/8/8/4K3 w - c6 0 1", "ep", 0, vec!["d5c6"]),
+            ("En passant from both sides", "4k3/8/8/1PpP4/8/8/8/4K3 w - c6 0 1", "ep", 0, vec!["b5c6", "d5c6"]),
+            ("Black en passant", "4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1", "ep", 0, vec!["e4d3"]),
+            ("Castling both ways", "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "castle", 0, vec!["e1c1", "e1g1"]),
+            ("Queenside blocked on b1", "r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1", "castle", 0, vec!["e1g1"]),
+            ("No rights left", "r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1", "castle", 0, vec![]),
+            ("Through check is for later", "r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1", "castle", 0, vec!["e1c1", "e1g1"]),
+            ("Black castling", "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "castle", 0, vec!["e8c8", "e8g8"]),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let generated = match generator {
+                "P" => position.get_likely_pawn_moves(square),
+                "N" => position.get_likely_knight_moves(square),
+                "B" => position.get_likely_bishop_moves(square),
+                "R" => position.get_likely_rook_moves(square),
+                "Q" => position.get_likely_queen_moves(square),
+                "K" => position.get_likely_king_moves(square),
+                "ep" => position.get_likely_ep_moves(),
+                _ => position.get_likely_castling_moves(),
+            };
+            let mut generated_strings: Vec<String> = generated.iter().map(|generated_move| generated_move.to_uci_string()).collect();
+            generated_strings.sort();
+            match generated_strings == expected {
+                true => println!("{}: ok", name),
+                false => println!("{0}: got {1:?}, expected {2:?}", name, generated_strings, expected),
+            }
+        }
+    }
+
+    if testing_quiet_moves {
+        // Classify a few hand-picked moves, then the positions themselves. 
+        for (fen, uci, expected) in [
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e2e4", true),
+            ("4k3/8/8/8/8/3p4/4P3/4K3 w - - 0 1", "e2d3", false),
+            ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n", false),
+            ("4k3/8/8/2pP4/8/8/8/4K3 w - c6 0 1", "d5c6", false),
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", false),
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a7", true),
+            ("4k3/8/8/8/8/8/4B3/4RK2 w - - 0 1", "e2b5", false),
+            ("r3k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", true),
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", false),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let possible_move = position.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == uci).unwrap();
+            match position.is_quiet(possible_move) == expected {
+                true => println!("{0} in {1}: ok", uci, fen),
+                false => println!("{0} in {1}: expected is_quiet to be {2}", uci, fen, expected),
+            }
+        }
+        for (fen, expected) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", true),
+            ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", false),
+            ("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/r3K3 w - - 0 1", false),
+            ("4k3/8/8/2pP4/8/8/8/4K3 w - c6 0 1", false),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            match position.is_position_quiet() == expected {
+                true => println!("{}: ok", fen),
+                false => println!("{0}: expected is_position_quiet to be {1}", fen, expected),
+            }
+        }
+    }
+
+    if testing_futility_pruning {
+        use chess::implementations::impls_vzero::search_code::*;
+        // The tactics should come out the same with and without futility pruning, just cheaper. 
+        // (WAC.002 would belong here, but Rxb2 is too deep for this search either way.) 
+        for (fen, expected_move) in [
+            ("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1", "g3g6"),
+            ("r1bq2rk/pp3pbp/2p1p1pQ/7P/3P4/2PB1N2/PP3PPR/2KR4 w - - 0 1", "h6h7"),
+            ("3k4/8/8/8/8/8/8/1R2K1R1 w - - 0 1", "b1b7"),
+            ("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", "d1d8"),
+            ("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1", "h5f7"),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            for use_futility_pruning in [false, true] {
+                let mut context = SearchContext::new(position.get_color(), 0);
+                context.use_futility_pruning = use_futility_pruning;
+                let search_start = std::time::Instant::now();
+                match search_best_move(&position, 5, &mut context) {
+                    None => println!("No move found"),
+                    Some((best_move, score)) => println!(
+                        "{0}, pruning {1}: plays {2} ({3}), score {4}, {5} nodes in {6:?}", 
+                        fen, use_futility_pruning, best_move.to_uci_string(), 
+                        match best_move.to_uci_string() == expected_move { true => "ok", false => "expected a different move" }, 
+                        score, context.nodes, search_start.elapsed()
+                    ),
+                }
+            }
+        }
+    }
+
+    if testing_delta_pruning {
+        use chess::implementations::impls_vzero::search_code::*;
+        // Quiescence values with and without delta pruning, over some random middlegames and 
+        // endgames. The values should agree; the node counts shouldn't. 
+        let mut positions = random_playout_positions(1632, &[(STARTPOS, 20, 120)]);
+        for fen in ["8/3k4/8/3p4/2pPp3/2P1P3/3K1q2/8 w - - 0 1", "4k3/8/3r4/8/3N4/2P5/1P6/4K2R w K - 0 1"] {
+            positions.push(UnwrappedFen::from_fen(fen).unwrap());
+        }
+        let mut mismatches = 0;
+        let mut node_totals = [0, 0];
+        for position in positions.iter() {
+            let mut scores = [0, 0];
+            for (index, use_delta_pruning) in [false, true].into_iter().enumerate() {
+                let mut context = SearchContext::new(position.get_color(), 0);
+                context.use_delta_pruning = use_delta_pruning;
+                scores[index] = quiescence(position, i32::MIN + 1, i32::MAX, 0, &mut context);
+                node_totals[index] += context.nodes;
+            }
+            if scores[0] != scores[1] {
+                mismatches += 1;
+                println!("{0}: {1} without delta pruning, {2} with", position.to_fen(), scores[0], scores[1]);
+            }
+        }
+        println!("{0} positions, {1} mismatches, {2} nodes without delta pruning, {3} with", positions.len(), mismatches, node_totals[0], node_totals[1]);
+
+        // And the full search with quiescence on still finds its tactics. 
+        let position = UnwrappedFen::from_fen("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1").unwrap();
+        let mut context = SearchContext::new(position.get_color(), 0);
+        context.use_quiescence = true;
+        println!("WAC.001 with quiescence: {0:?}, {1} nodes", search_best_move(&position, 4, &mut context).map(|(best_move, score)| (best_move.to_uci_string(), score)), context.nodes);
+    }
+
+    if testing_multi_pv {
+        use chess::implementations::impls_vzero::search_code::*;
+        use chess::uci::UciEngine;
+        // Three different legal moves, best first, and the first one is what a single-PV 
+        // search would have played. 
+        let position = UnwrappedFen::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let legal_strings: Vec<String> = position.get_legal_proper_moves().iter().map(|legal_move| legal_move.to_uci_string()).collect();
+        let mut context = SearchContext::new(position.get_color(), 0);
+        let lines = search_best_moves(&position, 3, 3, None, &mut context);
+        let line_strings: Vec<(String, i32)> = lines.iter().map(|line| (line.best_move.to_uci_string(), line.score)).collect();
+        println!("MultiPV 3 from startpos: {:?}", line_strings);
+        println!("Three lines: {}", lines.len() == 3);
+        println!("All legal: {}", line_strings.iter().all(|(move_string, _score)| legal_strings.contains(move_string)));
+        println!("All distinct: {}", line_strings.iter().enumerate().all(|(index, line)| line_strings[..index].iter().all(|earlier_line| earlier_line.0 != line.0)));
+        println!("Ranked by score: {}", line_strings.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        let mut single_context = SearchContext::new(position.get_color(), 0);
+        println!("First line matches the single-PV search: {}", iterative_deepening(&position, 3, None, &mut single_context, |_depth, _best_move, _score, _nodes| {}).map(|(best_move, _score)| best_move.to_uci_string()) == line_strings.first().map(|line| line.0.clone()));
+
+        // Every line comes with its own PV: a legal sequence starting from its root move, and 
+        // as long as the depth unless the line runs into a position that has ended. Each 
+        // score is exact, the same as searching that move on its own with a full window. 
+        for line in lines.iter() {
+            let pv_strings: Vec<String> = line.pv.iter().map(|pv_move| pv_move.to_uci_string()).collect();
+            let mut pv_position = position;
+            let pv_legal = line.pv.iter().all(|pv_move| {
+                let legal = pv_position.get_legal_proper_moves().iter().any(|legal_move| legal_move.to_uci_string() == pv_move.to_uci_string());
+                pv_position.make_move(*pv_move);
+                legal
+            });
+            let mut line_context = SearchContext::new(position.get_color(), 0);
+            let direct_score = -alpha_beta(&position.after_move(line.best_move), 2, i32::MIN + 1, i32::MAX, 1, &mut line_context);
+            println!("PV {0:?}: starts with its move {1}, legal {2}, at least 3 moves {3}, score matches a direct search {4}", pv_strings, pv_strings.first() == Some(&line.best_move.to_uci_string()), pv_legal, pv_strings.len() >= 3, direct_score == line.score);
+        }
+
+        // Asking for more lines than there are moves just gives all of them. 
+        let few_moves = UnwrappedFen::from_fen("7k/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let mut context = SearchContext::new(few_moves.get_color(), 0);
+        println!("MultiPV 10 with 3 legal moves: {} lines", search_best_moves(&few_moves, 2, 10, None, &mut context).len());
+
+        // Through UCI. Expect one info line per PV at each depth. 
+        let mut engine = UciEngine::default();
+        engine.handle_line("setoption name multipv value 3");
+        println!("MultiPV option: {}", engine.get_multi_pv());
+        engine.handle_line("position startpos");
+        engine.handle_line("go depth 2");
+        engine.wait_for_search();
+        engine.handle_line("setoption name MultiPV value 500");
+        println!("Clamped MultiPV option: {}", engine.get_multi_pv());
+    }
+
+    if testing_search_moves {
+        use chess::implementations::impls_vzero::search_code::*;
+        use chess::uci::UciEngine;
+        // Restricted to one move, the search has to play it, and should give it the same score 
+        // as searching it on its own would. 
+        let position = UnwrappedFen::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let only_move: Vec<_> = position.get_legal_proper_moves().into_iter().filter(|legal_move| legal_move.to_uci_string() == "a2a3").collect();
+        let mut context = SearchContext::new(position.get_color(), 0);
+        let restricted = iterative_deepening(&position, 4, Some(&only_move), &mut context, |_depth, _best_move, _score, _nodes| {});
+        let mut context = SearchContext::new(position.get_color(), 0);
+        let direct_score = -alpha_beta(&position.after_move(only_move[0]), 3, i32::MIN + 1, i32::MAX, 1, &mut context);
+        println!("Restricted to a2a3: {0:?}, searching it directly gives {1}", restricted.map(|(best_move, score)| (best_move.to_uci_string(), score)), direct_score);
+
+        // Underpromotions survive a restriction, and a restriction with nothing legal in it is 
+        // no restriction at all. 
+        let promotion_position = UnwrappedFen::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let underpromotion: Vec<_> = promotion_position.get_legal_proper_moves().into_iter().filter(|legal_move| legal_move.to_uci_string() == "b7b8r").collect();
+        let mut context = SearchContext::new(promotion_position.get_color(), 0);
+        println!("Restricted to b7b8r: {:?}", iterative_deepening(&promotion_position, 3, Some(&underpromotion), &mut context, |_depth, _best_move, _score, _nodes| {}).map(|(best_move, _score)| best_move.to_uci_string()));
+        let mut context = SearchContext::new(position.get_color(), 0);
+        println!("Restricted to nothing legal: {:?}", iterative_deepening(&position, 3, Some(&underpromotion), &mut context, |_depth, _best_move, _score, _nodes| {}).map(|(best_move, _score)| best_move.to_uci_string()));
+
+        // Through UCI, with a couple of candidates and one junk move. 
+        let mut engine = UciEngine::default();
+        engine.handle_line("position startpos");
+        engine.handle_line("go depth 3 searchmoves h2h3 g1h3 e2e5");
+        engine.wait_for_search();
+    }
+
+    if testing_ray_kinds {
+        use chess::abstracts::helper_types::{RayKind, SmallOffset};
+        // The eight queen directions, then a few things that aren't directions at all. 
+        let steps = [SmallOffset::MinusOne, SmallOffset::Stay, SmallOffset::PlusOne];
+        for rank_offset in steps {
+            for file_offset in steps {
+                let expected = match (rank_offset, file_offset) {
+                    (SmallOffset::Stay, SmallOffset::Stay) => RayKind::Invalid,
+                    (SmallOffset::Stay, _) | (_, SmallOffset::Stay) => RayKind::Orthogonal,
+                    _ => RayKind::Diagonal,
+                };
+                let kind = RayKind::of_direction((rank_offset, file_offset));
+                println!("{0:?}: {1:?} ({2})", (rank_offset, file_offset), kind, match kind == expected { true => "ok", false => "wrong" });
+            }
+        }
+        for direction in [(SmallOffset::PlusTwo, SmallOffset::PlusOne), (SmallOffset::MinusOne, SmallOffset::MinusTwo), (SmallOffset::PlusTwo, SmallOffset::Stay)] {
+            println!("{0:?}: {1:?}", direction, RayKind::of_direction(direction));
+        }
+        // Rays built between squares classify the same way. a1 to h8, a1 to a8, a1 to b3. 
+        println!("a1-h8: {0:?}, a1-a8: {1:?}, a1-b3: {2:?}", 
+            0i8.try_get_ray_to(63).map(|ray| ray.kind()), 
+            0i8.try_get_ray_to(56).map(|ray| ray.kind()), 
+            0i8.try_get_ray_to(17).map(|ray| ray.kind())
+        );
+        // Pin and check detection went through the new classification, so rerun the checks on them. 
+        let mut mismatches = 0;
+        for position in random_playout_positions(1635, &[(STARTPOS, 50, 80)]) {
+            for legal_move in position.get_legal_proper_moves() {
+                if position.gives_check(legal_move) != position.gives_check_by_making(legal_move) {
+                    mismatches += 1;
+                }
+            }
+        }
+        println!("gives_check mismatches: {}", mismatches);
+        println!("Kiwipete perft 3: {}", depth_n_total_perft(UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap(), 3));
+    }
+
+    if benchmarking_perfts {
+        // Depth 4 keeps plain perft on Kiwipete to a few seconds. 
+        benchmark_perfts(4);
+    }
+
+    if testing_promotion_castling {
+        // Promoting onto a rook that could still castle takes that side's right away, and 
+        // nothing else. Checked against the same position read straight from a FEN. 
+        for (fen, uci, expected_fen) in [
+            ("r3k2r/1P6/8/8/8/8/8/4K3 w kq - 0 1", "b7a8q", "Q3k2r/8/8/8/8/8/8/4K3 b k - 0 1"),
+            ("r3k2r/6P1/8/8/8/8/8/4K3 w kq - 0 1", "g7h8n", "r3k2N/8/8/8/8/8/8/4K3 b q - 0 1"),
+            ("4k3/8/8/8/8/8/1p6/R3K2R b KQ - 0 1", "b2a1q", "4k3/8/8/8/8/8/8/q3K2R w K - 0 2"),
+            ("1r2k2r/P7/8/8/8/8/8/4K3 w k - 0 1", "a7b8q", "1Q2k2r/8/8/8/8/8/8/4K3 b k - 0 1"),
+            ("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1", "b7b8q", "rQ2k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1"),
+        ] {
+            let mut position = UnwrappedFen::from_fen(fen).unwrap();
+            let promotion = position.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == uci).unwrap();
+            position.make_move(promotion);
+            let expected = UnwrappedFen::from_fen(expected_fen).unwrap();
+            println!("{0} after {1}: {2} ({3})", fen, uci, position.to_fen(), 
+                match position.to_fen() == expected_fen && position.zobrist_hash() == expected.zobrist_hash() {
+                    true => "ok",
+                    false => "expected something else",
+                }
+            );
+        }
+    }
+
+    if testing_capture_analysis {
+        // Kiwipete has plenty of captures, and the other two have en passant and 
+        // capture-promotions. 
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/1pPp4/8/8/8/4K3 w - d6 0 1",
+            "rn2k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            println!("{}", fen);
+            for analysed_capture in capture_analysis(&position) {
+                println!("    {}", analysed_capture);
+            }
+        }
+    }
+
+    if testing_hash_option {
+        use chess::uci::UciEngine;
+        // Each size should give the power of two entries that fits, and silly sizes get clamped. 
+        let mut engine = UciEngine::default();
+        println!("Default: {0} MB, {1} entries", engine.get_hash_mb(), engine.hash_capacity());
+        for hash_value in ["1", "64", "100", "0", "100000", "lots"] {
+            engine.handle_line(&format!("setoption name Hash value {}", hash_value));
+            println!("Hash {0}: {1} MB, {2} entries", hash_value, engine.get_hash_mb(), engine.hash_capacity());
+        }
+        engine.handle_line("uci");
+    }
+
+    if testing_new_game {
+        use chess::implementations::impls_vzero::ttable_code::*;
+        use chess::implementations::impls_vzero::packed_move_code::PackedMove;
+        use chess::uci::UciEngine;
+        // Fill some of the table by hand, since the search doesn't write to it yet. 
+        let mut engine = UciEngine::default();
+        let mut rng = XorShiftRng::new(1640);
+        for _ in 0..1000 {
+            engine.get_transposition_table().store(rng.next_u64(), TTData { score: 1, depth: 1, bound: Bound::Exact, best_move: PackedMove::NULL });
+        }
+        engine.handle_line("position startpos moves e2e4 e7e5");
+        println!("Before ucinewgame: {0} entries used, position {1}, {2} positions of history", engine.get_transposition_table().occupied(), engine.get_position().to_fen(), engine.get_game_history().len());
+        engine.handle_line("ucinewgame");
+        println!("After ucinewgame: {0} entries used, position {1}, {2} positions of history", engine.get_transposition_table().occupied(), engine.get_position().to_fen(), engine.get_game_history().len());
+    }
+
+    if testing_knight_outposts {
+        // White knight on d5 with pawns on c4 and e4. Black has pawns on a7 and h7, so 
+        // nothing can ever chase it away. 
+        let outpost = UnwrappedFen::from_fen("4k3/p6p/8/3N4/2P1P3/8/8/4K3 w - - 0 1").unwrap();
+        println!("White pawns on d5: {0} (expect 2), black pawns on d5: {1} (expect 0)", 
+            outpost.count_pawn_attackers(EnumColor::White, 35), outpost.count_pawn_attackers(EnumColor::Black, 35));
+        println!("d5 defended: {0}, d4 defended: {1} (expect true, false)", outpost.pawn_defends(EnumColor::White, 35), outpost.pawn_defends(EnumColor::White, 27));
+        println!("Outpost on d5: {} (expect true)", outpost.is_outpost(EnumColor::White, 35));
+        // A black pawn on e7 can still come to e6 and kick it, and a knight on d3 is at home. 
+        let kickable = UnwrappedFen::from_fen("4k3/4p3/8/3N4/2P1P3/8/8/4K3 w - - 0 1").unwrap();
+        println!("Outpost with a pawn on e7: {} (expect false)", kickable.is_outpost(EnumColor::White, 35));
+        let at_home = UnwrappedFen::from_fen("4k3/8/8/8/8/3N4/2P1P3/4K3 w - - 0 1").unwrap();
+        println!("Outpost on d3: {} (expect false)", at_home.is_outpost(EnumColor::White, 19));
+        // Black's version, mirrored: knight on d4, pawns on c5 and e5. 
+        let black_outpost = UnwrappedFen::from_fen("4k3/8/8/2p1p3/3n4/8/P6P/4K3 b - - 0 1").unwrap();
+        println!("Black outpost on d4: {} (expect true)", black_outpost.is_outpost(EnumColor::Black, 27));
+
+        // The eval only moves by the weight. 
+        let no_outposts = EvalWeights { knight_outpost: [0, 0], bishop_outpost: [0, 0], ..DEFAULT_WEIGHTS };
+        println!("Eval with outposts: {0}, without: {1}", hce_stm(&outpost), hce_stm_weighted(&outpost, &no_outposts));
+        println!("Eval with a pawn on e7: {0}, without outposts: {1}", hce_stm(&kickable), hce_stm_weighted(&kickable, &no_outposts));
+    }
+
+    if testing_outpost_scaling {
+        // The same position with the knight on its outpost and back on b1. Taking away what 
+        // the piece-square tables make of the move leaves the outpost bonus on its own. 
+        let no_outposts = EvalWeights { knight_outpost: [0, 0], bishop_outpost: [0, 0], ..DEFAULT_WEIGHTS };
+        for (outpost_fen, elsewhere_fen) in [
+            ("4k3/p6p/8/8/3N4/2P5/8/4K3 w - - 0 1", "4k3/p6p/8/8/8/2P5/8/1N2K3 w - - 0 1"),
+            ("4k3/p6p/8/3N4/2P5/8/8/4K3 w - - 0 1", "4k3/p6p/8/8/2P5/8/8/1N2K3 w - - 0 1"),
+            ("4k3/p6p/3N4/2P5/8/8/8/4K3 w - - 0 1", "4k3/p6p/8/2P5/8/8/8/1N2K3 w - - 0 1"),
+            ("4k3/p6p/3B4/2P5/8/8/8/4K3 w - - 0 1", "4k3/p6p/8/2P5/8/8/8/1B2K3 w - - 0 1"),
+            ("4k3/8/8/2p5/3n4/8/P6P/4K3 b - - 0 1", "4k3/8/8/2p5/8/8/P6P/1n2K3 b - - 0 1"),
+        ] {
+            let outpost = UnwrappedFen::from_fen(outpost_fen).unwrap();
+            let elsewhere = UnwrappedFen::from_fen(elsewhere_fen).unwrap();
+            let with_bonus = hce_stm(&outpost) - hce_stm(&elsewhere);
+            let without_bonus = hce_stm_weighted(&outpost, &no_outposts) - hce_stm_weighted(&elsewhere, &no_outposts);
+            println!("{0}: {1} better than {2}, {3} of it from the outpost", outpost_fen, with_bonus, elsewhere_fen, with_bonus - without_bonus);
+        }
+    }
+
+    if testing_moves_by_piece_type {
+        // Each piece type's moves, checked against the full pseudo-legal list split up by 
+        // what's on the from square. Castling belongs to the king and en passant to the pawns. 
+        for fen in [
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/2pP4/8/8/8/4K3 w - c6 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let moved_type = |pseudo_legal_move: &ChessMove<i8, i8>| match pseudo_legal_move {
+                ChessMove::StandardMove(standard_move) => position.query_square(standard_move.from_square).get_contents().map(|piece| piece.get_piece_type()),
+                ChessMove::PromotionMove(_) | ChessMove::EnPassantMove(_) => Some(EnumPiecesUncolored::Pawn),
+                ChessMove::CastlingMove(_) => Some(EnumPiecesUncolored::King),
+                ChessMove::NullMove => None,
+            };
+            let all_moves = position.get_pseudo_legal_proper_moves();
+            for piece_type in [EnumPiecesUncolored::Pawn, EnumPiecesUncolored::Knight, EnumPiecesUncolored::Bishop, EnumPiecesUncolored::Rook, EnumPiecesUncolored::Queen, EnumPiecesUncolored::King] {
+                let mut expected: Vec<String> = all_moves.iter().filter(|pseudo_legal_move| moved_type(pseudo_legal_move) == Some(piece_type)).map(|pseudo_legal_move| pseudo_legal_move.to_uci_string()).collect();
+                let mut generated: Vec<String> = position.get_moves_for_piece_type(piece_type).iter().map(|piece_move| piece_move.to_uci_string()).collect();
+                expected.sort();
+                generated.sort();
+                if piece_type == EnumPiecesUncolored::Knight {
+                    println!("{0} knight moves: {1:?}", fen, generated);
+                }
+                if generated != expected {
+                    println!("{0}: {1:?} moves don't match. Got {2:?}, expected {3:?}", fen, piece_type, generated, expected);
+                }
+            }
+        }
+    }
+
+    if testing_search_config {
+        use chess::implementations::impls_vzero::search_code::*;
+        use chess::uci::UciEngine;
+        // The default config is the search as it was: same moves, scores and node counts as 
+        // the depth 3 searches recorded just before SearchConfig existed, when the margins were 
+        // still consts. The positions are from random playouts (seed 1644). Quiescence with 
+        // futility pruning isn't in the table, since fast_material_eval has changed what 
+        // futility prunes since then; futility without quiescence still matches. 
+        let recorded_searches: [(&str, [(&str, i32, u64); 3]); 17] = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", [("d2d4", 45, 2232), ("d2d4", 45, 2232), ("d2d4", 27, 2391)]),
+            ("rnb1kbnr/3p1p2/pp2pq2/2p3pp/P3PP1P/1PN2Q1N/2PP2P1/R1B1KB1R b KQkq - 1 8", [("g5g4", 266, 770), ("g5g4", 266, 2200), ("g5g4", 16, 2585)]),
+            ("rnbk2n1/3pbp1r/pp2p2Q/4PPqp/P1p4p/RPN4N/2PP2P1/2B1KB1R w K - 5 16", [("h6h7", 443, 463), ("h6h7", 443, 1517), ("h6g5", 310, 3612)]),
+            ("rnb1k1nr/3pbp2/pp2P3/PN2P1Qp/7p/1P1p3N/R1P2KPR/2B2B2 b - - 0 23", [("e7g5", -1, 258), ("e7g5", -1, 383), ("e7g5", -333, 11857)]),
+            ("r1b1k1n1/3p1p2/p3PQ2/p1b1P2r/3n2p1/NP5p/R1p2R1K/2B2BN1 w - - 4 31", [("e6f7", 1568, 616), ("e6f7", 1568, 1953), ("e6f7", 999995, 4355)]),
+            ("rn1qkbnr/2p1pp2/7p/pp1p1Pp1/4P3/P5Kb/1PPPB1PP/RNBQ2NR b kq - 0 8", [("h3f5", -199, 878), ("h3f5", -199, 878), ("h3f5", -237, 2261)]),
+            ("r3kbnr/3npp2/5P1p/1p4p1/pPpqp1B1/P4NKb/2PP2PP/RNB1QR2 w kq - 1 16", [("f3d4", 900, 305), ("f3d4", 900, 788), ("f3d4", 769, 1097)]),
+            ("r3kbnr/4pp2/2N4p/1p1n2pB/pPp1p3/P6K/1BP2RPP/1R1qQ3 b - - 9 23", [("d1h5", 999997, 191), ("d1h5", 999997, 210), ("d1h5", 999997, 1208)]),
+            ("4kbn1/2r1pp1r/2N4p/1p4p1/p1p1p3/P2Q1PK1/1BP2R1P/6R1 w - - 7 31", [("d3d8", 999999, 898), ("d3d8", 999999, 2414), ("d3d8", 999999, 3045)]),
+            ("1rb1kbnr/2pp1ppp/1pn5/p3p1q1/1P5P/N1PP1N2/P3PPPR/R1BQKB2 b Qk b3 0 8", [("g5g4", 97, 1026), ("g5g4", 97, 2379), ("g5g6", -20, 5878)]),
+            ("1rb2bnr/2p2kqp/5pp1/pp1pP3/1P1nP2P/N1P1B3/P2N2PR/R1Q1KB2 w Q - 0 16", [("c3d4", 385, 535), ("c3d4", 385, 1440), ("c3d4", 421, 45650)]),
+            ("2b2bnr/2p1k1qp/1N2Ppp1/1p1p4/1p1BP2P/2P2N2/P4KPR/R1Q2B2 b - - 0 23", [("c7b6", -537, 253), ("c7b6", -537, 455), ("c7b6", -607, 1472)]),
+            ("2N1kbnr/2p1B2p/4Ppq1/1p1p4/4P2p/5N2/P1p3PR/3RQBK1 w - - 0 31", [("f1b5", 999997, 215), ("f1b5", 999997, 314), ("f1b5", 999997, 6393)]),
+            ("rn1q1bnr/p3pkpp/1pp1b3/3p4/1P2pP2/3P1N2/P1P1Q1PP/R1BNKB1R b KQ b3 0 8", [("e4f3", 315, 196), ("e4f3", 315, 947), ("e4f3", 315, 990)]),
+            ("rn3bnr/p3p1p1/1p2bk2/1Pp2PNp/2Q1p3/2P3P1/P2q3P/R1BNKB1R w KQ - 2 16", [("c1d2", 411, 69), ("c1d2", 411, 92), ("c1d2", 364, 134)]),
+            ("r5nr/p1n1p1b1/1p2Nkp1/1Pp2P1p/8/2P1Q1P1/P6P/1RBNKB1R b - - 0 23", [("g7h6", -1018, 837), ("g7h6", -1018, 1369), ("g7h6", -1355, 4059)]),
+            ("4r3/p3n1br/4nkpB/1Pp2P1p/2B4P/2P3P1/P3K3/1R1N1R2 w - - 2 31", [("f5e6", 823, 236), ("f5e6", 823, 1047), ("f5e6", 535, 1454)]),
+        ];
+        let mut differences = 0;
+        for (fen, recorded_results) in recorded_searches.iter() {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            for ((use_quiescence, use_futility_pruning), (recorded_move, recorded_score, recorded_nodes)) in [(false, true), (false, false), (true, false)].into_iter().zip(recorded_results.iter()) {
+                let mut context = SearchContext::with_config(position.get_color(), SearchConfig::default());
+                context.use_quiescence = use_quiescence;
+                context.use_futility_pruning = use_futility_pruning;
+                let found = search_best_move(&position, 3, &mut context).map(|(best_move, score)| (best_move.to_uci_string(), score));
+                if found != Some((recorded_move.to_string(), *recorded_score)) || context.nodes != *recorded_nodes {
+                    differences += 1;
+                    println!("Differs from the recorded search: {0} (quiescence {1}, futility {2}) gave {3:?} in {4} nodes", fen, use_quiescence, use_futility_pruning, found, context.nodes);
+                }
+            }
+        }
+        println!("Default config vs recorded searches: {0} searches, {1} differences", 3 * recorded_searches.len(), differences);
+        assert_eq!(differences, 0);
+
+        // Changing a margin should change what gets searched. 
+        let position = UnwrappedFen::from_fen("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1").unwrap();
+        for futility_margins in [[0, 200, 500], [0, 50, 100], [0, 1000, 2000]] {
+            let mut context = SearchContext::with_config(position.get_color(), SearchConfig { futility_margins: futility_margins, ..SearchConfig::default() });
+            let found = search_best_move(&position, 4, &mut context).map(|(best_move, score)| (best_move.to_uci_string(), score));
+            println!("Futility margins {0:?}: {1:?}, {2} nodes", futility_margins, found, context.nodes);
+        }
+
+        // And through setoption. 
+        let mut engine = UciEngine::default();
+        println!("Default engine config: {:?}", engine.get_search_config());
+        for option_line in ["setoption name FutilityMargin1 value 150", "setoption name futilitymargin2 value 400", "setoption name DeltaMargin value 99999", "setoption name Contempt value 20"] {
+            engine.handle_line(option_line);
+        }
+        println!("After setoption: {:?}", engine.get_search_config());
+    }
+
+    if testing_divide_file {
+        // Startpos depth 1 is every one of the 20 moves once, and depth 2 is 20 each. 
+        let startpos = UnwrappedFen::startpos();
+        let (_total, sub_perfts) = depth_n_better_perft(startpos, 1);
+        let text = divide_text(&sub_perfts);
+        let lines: Vec<&str> = text.lines().collect();
+        let expected_moves = ["a2a3", "a2a4", "b1a3", "b1c3", "b2b3", "b2b4", "c2c3", "c2c4", "d2d3", "d2d4", 
+            "e2e3", "e2e4", "f2f3", "f2f4", "g1f3", "g1h3", "g2g3", "g2g4", "h2h3", "h2h4"];
+        let expected_lines: Vec<String> = expected_moves.iter().map(|move_string| format!("{} 1", move_string)).collect();
+        println!("Sorted: {}", lines.windows(2).all(|pair| pair[0] < pair[1]));
+        println!("Matches the known depth 1 divide: {}", lines == expected_lines.iter().map(|line| line.as_str()).collect::<Vec<&str>>());
+
+        let divide_path = std::env::temp_dir().join("cladonia_divide.txt");
+        match write_divide(startpos, 2, Some(&divide_path)) {
+            Err(message) => println!("{}", message),
+            Ok(total) => {
+                let written = std::fs::read_to_string(&divide_path).unwrap_or_default();
+                println!("Depth 2 total {0}, {1} lines written, all 20: {2}", total, written.lines().count(), written.lines().all(|line| line.ends_with(" 20")));
+                let _ = std::fs::remove_file(&divide_path);
+            },
+        }
+        println!("Writing somewhere impossible: {:?}", write_divide(startpos, 1, Some(std::path::Path::new("/nonexistent/directory/divide.txt"))));
+    }
+
+    if testing_counter_overflow {
+        // Counters that don't fit should be errors, not panics or wrapped numbers. The biggest 
+        // ones that do fit still parse. 
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 32767 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 32768 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 99999999999 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 32767",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 32768",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 99999999999999",
+        ] {
+            match UnwrappedFen::from_fen(fen) {
+                Ok(position) => println!("{0}: halfmove {1}, fullmove {2}", fen, position.get_ply_count(), position.get_move_count()),
+                Err(error) => println!("{0}: {1:?} / {2}", fen, error, error),
+            }
+        }
+    }
+
+    if testing_wide_halfmove_clock {
+        use chess::implementations::impls_vzero::game_code::Game;
+        // A clock of 150 has to survive parsing, printing, and the moves that bump it. 
+        let fen = "7k/8/8/8/8/8/8/K6N w - - 150 120";
+        let position = UnwrappedFen::from_fen(fen).unwrap();
+        println!("{0}: clock {1}, time up {2}, round trip {3}", fen, position.get_ply_count(), position.time_up(), position.to_fen() == fen);
+
+        let mut game = Game::new(UnwrappedFen::from_fen("7k/8/8/8/8/8/8/K6N w - - 148 120").unwrap());
+        for move_string in ["a1a2", "h8g8"] {
+            game.push_uci_move(move_string).unwrap();
+            println!("After {0}: clock {1}, {2:?}", move_string, game.get_position().get_ply_count(), game.game_result());
+        }
+        println!("{}", game.get_position().to_fen());
+    }
+
+    if testing_quiescence_evasions {
+        use chess::implementations::impls_vzero::search_code::*;
+        // Out of check only captures and promotions make it in. In check every evasion does, 
+        // which here means quiet king steps, and the score shouldn't come out as a mate. 
+        for fen in [
+            "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1",
+            "4r1k1/8/8/8/8/8/8/4K3 w - - 0 1",
+            "6k1/8/8/8/8/8/5PPP/4r1K1 w - - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let mut quiescence_moves: Vec<String> = position.get_quiescence_moves().into_iter().map(|possible_move| possible_move.to_uci_string()).collect();
+            quiescence_moves.sort();
+            let mut context = SearchContext::new(position.get_color(), 0);
+            let score = quiescence(&position, i32::MIN + 1, i32::MAX, 0, &mut context);
+            println!("{0}: in check {1}, moves {2:?}, score {3}, mated {4}", fen, position.mover_in_check(), quiescence_moves, score, score <= -MATE_THRESHOLD);
+        }
+    }
+
+    if testing_eval_sign {
+        // hce_stm is from the mover's point of view, which negamax relies on. Handing the move 
+        // over should flip the sign and nothing else, once the tempo bonus (which always goes 
+        // to whoever is on the move) is taken off both sides. 
+        let tempo = DEFAULT_WEIGHTS.tempo;
+        let positions = random_test_positions(1649);
+        let mut mismatches = 0;
+        for position in positions.iter() {
+            let before = hce_stm(position) - tempo;
+            let after = hce_stm(&position.after_null_move()) - tempo;
+            if before != -after {
+                mismatches += 1;
+                println!("{0}: {1} before the null move, {2} after", position.to_fen(), before, after);
+            }
+        }
+        println!("{0} positions, {1} sign mismatches", positions.len(), mismatches);
+    }
+
+    if testing_checkers {
+        // No check, a plain rook check, a pawn check on the back rank (one square, not four), 
+        // and a knight move that uncovers the rook behind it for a double check. 
+        let mut positions = Vec::new();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/4R1K1 b - - 0 1",
+            "8/8/8/8/8/8/3p4/4K2k w - - 0 1",
+        ] {
+            positions.push(UnwrappedFen::from_fen(fen).unwrap());
+        }
+        let mut double_check = UnwrappedFen::from_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+        let knight_move = double_check.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == "e4d6").unwrap();
+        double_check.make_move(knight_move);
+        positions.push(double_check);
+        for position in positions.iter() {
+            let checking_squares: Vec<String> = position.checkers().into_iter()
+                .map(|square| format!("{0}{1}", (b'a' + (square % 8) as u8) as char, square / 8 + 1))
+                .collect();
+            println!("{0}: checkers {1:?}, in check {2}", position.to_fen(), checking_squares, position.mover_in_check());
+        }
+    }
+
+    if testing_position_stream {
+        use chess::implementations::impls_vzero::epd_code::*;
+        // Two FENs, two EPDs (one with operations, one without), a blank line and two bad 
+        // lines. The bad ones get reported and the good ones after them still come through. 
+        let stream_path = std::env::temp_dir().join("cladonia_positions.epd");
+        let file_text = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";",
+            "",
+            "this is not a position",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/8/8/8/8/8/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2R w K -",
+        ].join("\n");
+        std::fs::write(&stream_path, file_text).unwrap();
+        match stream_positions(&stream_path) {
+            Err(message) => println!("{}", message),
+            Ok(positions) => {
+                let (mut valid, mut invalid) = (0, 0);
+                for parsed in positions {
+                    match parsed {
+                        Ok(position) => {
+                            valid += 1;
+                            println!("{}", position.to_fen());
+                        },
+                        Err(message) => {
+                            invalid += 1;
+                            println!("{}", message);
+                        },
+                    }
+                }
+                println!("{0} valid, {1} invalid", valid, invalid);
+            },
+        }
+        let _ = std::fs::remove_file(&stream_path);
+        println!("A missing file: {:?}", stream_positions(std::path::Path::new("/nonexistent/positions.epd")).err());
+    }
+
+    if testing_divide_down_line {
+        // Each level should be exactly the divide from that child on its own, and its total 
+        // should be the count the level above gave for the move followed. 
+        let kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let line = ["e1g1", "h3g2"];
+        match divide_down_line(kiwipete, 3, &line) {
+            Err(message) => println!("{}", message),
+            Ok(levels) => {
+                let mut curr_pos = kiwipete;
+                for (level, (total_num, sub_perfts)) in levels.iter().enumerate() {
+                    let standalone = depth_n_better_perft(curr_pos, 3 - level as i8);
+                    println!("Level {0}: total {1}, matches standalone divide {2}", level, total_num, *total_num == standalone.0 && divide_text(sub_perfts) == divide_text(&standalone.1));
+                    if level < line.len() {
+                        let (next_move, count_above) = sub_perfts.iter().find(|(move_made, _)| move_made.to_uci_string() == line[level]).unwrap();
+                        println!("  {0} counted {1} here, level below totals {2}", line[level], count_above, levels[level + 1].0);
+                        curr_pos.make_move(*next_move);
+                    }
+                }
+            },
+        }
+        println!("Too deep: {:?}", divide_down_line(kiwipete, 1, &["e1g1"]).err());
+        println!("Illegal: {:?}", divide_down_line(kiwipete, 2, &["e1e3"]).err());
+    }
+
+    if testing_g2g1_promotions {
+        // The first_test_pos_two bug: after a1b1 h3g2 e5c6 from Kiwipete, black's g2g1 came 
+        // out as a plain pawn move and the promotions were missing. All four should be 
+        // generated, the plain move shouldn't be, and all four are legal (the rook and queen 
+        // ones with check along the first rank). 
+        let kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let mut curr_pos = kiwipete;
+        for move_string in ["a1b1", "h3g2", "e5c6"] {
+            let legal_move = curr_pos.get_legal_proper_moves().into_iter().find(|legal_move| legal_move.to_uci_string() == move_string).unwrap();
+            curr_pos.make_move(legal_move);
+        }
+        println!("{}", curr_pos.to_fen());
+        let pseudo_legal_strings: Vec<String> = curr_pos.get_pseudo_legal_proper_moves().into_iter().map(|possible_move| possible_move.to_uci_string()).collect();
+        let legal_moves = curr_pos.get_legal_proper_moves();
+        println!("Plain g2g1 generated: {}", pseudo_legal_strings.iter().any(|move_string| move_string == "g2g1"));
+        for move_string in ["g2g1q", "g2g1r", "g2g1b", "g2g1n"] {
+            let legal_move = legal_moves.iter().find(|legal_move| legal_move.to_uci_string() == move_string);
+            println!("{0}: pseudo-legal {1}, legal {2}, gives check {3:?}", 
+                move_string, pseudo_legal_strings.iter().any(|pseudo_legal_string| pseudo_legal_string == move_string), 
+                legal_move.is_some(), legal_move.map(|legal_move| curr_pos.gives_check(*legal_move)));
+        }
+    }
+
+    if testing_castling_validation {
+        // Rights for a rook that isn't there, for a king that isn't on e1, and for a rook that's 
+        // really a knight. Repairing drops just the bad rules; Kiwipete's are all fine. 
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/4K3 w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R2K3R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/n3K2R w KQkq - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let repaired = UnwrappedFen::from_fen_repairing_castling(fen).unwrap();
+            let castling_moves = repaired.get_legal_proper_moves().into_iter().filter(|legal_move| match legal_move {
+                ChessMove::CastlingMove(_) => true,
+                _ => false,
+            }).count();
+            println!("{0}: valid {1}, repaired to {2}, valid after {3}, {4} castling moves", 
+                fen, position.validate_castling_rights(), repaired.to_fen(), repaired.validate_castling_rights(), castling_moves);
+        }
+    }
+
+    if testing_packed_moves {
+        use chess::implementations::impls_vzero::ttable_code::*;
+        use chess::implementations::impls_vzero::packed_move_code::PackedMove;
+        // Every legal move should pack, go through the table, and unpack to exactly what it 
+        // was, and no two moves in a position should pack the same. ChessMove has no 
+        // PartialEq, so the Debug output stands in for it. 
+        let mut positions = Vec::new();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            positions.push(UnwrappedFen::from_fen(fen).unwrap());
+        }
+        positions.append(&mut random_test_positions(1655));
+        let table = TranspositionTable::with_entries(1024);
+        let (mut moves_checked, mut mismatches, mut collisions) = (0, 0, 0);
+        for position in positions.iter() {
+            let mut packed_moves = Vec::new();
+            for legal_move in position.get_legal_proper_moves() {
+                let packed = PackedMove::from_move(legal_move);
+                let key = position.zobrist_hash() ^ packed.0 as u64;
+                table.store(key, TTData { score: 0, depth: 1, bound: Bound::Exact, best_move: packed });
+                let unpacked = table.probe(key).and_then(|data| data.best_move.to_move(position));
+                moves_checked += 1;
+                if unpacked.map(|unpacked_move| format!("{:?}", unpacked_move)) != Some(format!("{:?}", legal_move)) {
+                    mismatches += 1;
+                    println!("{0}: {1:?} came back as {2:?}", position.to_fen(), legal_move, unpacked);
+                }
+                if packed_moves.contains(&packed) {
+                    collisions += 1;
+                }
+                packed_moves.push(packed);
+            }
+        }
+        println!("{0} positions, {1} moves, {2} round trip mismatches, {3} collisions", positions.len(), moves_checked, mismatches, collisions);
+        println!("Null move: {0:?} -> {1:?}", PackedMove::from_move(ChessMove::NullMove), PackedMove::NULL.to_move(&STARTPOS));
+        println!("Sizes: ChessMove {0} bytes, PackedMove {1} bytes", std::mem::size_of::<ChessMove<i8, i8>>(), std::mem::size_of::<PackedMove>());
+    }
+
+    if testing_blocked_promotions {
+        // A pawn on the seventh with a piece straight ahead (its own or not) can only promote 
+        // by capturing. Only the eight capture-promotions onto d8 and f8 (or d1 and f1) 
+        // should come out, and nothing onto the blocked square. 
+        for fen in [
+            "3rBn2/4P3/8/8/8/8/8/K6k w - - 0 1",
+            "3rbn2/4P3/8/8/8/8/8/K6k w - - 0 1",
+            "K6k/8/8/8/8/8/4p3/3RbN2 b - - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let mut pawn_moves: Vec<String> = position.get_moves_for_piece_type(EnumPiecesUncolored::Pawn).into_iter().map(|pawn_move| pawn_move.to_uci_string()).collect();
+            pawn_moves.sort();
+            let legal_promotions = position.get_legal_proper_moves().into_iter().filter(|legal_move| match legal_move {
+                ChessMove::PromotionMove(_) => true,
+                _ => false,
+            }).count();
+            let forward_square = match position.get_color() {
+                EnumColor::White => "e8",
+                EnumColor::Black => "e1",
+            };
+            println!("{0}: {1} pawn moves, {2} legal promotions, any onto {3}: {4}", 
+                fen, pawn_moves.len(), legal_promotions, forward_square, pawn_moves.iter().any(|pawn_move| &pawn_move[2..4] == forward_square));
+            println!("  {:?}", pawn_moves);
+        }
+    }
+
+    if testing_node_terminals {
+        use chess::implementations::impls_vzero::search_code::*;
+        // Stalemate and dead positions are draws, mate is a mate, and positions that can 
+        // still be won aren't terminal at all. The full search should agree at the root. 
+        for fen in [
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+            "6rk/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+            "7k/6Q1/6K1/8/8/8/8/8 b - - 0 1",
+            "7k/8/6K1/8/8/8/8/8 w - - 0 1",
+            "7k/8/6K1/8/3N4/8/8/8 w - - 0 1",
+            "5b1k/8/6K1/8/8/2B5/8/1B6 w - - 0 1",
+            "6bk/8/6K1/8/8/3B4/8/8 w - - 0 1",
+            "7k/8/6K1/8/3NN3/8/8/8 w - - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let context = SearchContext::new(position.get_color(), 0);
+            let node_score = node_terminal_score(&position, &position.get_legal_proper_moves(), 0, &context);
+            let mut search_context = SearchContext::new(position.get_color(), 0);
+            let search_score = alpha_beta(&position, 2, i32::MIN + 1, i32::MAX, 0, &mut search_context);
+            println!("{0}: node {1:?}, search {2}, mated {3}", fen, node_score, search_score, search_score <= -MATE_THRESHOLD);
+        }
+    }
+
+    if testing_white_tables {
+        // Each white table should be its black one flipped top to bottom. 
+        let mut table_mismatches = 0;
+        for piece_number in 0..6 {
+            for square in 0..64 {
+                let flipped_square = (7 - square / 8) * 8 + square % 8;
+                for tables in [DEFAULT_WEIGHTS.mg_tables, DEFAULT_WEIGHTS.eg_tables] {
+                    if tables[2 * piece_number][square] != tables[2 * piece_number + 1][flipped_square] {
+                        table_mismatches += 1;
+                    }
+                }
+            }
+        }
+        println!("White table entries that aren't the flipped black ones: {}", table_mismatches);
+
+        // And the eval itself shouldn't have moved. The expected checksum is from before the 
+        // white tables existed, when white's squares were flipped on every lookup. 
+        let mut checksum = 0i64;
+        let mut positions = 0;
+        for position in random_playout_positions(1658, &[(STARTPOS, 40, 160)]) {
+            checksum = checksum.wrapping_mul(31).wrapping_add(hce_stm(&position) as i64);
+            positions += 1;
+        }
+        println!("hce_stm checksum over {0} positions: {1}", positions, checksum);
+        assert!(checksum == 3786299735669136737);
+    }
+
+    if testing_pins {
+        // The knight on e2 and the bishop on d2 are both pinned. The queen and bishop on the 
+        // first rank shield each other, so neither is. Each pin should agree with 
+        // pinning_square, and the pinned pieces' legal moves should stay on their lines. 
+        let square_name = |square: i8| format!("{0}{1}", (b'a' + (square % 8) as u8) as char, square / 8 + 1);
+        for fen in [
+            "4r2k/8/8/8/1b6/8/3BN3/r1BQK3 w - - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let pins = position.pins();
+            println!("{0}: {1} pins", fen, pins.len());
+            for pin in pins {
+                let mut pinned_moves: Vec<String> = position.get_legal_proper_moves().into_iter()
+                    .map(|legal_move| legal_move.to_uci_string())
+                    .filter(|move_string| move_string[0..2] == square_name(pin.pinned_square))
+                    .collect();
+                pinned_moves.sort();
+                println!("  {0} pinned by {1} along {2:?}, pinning_square agrees: {3}, moves {4:?}", 
+                    square_name(pin.pinned_square), square_name(pin.pinning_square), pin.direction, 
+                    position.pinning_square(pin.pinned_square) == Some(pin.pinning_square), pinned_moves);
+            }
+        }
+    }
+
+    if testing_pinned_ep {
+        // exd6 e.p. with the e5 pawn pinned on the c3-g7 diagonal: generated, but illegal. 
+        // Without the bishop it's fine, and so it is when the pin runs along the capture 
+        // itself (king on h2, bishop on b8). The last one is the fifth-rank case, where it's 
+        // the victim leaving that exposes the king. The slow reference filter has to agree. 
+        for (fen, expected_legal) in [
+            ("8/6b1/8/3pP3/8/2K5/8/7k w - d6 0 1", false),
+            ("8/8/8/3pP3/8/2K5/8/7k w - d6 0 1", true),
+            ("1b6/8/8/3pP3/8/8/7K/k7 w - d6 0 1", true),
+            ("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1", false),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let generated = position.get_pseudo_legal_proper_moves().iter().any(|possible_move| possible_move.to_uci_string() == "e5d6");
+            let legal = position.get_legal_proper_moves().iter().any(|legal_move| legal_move.to_uci_string() == "e5d6");
+            let reference_legal = reference_legal_moves(position).iter().any(|legal_move| legal_move.to_uci_string() == "e5d6");
+            println!("{0}: generated {1}, legal {2}, reference {3}, as expected {4}", fen, generated, legal, reference_legal, legal == expected_legal && reference_legal == expected_legal);
+        }
+    }
+
+    if testing_material_outlook {
+        // KBvKN and KNNvK can't be forced but aren't dead either, unlike KNvK or same-colored 
+        // bishops. Anything with a pawn, a major piece or a real mating pair is decisive. 
+        for (fen, expected) in [
+            ("7k/8/6K1/8/8/8/8/8 w - - 0 1", MaterialOutlook::Dead),
+            ("7k/8/6K1/8/3N4/8/8/8 w - - 0 1", MaterialOutlook::Dead),
+            ("6bk/8/6K1/8/8/3B4/8/8 w - - 0 1", MaterialOutlook::Dead),
+            ("6nk/8/6K1/8/8/3B4/8/8 w - - 0 1", MaterialOutlook::NoForcedMate),
+            ("7k/8/6K1/8/3NN3/8/8/8 w - - 0 1", MaterialOutlook::NoForcedMate),
+            ("5b1k/8/6K1/8/8/3B4/8/8 w - - 0 1", MaterialOutlook::NoForcedMate),
+            ("7k/8/6K1/8/3NNN2/8/8/8 w - - 0 1", MaterialOutlook::Decisive),
+            ("7k/8/6K1/8/3NB3/8/8/8 w - - 0 1", MaterialOutlook::Decisive),
+            ("7k/8/6K1/8/3BB3/8/8/8 w - - 0 1", MaterialOutlook::Decisive),
+            ("7k/8/6K1/8/8/8/8/R7 w - - 0 1", MaterialOutlook::Decisive),
+            ("7k/8/6K1/8/8/8/P7/8 w - - 0 1", MaterialOutlook::Decisive),
+            ("7k/8/6K1/8/8/8/8/r7 w - - 0 1", MaterialOutlook::Decisive),
+        ] {
+            let outlook = UnwrappedFen::from_fen(fen).unwrap().material_outlook();
+            println!("{0}: {1:?}, as expected {2}", fen, outlook, outlook == expected);
+        }
+    }
+
+    if testing_self_play {
+        use chess::implementations::impls_vzero::search_code::*;
+        use chess::implementations::impls_vzero::match_code::*;
+        // A back rank mate should be a quick white win whoever plays it, and a dead position a 
+        // draw without a move being searched. 
+        let back_rank = UnwrappedFen::from_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1").unwrap();
+        let bare_kings = UnwrappedFen::from_fen("7k/8/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        println!("Back rank: {:?}", play_game(back_rank, SearchConfig::default(), SearchConfig::default(), 2, 20));
+        println!("Bare kings: {:?}", play_game(bare_kings, SearchConfig::default(), SearchConfig::default(), 2, 20));
+
+        // A few quick games between the default config and one that likes to avoid draws. 
+        // Nothing to learn from the score at this depth, but every game should be counted. 
+        let openings: Vec<UnwrappedFen> = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        ].iter().map(|fen| UnwrappedFen::from_fen(fen).unwrap()).collect();
+        let contemptuous = SearchConfig { contempt: 50, ..SearchConfig::default() };
+        let start_time = std::time::Instant::now();
+        let result = play_match(&openings, 4, SearchConfig::default(), contemptuous, 2, 120);
+        println!("{0:?}, {1} games in {2:?}", result, result.wins + result.draws + result.losses, start_time.elapsed());
+    }
+
+    if testing_uci_debug {
+        use chess::uci::UciEngine;
+        // Quiet by default, chatty after "debug on", and quiet again after "debug off". 
+        let mut engine = UciEngine::default();
+        println!("Debug by default: {}", engine.get_debug());
+        for debug_command in ["", "debug on", "debug off"] {
+            engine.handle_line(debug_command);
+            println!("--- after \"{0}\", debug {1}", debug_command, engine.get_debug());
+            engine.handle_line("position startpos moves e2e4");
+            engine.handle_line("go depth 3 searchmoves e7e5 c7c5");
+            engine.wait_for_search();
+        }
+    }
+
+    if testing_uci_register {
+        use chess::uci::parsed_command_debug;
+        // "code" used to come back as a Name. 
+        for line in ["register code 1234567890", "register name Foo", "register name Foo Bar", "register later"] {
+            println!("{0}: {1:?}", line, parsed_command_debug(line));
+        }
+    }
+
+    if testing_material_key {
+        // KRvK, KRvKR and KRPvK are all different material, and KRvK with things moved 
+        // around (or colors swapped to KvKR) is still its own key. 
+        let key_of = |fen: &str| UnwrappedFen::from_fen(fen).unwrap().material_key();
+        let krk = key_of("7k/8/6K1/8/8/8/8/R7 w - - 0 1");
+        let krkr = key_of("r6k/8/6K1/8/8/8/8/R7 w - - 0 1");
+        let krpk = key_of("7k/8/6K1/8/8/8/P7/R7 w - - 0 1");
+        let krk_moved = key_of("8/8/2k5/8/8/5R2/8/K7 b - - 0 1");
+        let kkr = key_of("7K/8/6k1/8/8/8/8/r7 w - - 0 1");
+        println!("KRvK {0:#x}, KRvKR {1:#x}, KRPvK {2:#x}, KvKR {3:#x}", krk, krkr, krpk, kkr);
+        println!("All distinct: {}", krk != krkr && krk != krpk && krkr != krpk && krk != kkr);
+        println!("Moving pieces keeps the key: {}", krk == krk_moved);
+        println!("Startpos: {:#x}", STARTPOS.material_key());
+    }
+
+    if testing_eval_clamp {
+        // Forty-odd queens against a bare king. The raw eval would be somewhere past 40000; it 
+        // should be held at MAX_EVAL, clear of the mate band, for either side to move. 
+        for fen in [
+            "1k6/8/QQQQQQQQ/QQQQQQQQ/QQQQQQQQ/QQQQQQQQ/QQQQQQQQ/QQQQQQK1 b - - 0 1",
+            "1K6/8/qqqqqqqq/qqqqqqqq/qqqqqqqq/qqqqqqqq/qqqqqqqq/qqqqqqk1 w - - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let eval = hce_stm(&position);
+            println!("{0}: hce_stm {1}, weighted {2}, at the limit {3}, below mate threshold {4}", 
+                fen, eval, hce_stm_weighted(&position, &DEFAULT_WEIGHTS), eval.abs() == MAX_EVAL, eval.abs() < MATE_THRESHOLD);
+        }
+    }
+
+    if benchmarking_fast_eval {
+        // Scoring every child for ordering, the way moves_with_static_evals does, once with the 
+        // full eval and once with the fast one. Also how far apart the two evals get. 
+        let positions = random_test_positions(1667);
+        let move_lists: Vec<_> = positions.iter().map(|position| position.get_legal_proper_moves()).collect();
+        let mut checksums = [0i64, 0i64];
+        let mut timings = Vec::new();
+        for (index, eval) in [hce_stm as fn(&UnwrappedFen) -> i32, fast_material_eval].into_iter().enumerate() {
+            let start_time = std::time::Instant::now();
+            for _round in 0..5 {
+                for (position, legal_moves) in positions.iter().zip(move_lists.iter()) {
+                    let mut scored_moves: Vec<_> = legal_moves.iter().map(|legal_move| (*legal_move, eval(&position.after_move(*legal_move)))).collect();
+                    scored_moves.sort_by_key(|(_, successor_eval)| *successor_eval);
+                    checksums[index] = checksums[index].wrapping_add(scored_moves.len() as i64);
+                }
+            }
+            timings.push(start_time.elapsed());
+        }
+        println!("Ordering {0} positions x5: hce_stm {1:?}, fast_material_eval {2:?}, same move counts {3}", positions.len(), timings[0], timings[1], checksums[0] == checksums[1]);
+        let largest_gap = positions.iter().map(|position| (hce_stm(position) - fast_material_eval(position)).abs()).max().unwrap_or(0);
+        let mean_gap = positions.iter().map(|position| (hce_stm(position) - fast_material_eval(position)).abs() as f64).sum::<f64>() / positions.len() as f64;
+        println!("Gap between the evals: mean {0:.1}, largest {1}", mean_gap, largest_gap);
+    }
+
+    if testing_incremental_eval {
+        // update_eval against hce_stm of the position after the move, for every legal move out 
+        // of a pile of random positions, with a few from kiwipete to get more castling in. 
+        use crate::search::eval_abstracts::{Evaluates, IncrementallyUpdatingEvaluator};
+        let positions = random_test_positions(1668);
+        let mut checked_moves = 0;
+        let mut mismatches = 0;
+        for position in positions.iter() {
+            let parent_eval = HceEval::get_evaluation(position);
+            for legal_move in position.get_legal_proper_moves() {
+                checked_moves += 1;
+                let updated_eval = parent_eval.update_eval(position, &legal_move);
+                let scratch_eval = hce_stm(&position.after_move(legal_move));
+                if updated_eval.0 != scratch_eval {
+                    mismatches += 1;
+                    if mismatches <= 5 {
+                        println!("{0} after {1}: update_eval {2}, hce_stm {3}", position.to_fen(), legal_move.to_uci_string(), updated_eval.0, scratch_eval);
+                    }
+                }
+            }
+        }
+        println!("Checked update_eval on {0} moves from {1} positions, {2} mismatches", checked_moves, positions.len(), mismatches);
+
+        // Only the accumulator part is incremental, so this is the number that says whether 
+        // update_eval is worth having over evaluating the position after the move. 
+        // The moves and parent evals are worked out beforehand so that only the evaluating 
+        // gets timed. 
+        let positions_with_moves: Vec<_> = positions.iter().map(|position| (position, HceEval::get_evaluation(position), position.get_legal_proper_moves())).collect();
+        let mut timings = Vec::new();
+        let mut checksums = [0i64; 2];
+        for (index, use_update_eval) in [true, false].into_iter().enumerate() {
+            let start_time = std::time::Instant::now();
+            for _repetition in 0..5 {
+                for (position, parent_eval, legal_moves) in positions_with_moves.iter() {
+                    for legal_move in legal_moves.iter().copied() {
+                        let eval = match use_update_eval {
+                            true => parent_eval.update_eval(position, &legal_move).0,
+                            false => hce_stm(&position.after_move(legal_move)),
+                        };
+                        checksums[index] = checksums[index].wrapping_add(eval as i64);
+                    }
+                }
+            }
+            timings.push(start_time.elapsed());
+        }
+        println!("Evaluating {0} moves x5: update_eval {1:?}, hce_stm after after_move {2:?}, same checksum {3}", checked_moves, timings[0], timings[1], checksums[0] == checksums[1]);
+    }
+
+    if testing_legal_moves_from {
+        // Destinations for a single piece: a knight, pinned pieces, an ep capture and the king 
+        // with both castling moves. 
+        let square_name = |square: i8| format!("{0}{1}", (b'a' + (square % 8) as u8) as char, square / 8 + 1);
+        for (fen, square, description) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 1, "knight on b1"),
+            ("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1", 12, "bishop pinned on the file (none)"),
+            ("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1", 12, "rook pinned on the file (along it only)"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", 36, "pawn with an ep capture"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", 4, "king with both castlings"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", 60, "enemy king (none)"),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let move_names: Vec<String> = position.legal_moves_from(square).into_iter().map(|legal_move| legal_move.to_uci_string()).collect();
+            println!("{0} from {1}: {2:?}", description, square_name(square), move_names);
+        }
+        // And square by square, the moves should add up to exactly the legal moves. 
+        let positions = random_test_positions(1669);
+        let mut mismatches = 0;
+        for position in positions.iter() {
+            let mut by_square: Vec<String> = (0..64i8).flat_map(|square| position.legal_moves_from(square)).map(|legal_move| legal_move.to_uci_string()).collect();
+            let mut all_moves: Vec<String> = position.get_legal_proper_moves().into_iter().map(|legal_move| legal_move.to_uci_string()).collect();
+            by_square.sort();
+            all_moves.sort();
+            if by_square != all_moves {
+                mismatches += 1;
+            }
+        }
+        println!("legal_moves_from over every square against get_legal_proper_moves: {0} positions, {1} mismatches", positions.len(), mismatches);
+    }
+
+    if testing_go_mate {
+        use chess::uci::UciEngine;
+        // Morphy's mate in 2: 1. Ra6 bxa6 2. b7#. "go mate 2" should find it with Ra6 and 
+        // stop there, "go mate 1" should say there's nothing, and a back rank mate in 1 gets 
+        // found by "go mate 3" without deepening any further. 
+        let mut engine = UciEngine::default();
+        for (fen, go_command) in [
+            ("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", "go mate 2"),
+            ("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", "go mate 1"),
+            ("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", "go mate 3"),
+        ] {
+            println!("--- {0}: {1}", fen, go_command);
+            engine.handle_line(&format!("position fen {}", fen));
+            engine.handle_line(go_command);
+            engine.wait_for_search();
+        }
+    }
+
+    if testing_go_nodes {
+        use chess::uci::UciEngine;
+        use chess::implementations::impls_vzero::search_code::*;
+        // With a node limit the search should stop right at it (plus the node that noticed), 
+        // and still come back with a legal move from the last depth that finished. 
+        for (fen, node_limit) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 10_000),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 10_000),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 50),
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let mut context = SearchContext::new(position.get_color(), 0);
+            context.node_limit = Some(node_limit);
+            let best_found = iterative_deepening(&position, 64, None, &mut context, |_depth, _best_move, _score, _nodes| {});
+            let is_legal = best_found.is_some_and(|(best_move, _score)| position.get_legal_proper_moves().iter().any(|legal_move| legal_move.to_uci_string() == best_move.to_uci_string()));
+            println!("{0} with a limit of {1}: {2} nodes, {3:?} legal {4}", fen, node_limit, context.nodes, best_found.map(|(best_move, score)| (best_move.to_uci_string(), score)), is_legal);
+        }
+        let mut engine = UciEngine::default();
+        engine.handle_line("position startpos moves e2e4 e7e5");
+        engine.handle_line("go nodes 10000");
+        engine.wait_for_search();
+    }
+
+    if testing_bitboard_perft {
+        use chess::implementations::impls_v_one::{board_rep::BitboardPosition, movegen as bitboard_movegen};
+        // The perft suite on the bitboard board, against the known totals and against 
+        // UnwrappedFen's, plus a side-by-side walk of both trees a little shallower that 
+        // catches anything the totals could hide. 
+        let perft_suite = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 5, 4865609),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 4, 4085603),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674624),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 4, 422333),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 4, 2103487),
+            ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", 4, 3894594),
+        ];
+        for (fen, depth, expected_total) in perft_suite {
+            let unwrapped = UnwrappedFen::from_fen(fen).unwrap();
+            let side_by_side = bitboard_movegen::compare_with_unwrapped(&unwrapped, depth - 1);
+            let bitboard_start = std::time::Instant::now();
+            let bitboards = BitboardPosition::from_fen(fen).unwrap();
+            let bitboard_total = bitboard_movegen::depth_n_total_perft(bitboards, depth);
+            let bitboard_time = bitboard_start.elapsed();
+            let unwrapped_start = std::time::Instant::now();
+            let unwrapped_total = depth_n_total_perft(unwrapped, depth);
+            let unwrapped_time = unwrapped_start.elapsed();
+            println!("{0} depth {1}: bitboards {2} in {3:?}, UnwrappedFen {4} in {5:?}, expected {6}, correct {7}", 
+                fen, depth, bitboard_total, bitboard_time, unwrapped_total, unwrapped_time, expected_total, bitboard_total == expected_total && unwrapped_total == expected_total);
+            if bitboard_total != expected_total {
+                for (move_made, successors_num) in bitboard_movegen::depth_n_better_perft(bitboards, depth).1 {
+                    println!("    {0} - {1}", move_made.to_uci_string(), successors_num);
+                }
+            }
+            match side_by_side {
+                Ok(leaves) => println!("    side by side to depth {0}: {1} leaves, no differences", depth - 1, leaves),
+                Err(difference) => println!("    side by side to depth {0}: {1}", depth - 1, difference),
+            }
+        }
+    }
+
+    if testing_magic_bitboards {
+        use chess::implementations::impls_v_one::magic_code::*;
+        // Magic lookups against the slow ray-based attacks, on every square with random 
+        // occupancies from nearly empty to nearly full. The lookups don't care about the 
+        // slider's own square or the edges, so those get thrown in too. 
+        let generation_start = std::time::Instant::now();
+        let table_len = magic_tables().table_len();
+        println!("Magics found and tables filled in {0:?}, {1} entries", generation_start.elapsed(), table_len);
+        let mut rng = XorShiftRng::new(1673);
+        let mut checked = 0;
+        let mut mismatches = 0;
+        for _round in 0..200 {
+            let occupancies = [rng.next_u64() & rng.next_u64() & rng.next_u64(), rng.next_u64() & rng.next_u64(), rng.next_u64(), rng.next_u64() | rng.next_u64()];
+            for occupancy in occupancies {
+                for square in 0..64i8 {
+                    checked += 1;
+                    let rook_ok = get_rook_attacks(square, occupancy) == slow_slider_attacks(EnumPiecesUncolored::Rook, square, occupancy);
+                    let bishop_ok = get_bishop_attacks(square, occupancy) == slow_slider_attacks(EnumPiecesUncolored::Bishop, square, occupancy);
+                    let queen_ok = get_queen_attacks(square, occupancy) == slow_slider_attacks(EnumPiecesUncolored::Rook, square, occupancy) | slow_slider_attacks(EnumPiecesUncolored::Bishop, square, occupancy);
+                    if !(rook_ok && bishop_ok && queen_ok) {
+                        mismatches += 1;
+                        if mismatches <= 5 {
+                            println!("Mismatch on square {0} with occupancy {1:#018x}: rook {2}, bishop {3}, queen {4}", square, occupancy, rook_ok, bishop_ok, queen_ok);
+                        }
+                    }
+                }
+            }
+        }
+        println!("Checked {0} (square, occupancy) pairs, {1} mismatches", checked, mismatches);
+        // And roughly what the lookup saves over the rays. 
+        let occupancy = rng.next_u64() & rng.next_u64();
+        let lookup_start = std::time::Instant::now();
+        let lookup_checksum = (0..10_000).fold(0u64, |checksum, index| checksum ^ get_rook_attacks((index % 64) as i8, occupancy.rotate_left(index)));
+        let lookup_time = lookup_start.elapsed();
+        let slow_start = std::time::Instant::now();
+        let slow_checksum = (0..10_000).fold(0u64, |checksum, index| checksum ^ slow_slider_attacks(EnumPiecesUncolored::Rook, (index % 64) as i8, occupancy.rotate_left(index)));
+        let slow_time = slow_start.elapsed();
+        println!("10000 rook lookups: magics {0:?}, rays {1:?}, same answers {2}", lookup_time, slow_time, lookup_checksum == slow_checksum);
+    }
+
+    if testing_perft_suite {
+        // The embedded suite should come out clean. Then the same again from a file with a 
+        // wrong count slipped in, which should be the only mismatch, and a broken line, which 
+        // should be an error naming the line. 
+        let entries = parse_perft_suite(SMALL_PERFT_SUITE).unwrap();
+        let (checked, mismatches) = run_perft_suite(&entries, 4);
+        println!("Embedded suite: {0} positions, {1} counts checked, {2} mismatches", entries.len(), checked, mismatches.len());
+        let suite_path = std::env::temp_dir().join("cladonia_perft_suite_test.epd");
+        std::fs::write(&suite_path, format!("{}8/8/8/8/8/8/8/K6k w - - 0 1 ;D1 3 ;D2 10\n", SMALL_PERFT_SUITE)).unwrap();
+        match run_perft_suite_file(&suite_path, 3) {
+            Err(error) => println!("Suite file: {}", error),
+            Ok((checked, mismatches)) => {
+                println!("Suite file: {0} counts checked, {1} mismatches", checked, mismatches.len());
+                for mismatch in mismatches {
+                    println!("    {}", mismatch);
+                }
+            },
+        }
+        std::fs::write(&suite_path, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20\n\nnot a fen ;D1 20\n").unwrap();
+        println!("Broken suite file: {:?}", run_perft_suite_file(&suite_path, 3).map(|(checked, _mismatches)| checked));
+        let _ = std::fs::remove_file(&suite_path);
+    }
+
+    if testing_castling_order {
+        use chess::implementations::impls_vzero::search_code::*;
+        // Where the king moves land in the search's move order. In the Italian, castling 
+        // should come ahead of every quiet move and the king steps right at the back. With 
+        // the kingside pawns gone, O-O-O keeps its place but O-O drops behind the capture, 
+        // still ahead of the other quiet moves. 
+        for fen in [
+            "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+            "r3k2r/pppppppp/8/8/8/8/PPPPP3/R3K2R w KQkq - 0 1",
+        ] {
+            let position = UnwrappedFen::from_fen(fen).unwrap();
+            let mut ordered_moves = position.get_legal_proper_moves();
+            order_moves(&position, &mut ordered_moves);
+            let move_names: Vec<String> = ordered_moves.iter().map(|legal_move| legal_move.to_uci_string()).collect();
+            let place_of = |move_name: &str| move_names.iter().position(|name| name == move_name);
+            println!("{0}: {1} moves, O-O at {2:?}, O-O-O at {3:?}, Kf1 at {4:?}, Ke2 at {5:?}, shield after O-O {6}", 
+                fen, move_names.len(), place_of("e1g1"), place_of("e1c1"), place_of("e1f1"), place_of("e1e2"), position.has_pawn_shield(EnumColor::White, 6));
+            println!("    {:?}", move_names);
+        }
+    }
+
+    if testing_make_unmake {
+        // Every legal move out of a pile of random positions gets made and taken back, and the 
+        // position (accumulator included) has to come back exactly as it was. Kiwipete gets 
+        // more castling and ep in. 
+        let positions = random_test_positions(1677);
+        let mut checked_moves = 0;
+        let mut mismatches = 0;
+        for position in positions.iter() {
+            let original = format!("{:?}", position);
+            for legal_move in position.get_legal_proper_moves() {
+                checked_moves += 1;
+                let mut unmade = *position;
+                let undo_info = unmade.make_move_with_undo(legal_move);
+                let made = format!("{:?}", unmade);
+                unmade.unmake_move(undo_info);
+                let mut fen_unmade = *position;
+                let fen_undo_info = fen_unmade.make_move_with_fen_undo(legal_move);
+                let fen_made = format!("{:?}", fen_unmade);
+                fen_unmade.unmake_fen_move(fen_undo_info);
+                if made != format!("{:?}", position.after_move(legal_move)) || format!("{:?}", unmade) != original || !unmade.accumulator_matches_board() 
+                    || fen_made != made || format!("{:?}", fen_unmade) != original || !fen_unmade.accumulator_matches_board() {
+                    mismatches += 1;
+                    if mismatches <= 5 {
+                        println!("Mismatch: {0} from {1}", legal_move.to_uci_string(), position.to_fen());
+                    }
+                }
+            }
+        }
+        println!("Make/unmake round trips: {0} moves checked, {1} mismatches", checked_moves, mismatches);
+
+        // The same depth 4 walk of kiwipete three ways: copying the position for every move, 
+        // making and unmaking through FENnec, and with the UnwrappedFen undo. The search keeps 
+        // copying until one of the in-place walks is clearly faster. Both of them have to leave 
+        // kiwipete as they found it. 
+        fn copying_walk(position: &UnwrappedFen, depth: i8) -> usize {
+            match depth <= 0 {
+                true => 1,
+                false => position.get_legal_proper_moves().into_iter().map(|legal_move| copying_walk(&position.after_move(legal_move), depth - 1)).sum(),
+            }
+        }
+        fn in_place_walk(position: &mut UnwrappedFen, depth: i8) -> usize {
+            if depth <= 0 {
+                return 1
+            }
+            let mut leaves = 0;
+            for legal_move in position.get_legal_proper_moves() {
+                let undo_info = position.make_move_with_undo(legal_move);
+                leaves += in_place_walk(position, depth - 1);
+                position.unmake_move(undo_info);
+            }
+            leaves
+        }
+        fn fen_undo_walk(position: &mut UnwrappedFen, depth: i8) -> usize {
+            if depth <= 0 {
+                return 1
+            }
+            let mut leaves = 0;
+            for legal_move in position.get_legal_proper_moves() {
+                let undo_info = position.make_move_with_fen_undo(legal_move);
+                leaves += fen_undo_walk(position, depth - 1);
+                position.unmake_fen_move(undo_info);
+            }
+            leaves
         }
+        let mut kiwipete = UnwrappedFen::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let kiwipete_before = format!("{:?}", kiwipete);
+        let copying_start = std::time::Instant::now();
+        let copying_leaves = copying_walk(&kiwipete, 4);
+        let copying_time = copying_start.elapsed();
+        let in_place_start = std::time::Instant::now();
+        let in_place_leaves = in_place_walk(&mut kiwipete, 4);
+        let in_place_time = in_place_start.elapsed();
+        let fen_undo_start = std::time::Instant::now();
+        let fen_undo_leaves = fen_undo_walk(&mut kiwipete, 4);
+        let fen_undo_time = fen_undo_start.elapsed();
+        println!("Copying walk: {0} leaves in {1:?}", copying_leaves, copying_time);
+        println!("Make/unmake walk: {0} leaves in {1:?}", in_place_leaves, in_place_time);
+        println!("UnwrappedFen undo walk: {0} leaves in {1:?}", fen_undo_leaves, fen_undo_time);
+        println!("Kiwipete unchanged after the walks: {}", format!("{:?}", kiwipete) == kiwipete_before && kiwipete.accumulator_matches_board());
     }
 
     // Perft for all tested positions gives correct totals up to depth 4, and startpos is correct to depth 5. 
@@ -91,6 +91,7 @@ pub(crate) mod searches {
     // Traits for gamestate representaiton types. 
 
     use super::eval_abstracts::Evaluates;
+    use crate::value::MATE_SCORE;
 
     pub(crate) trait UpdatesOnMove: Copy {
         type MoveRep;
@@ -108,6 +109,76 @@ pub(crate) mod searches {
         fn check_remaining_legality(&self, pseudolegal_move: Self::MoveRep) -> bool;
     }
 
+    // The first concrete version of the Searchable idea from the planning comment above: 
+    // the least a game has to provide for a plain negamax to run on it. Moves are generated 
+    // pseudo-legally and then checked, like FENnec does it, since that's the natural split 
+    // for most games. Undo information is whatever the game needs to take a move back; a 
+    // game without a real unmake can just hand back a copy of itself. 
+    pub(crate) trait Searchable: Copy {
+        type MoveRep: Copy;
+        type UndoInfo;
+
+        fn push_pseudolegal_moves(&self, moves: &mut Vec<Self::MoveRep>) -> ();
+        fn is_legal(&self, pseudolegal_move: Self::MoveRep) -> bool;
+        fn make(&mut self, legal_move: Self::MoveRep) -> Self::UndoInfo;
+        fn unmake(&mut self, undo_info: Self::UndoInfo) -> ();
+        // Whether the game is over for the side to move, given its legal moves. 
+        fn game_over(&self, legal_moves: &[Self::MoveRep]) -> Option<TerminalState>;
+    }
+
+    // How a finished game went, from the side to move's perspective. 
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub(crate) enum TerminalState {
+        Lost,
+        Drawn,
+    }
+
+    // Plain full-width negamax over any Searchable. Scores are from the side to move's 
+    // perspective and on the chess search's scale: a loss is -MATE_SCORE plus the plies to 
+    // get there, and draws are 0. Much too slow for real use, but a useful reference for 
+    // the real searches. 
+    pub(crate) fn negamax<G, F>(position: &mut G, depth: i8, ply: i32, evaluate: &F) -> i32
+    where G: Searchable, F: Fn(&G) -> i32 {
+        let mut legal_moves = Vec::new();
+        position.push_pseudolegal_moves(&mut legal_moves);
+        legal_moves.retain(|possible_move| position.is_legal(*possible_move));
+        match position.game_over(&legal_moves) {
+            Some(TerminalState::Lost) => return -MATE_SCORE + ply,
+            Some(TerminalState::Drawn) => return 0,
+            None => {},
+        }
+        if depth <= 0 {
+            return evaluate(position)
+        }
+        let mut best_score = i32::MIN + 1;
+        for legal_move in legal_moves {
+            let undo_info = position.make(legal_move);
+            let score = -negamax(position, depth - 1, ply + 1, evaluate);
+            position.unmake(undo_info);
+            best_score = best_score.max(score);
+        }
+        best_score
+    }
+
+    // The root of the above. None when there are no legal moves. 
+    pub(crate) fn negamax_best_move<G, F>(position: &mut G, depth: i8, evaluate: &F) -> Option<(G::MoveRep, i32)>
+    where G: Searchable, F: Fn(&G) -> i32 {
+        let mut legal_moves = Vec::new();
+        position.push_pseudolegal_moves(&mut legal_moves);
+        legal_moves.retain(|possible_move| position.is_legal(*possible_move));
+        let mut best_found = None;
+        for legal_move in legal_moves {
+            let undo_info = position.make(legal_move);
+            let score = -negamax(position, depth - 1, 1, evaluate);
+            position.unmake(undo_info);
+            match best_found {
+                Some((_best_move, best_score)) if best_score >= score => {},
+                _ => best_found = Some((legal_move, score)),
+            }
+        }
+        best_found
+    }
+
     // Traits for search trees
 
     pub(crate) trait IterativelySearches {
@@ -3,39 +3,24 @@
 // }
 #![allow(dead_code)]
 
+// Mates are counted in plies. Centipawns used to be an i8 too, which can't even hold a rook. 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub(crate) enum OutStyleScore {
     MatingIn(i8),
     MatedIn(i8),
-    Centipawn(i8),
+    Centipawn(i32),
+    Draw,
 }
 
 impl PartialOrd for OutStyleScore {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self {
-            OutStyleScore::MatingIn(n) => {
-                match other {
-                    OutStyleScore::MatingIn(k) => k.partial_cmp(n),
-                    _ => Some(std::cmp::Ordering::Greater),
-                }
-            },
-            OutStyleScore::MatedIn(n) => {
-                match other {
-                    OutStyleScore::MatedIn(k) => n.partial_cmp(k),
-                    _ => Some(std::cmp::Ordering::Less),
-                }
-            },
-            OutStyleScore::Centipawn(n) => {
-                match other {
-                    OutStyleScore::MatingIn(_) => Some(std::cmp::Ordering::Less),
-                    OutStyleScore::MatedIn(_) => Some(std::cmp::Ordering::Greater),
-                    OutStyleScore::Centipawn(k) => n.partial_cmp(k),
-                }
-            },
-        }
+        Some(self.cmp(other))
     }
 }
 
+// A proven draw sits between the negative and positive centipawn scores. It has to compare 
+// unequal to Centipawn(0) to agree with the derived Eq, so it goes just below it: an unresolved 
+// even position at least still has some chances left in it. 
 impl Ord for OutStyleScore {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match self {
@@ -56,12 +41,88 @@ impl Ord for OutStyleScore {
                     OutStyleScore::MatingIn(_) => std::cmp::Ordering::Less,
                     OutStyleScore::MatedIn(_) => std::cmp::Ordering::Greater,
                     OutStyleScore::Centipawn(k) => n.cmp(k),
+                    OutStyleScore::Draw => match *n >= 0 {
+                        true => std::cmp::Ordering::Greater,
+                        false => std::cmp::Ordering::Less,
+                    },
+                }
+            },
+            OutStyleScore::Draw => {
+                match other {
+                    OutStyleScore::MatingIn(_) => std::cmp::Ordering::Less,
+                    OutStyleScore::MatedIn(_) => std::cmp::Ordering::Greater,
+                    OutStyleScore::Centipawn(k) => match *k >= 0 {
+                        true => std::cmp::Ordering::Less,
+                        false => std::cmp::Ordering::Greater,
+                    },
+                    OutStyleScore::Draw => std::cmp::Ordering::Equal,
                 }
             },
         }
     }
 }
 
+// The same score from the other side's perspective, at the same node. Going from a child's 
+// score to its parent's also needs ply_earlier, since the parent is one ply further from 
+// any mate. 
+impl std::ops::Neg for OutStyleScore {
+    type Output = Self;
+    fn neg(self) -> Self {
+        match self {
+            OutStyleScore::MatingIn(n) => OutStyleScore::MatedIn(n),
+            OutStyleScore::MatedIn(n) => OutStyleScore::MatingIn(n),
+            OutStyleScore::Centipawn(n) => OutStyleScore::Centipawn(-n),
+            OutStyleScore::Draw => OutStyleScore::Draw,
+        }
+    }
+}
+
+// For the searches that score in plain i32 centipawns. Mate scores are MATE_SCORE minus the 
+// number of plies to the mate, so shorter mates score higher. Anything past MATE_THRESHOLD in 
+// absolute value is a mate score rather than an eval. 
+pub(crate) const MATE_SCORE: i32 = 1_000_000;
+pub(crate) const MATE_THRESHOLD: i32 = MATE_SCORE - 1_000;
+
+// Centipawns per factor-of-ten change in the odds of winning. This is the usual Elo-style 
+// logistic curve, and the scale is a placeholder until there's data to fit it to. 
+pub(crate) const WIN_PROBABILITY_SCALE: f64 = 400.0;
+
+impl OutStyleScore {
+    // Rough probability that the side this score belongs to goes on to win. Meant for eventual 
+    // WDL output and contempt, so it only needs to be monotone and end up in the right places. 
+    pub(crate) fn win_probability(&self) -> f64 {
+        match self {
+            OutStyleScore::MatingIn(_) => 1.0,
+            OutStyleScore::MatedIn(_) => 0.0,
+            OutStyleScore::Draw => 0.5,
+            OutStyleScore::Centipawn(n) => 1.0 / (1.0 + 10f64.powf(-(*n as f64) / WIN_PROBABILITY_SCALE)),
+        }
+    }
+
+    // What a child's score means one ply up, but still from the child's perspective, so a 
+    // parent's score is -child.ply_earlier(). Mate distances saturate rather than wrap. 
+    pub(crate) fn ply_earlier(self) -> Self {
+        match self {
+            OutStyleScore::MatingIn(n) => OutStyleScore::MatingIn(n.saturating_add(1)),
+            OutStyleScore::MatedIn(n) => OutStyleScore::MatedIn(n.saturating_add(1)),
+            other => other,
+        }
+    }
+
+    // Shifts centipawn scores, for search windows with some slack in them. A draw shifts 
+    // like 0 would. Mates stay put, since no amount of slack makes a mate any less of one. 
+    pub(crate) fn plus_centipawns(self, centipawns: i32) -> Self {
+        match self {
+            OutStyleScore::Centipawn(n) => OutStyleScore::Centipawn(n.saturating_add(centipawns)),
+            OutStyleScore::Draw => match centipawns == 0 {
+                true => OutStyleScore::Draw,
+                false => OutStyleScore::Centipawn(centipawns),
+            },
+            mate => mate,
+        }
+    }
+}
+
 // #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 // pub(crate) struct 
 